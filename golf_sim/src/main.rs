@@ -0,0 +1,516 @@
+//! Runs the game in a terminal.
+//!
+//! The game runs against the emulated calculator of `libnw::host`: its framebuffer
+//! is drawn with truecolor half-block characters, and PC keys are mapped to calculator keys.
+//! Only std and ANSI escape codes are used, so this works over SSH on any Linux machine.
+//!
+//! Run it with `cargo run --release --target <your host target>` from this directory,
+//! and `--help` to list the keys.
+
+use std::{
+    fmt::Write as _,
+    io::{self, Read, Write},
+    process::{self, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use golf::ExitBehaviour;
+use libnw::{
+    display::{Color, SCREEN_HEIGHT, SCREEN_WIDTH},
+    host::{self, HostEvent},
+    keyboard::RawKey,
+};
+
+const HELP: &str = "\
+golf-sim: run the game in a truecolor terminal
+
+Usage: golf-sim [--scale N] [--storage DIR]
+
+  --scale N       draw one character per N×2N pixels (1, 2 or 4, defaults to 2)
+  --storage DIR   keep the saved records in DIR, instead of losing them on exit
+
+Keys:
+  arrows            arrows
+  Enter             OK
+  Backspace, Esc    Back
+  e                 EXE
+  h                 Home
+  o                 On/Off
+  s                 Shift
+  a                 Alpha
+  Delete            Backspace
+  0-9 + - * / ( ) . , ^
+                    the matching calculator key
+  Ctrl-C            quit
+";
+
+/// How long a key stays down after its first character.
+///
+/// Terminals don't report key releases, only characters, and wait a bit before
+/// repeating a held key: this must cover that delay.
+const FIRST_HOLD: Duration = Duration::from_millis(500);
+/// How long a key stays down after a repeated character.
+const REPEAT_HOLD: Duration = Duration::from_millis(100);
+/// The minimum time between two screen refreshes.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(16);
+/// How long an escape byte waits for the rest of a sequence before being the Esc key.
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+fn main() {
+    let mut scale = 2;
+    let mut storage = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n @ (1 | 2 | 4)) => scale = n,
+                _ => fail("--scale expects 1, 2 or 4"),
+            },
+            "--storage" => match args.next() {
+                Some(dir) => storage = Some(dir),
+                None => fail("--storage expects a directory"),
+            },
+            "-h" | "--help" => {
+                print!("{HELP}");
+                return;
+            }
+            _ => fail(&format!("unknown argument {arg:?}")),
+        }
+    }
+
+    host::reset();
+    if let Some(dir) = storage
+        && let Err(e) = host::set_storage_dir(&dir)
+    {
+        fail(&format!("can't use {dir:?} as storage: {e}"));
+    }
+
+    let terminal = Terminal::enter(scale);
+    let mut simulator = Simulator {
+        terminal,
+        keys: Vec::new(),
+        input: spawn_input_reader(),
+        parser: InputParser::default(),
+        last_input: Instant::now(),
+        last_refresh: Instant::now() - REFRESH_INTERVAL,
+    };
+
+    host::set_hook(move |event| simulator.on_event(event));
+
+    let mut behaviour = ExitBehaviour::Restart;
+    while let ExitBehaviour::Restart = behaviour {
+        behaviour = golf::main();
+    }
+    if let ExitBehaviour::Hang = behaviour {
+        loop {
+            libnw::time::msleep(100);
+        }
+    }
+    quit(0);
+}
+
+/// Prints `msg` and exits with an error.
+fn fail(msg: &str) -> ! {
+    eprintln!("golf-sim: {msg}\n\n{HELP}");
+    process::exit(2);
+}
+
+/// Restores the terminal and exits.
+fn quit(code: i32) -> ! {
+    Terminal::restore();
+    process::exit(code);
+}
+
+/// Everything the host hook needs.
+struct Simulator {
+    terminal: Terminal,
+    /// The keys currently down, with when they go up.
+    keys: Vec<(RawKey, Instant)>,
+    input: Receiver<Vec<u8>>,
+    parser: InputParser,
+    /// When bytes were last read from stdin.
+    last_input: Instant,
+    last_refresh: Instant,
+}
+
+impl Simulator {
+    fn on_event(&mut self, event: HostEvent) {
+        match event {
+            HostEvent::Scan => self.update_keys(),
+            HostEvent::Sleep { us } => {
+                self.refresh();
+                thread::sleep(Duration::from_micros(us));
+            }
+            HostEvent::VBlank => self.refresh(),
+        }
+    }
+
+    /// Applies the characters typed since the last call to the emulated keyboard.
+    fn update_keys(&mut self) {
+        let now = Instant::now();
+        let mut inputs = Vec::new();
+        while let Ok(bytes) = self.input.try_recv() {
+            inputs.extend(self.parser.feed(&bytes));
+            self.last_input = now;
+        }
+        if now - self.last_input >= ESCAPE_TIMEOUT {
+            inputs.extend(self.parser.flush());
+        }
+        for input in inputs {
+            let key = match input {
+                Input::Quit => quit(0),
+                Input::Key(key) => key,
+            };
+            match self.keys.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, until)) => *until = now + REPEAT_HOLD,
+                None => self.keys.push((key, now + FIRST_HOLD)),
+            }
+        }
+        self.keys.retain(|(_, until)| *until > now);
+        host::release_all_keys();
+        for (key, _) in &self.keys {
+            host::press_key(*key);
+        }
+    }
+
+    /// Draws the framebuffer, unless it was drawn very recently.
+    fn refresh(&mut self) {
+        if self.last_refresh.elapsed() >= REFRESH_INTERVAL {
+            host::with_framebuffer(|framebuffer| self.terminal.draw(framebuffer));
+            self.last_refresh = Instant::now();
+        }
+    }
+}
+
+/// Something typed on the PC keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Input {
+    Key(RawKey),
+    Quit,
+}
+
+/// Reads stdin on another thread, and sends the bytes as they come.
+fn spawn_input_reader() -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0u8; 64];
+        loop {
+            let len = match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(len) => len,
+            };
+            if sender.send(buf[..len].to_vec()).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// Maps the bytes read from a raw terminal to inputs.
+///
+/// An escape sequence can be split across reads, so an unfinished one is kept until
+/// the next [`InputParser::feed`]. A lone escape byte is the Esc key, but only once
+/// nothing followed it for a while: see [`InputParser::flush`].
+#[derive(Default)]
+struct InputParser {
+    /// The start of an unfinished escape sequence.
+    pending: Vec<u8>,
+}
+
+impl InputParser {
+    /// Parses `bytes`, following what was left from the previous calls.
+    fn feed(&mut self, bytes: &[u8]) -> Vec<Input> {
+        self.pending.extend_from_slice(bytes);
+        let mut inputs = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() {
+            let (len, input) = match parse_one(&self.pending[i..]) {
+                Some(parsed) => parsed,
+                None => break,
+            };
+            match input {
+                Some(Input::Quit) => {
+                    inputs.push(Input::Quit);
+                    self.pending.clear();
+                    return inputs;
+                }
+                Some(input) => inputs.push(input),
+                None => {}
+            }
+            i += len;
+        }
+        self.pending.drain(..i);
+        inputs
+    }
+
+    /// Gives up waiting for the end of an escape sequence.
+    ///
+    /// A lone escape byte is then the Esc key, and an unfinished sequence is dropped.
+    fn flush(&mut self) -> Vec<Input> {
+        let inputs = match self.pending[..] {
+            [0x1b] => vec![Input::Key(RawKey::Back)],
+            _ => Vec::new(),
+        };
+        self.pending.clear();
+        inputs
+    }
+}
+
+/// Parses the input at the start of `bytes`.
+///
+/// Returns how many bytes it takes and the input, if they mean one, or `None` if
+/// `bytes` is the start of an unfinished escape sequence.
+fn parse_one(bytes: &[u8]) -> Option<(usize, Option<Input>)> {
+    let key = match *bytes {
+        [0x03 | 0x04, ..] => return Some((1, Some(Input::Quit))),
+        [0x1b] | [0x1b, b'O'] => return None,
+        [0x1b, b'[', ref rest @ ..] => {
+            // CSI : parameter bytes, intermediate bytes, then a final byte
+            let len = rest.iter().position(|b| !(0x20..0x40).contains(b))?;
+            let (params, end) = rest.split_at(len);
+            if !(0x40..0x7f).contains(&end[0]) {
+                // not a valid sequence, the byte stopping it is parsed on its own
+                return Some((2 + len, None));
+            }
+            let key = match (params, end[0]) {
+                (b"", b'A') => Some(RawKey::Up),
+                (b"", b'B') => Some(RawKey::Down),
+                (b"", b'C') => Some(RawKey::Right),
+                (b"", b'D') => Some(RawKey::Left),
+                (b"3", b'~') => Some(RawKey::Backspace),
+                _ => None,
+            };
+            return Some((3 + len, key.map(Input::Key)));
+        }
+        [0x1b, b'O', code, ..] => {
+            let key = match code {
+                b'A' => Some(RawKey::Up),
+                b'B' => Some(RawKey::Down),
+                b'C' => Some(RawKey::Right),
+                b'D' => Some(RawKey::Left),
+                _ => None,
+            };
+            return Some((3, key.map(Input::Key)));
+        }
+        // the byte after a lone escape is parsed on its own
+        [0x1b, ..] => Some(RawKey::Back),
+        [byte, ..] => match byte {
+            b'\r' | b'\n' => Some(RawKey::Ok),
+            0x7f | 0x08 => Some(RawKey::Back),
+            b'e' => Some(RawKey::Exe),
+            b'h' => Some(RawKey::Home),
+            b'o' => Some(RawKey::OnOff),
+            b's' => Some(RawKey::Shift),
+            b'a' => Some(RawKey::Alpha),
+            b'0' => Some(RawKey::Zero),
+            b'1' => Some(RawKey::One),
+            b'2' => Some(RawKey::Two),
+            b'3' => Some(RawKey::Three),
+            b'4' => Some(RawKey::Four),
+            b'5' => Some(RawKey::Five),
+            b'6' => Some(RawKey::Six),
+            b'7' => Some(RawKey::Seven),
+            b'8' => Some(RawKey::Eight),
+            b'9' => Some(RawKey::Nine),
+            b'+' => Some(RawKey::Plus),
+            b'-' => Some(RawKey::Minus),
+            b'*' => Some(RawKey::Multiplication),
+            b'/' => Some(RawKey::Division),
+            b'(' => Some(RawKey::LeftParenthesis),
+            b')' => Some(RawKey::RightParenthesis),
+            b'.' => Some(RawKey::Dot),
+            b',' => Some(RawKey::Comma),
+            b'^' => Some(RawKey::Power),
+            _ => None,
+        },
+        [] => return None,
+    };
+    Some((1, key.map(Input::Key)))
+}
+
+/// The terminal, in raw mode, showing the screen.
+struct Terminal {
+    /// How many pixels wide a character is (it is twice as high).
+    scale: usize,
+    /// The (top, bottom) colors of every character currently displayed.
+    cells: Vec<Option<(Color, Color)>>,
+}
+
+impl Terminal {
+    /// Switches the terminal to raw mode and to the alternate screen.
+    fn enter(scale: usize) -> Self {
+        let raw = Command::new("stty")
+            .args(["raw", "-echo"])
+            .stdin(Stdio::inherit())
+            .status();
+        if !raw.is_ok_and(|status| status.success()) {
+            fail("could not switch the terminal to raw mode (is stdin a terminal ?)");
+        }
+        let default_panic = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            Terminal::restore();
+            default_panic(info);
+        }));
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        let _ = io::stdout().flush();
+        let columns = SCREEN_WIDTH as usize / scale;
+        let rows = SCREEN_HEIGHT as usize / (2 * scale);
+        Self {
+            scale,
+            cells: vec![None; columns * rows],
+        }
+    }
+
+    /// Leaves the alternate screen and raw mode.
+    fn restore() {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = Command::new("stty")
+            .arg("sane")
+            .stdin(Stdio::inherit())
+            .status();
+    }
+
+    /// Draws the characters whose colors changed since the last call.
+    fn draw(&mut self, framebuffer: &[Color]) {
+        let columns = SCREEN_WIDTH as usize / self.scale;
+        let mut out = String::new();
+        let mut cursor = None;
+        let mut colors = None;
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            let (column, row) = (i % columns, i / columns);
+            let top = average(framebuffer, self.scale, column, row * 2);
+            let bottom = average(framebuffer, self.scale, column, row * 2 + 1);
+            if *cell == Some((top, bottom)) {
+                continue;
+            }
+            *cell = Some((top, bottom));
+            if cursor != Some((column, row)) {
+                let _ = write!(out, "\x1b[{};{}H", row + 1, column + 1);
+            }
+            if colors != Some((top, bottom)) {
+                let (tr, tg, tb) = top.separate_rgb();
+                let (br, bg, bb) = bottom.separate_rgb();
+                let _ = write!(out, "\x1b[38;2;{tr};{tg};{tb};48;2;{br};{bg};{bb}m");
+                colors = Some((top, bottom));
+            }
+            out.push('▀');
+            cursor = Some((column + 1, row));
+        }
+        if !out.is_empty() {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(out.as_bytes());
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// The average color of the `scale`×`scale` block of pixels at (`x`, `y`), in blocks.
+fn average(framebuffer: &[Color], scale: usize, x: usize, y: usize) -> Color {
+    let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+    for py in y * scale..(y + 1) * scale {
+        for px in x * scale..(x + 1) * scale {
+            let (pr, pg, pb) = framebuffer[py * SCREEN_WIDTH as usize + px].separate_rgb();
+            r += pr as u32;
+            g += pg as u32;
+            b += pb as u32;
+        }
+    }
+    let n = (scale * scale) as u32;
+    Color::from_rgb((r / n) as u8, (g / n) as u8, (b / n) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[RawKey]) -> Vec<Input> {
+        keys.iter().map(|key| Input::Key(*key)).collect()
+    }
+
+    #[test]
+    fn plain_bytes_are_keys() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            parser.feed(b"5\re\x7fz"),
+            keys(&[RawKey::Five, RawKey::Ok, RawKey::Exe, RawKey::Back])
+        );
+        assert_eq!(parser.flush(), []);
+    }
+
+    #[test]
+    fn arrows_and_delete_are_parsed() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            parser.feed(b"\x1b[A\x1bOB\x1b[C\x1b[D\x1b[3~"),
+            keys(&[
+                RawKey::Up,
+                RawKey::Down,
+                RawKey::Right,
+                RawKey::Left,
+                RawKey::Backspace
+            ])
+        );
+    }
+
+    #[test]
+    fn sequences_split_across_reads_are_joined() {
+        let mut parser = InputParser::default();
+        assert_eq!(parser.feed(b"1\x1b"), keys(&[RawKey::One]));
+        assert_eq!(parser.feed(b"["), []);
+        assert_eq!(parser.feed(b"3"), []);
+        assert_eq!(parser.feed(b"~2"), keys(&[RawKey::Backspace, RawKey::Two]));
+        assert_eq!(parser.feed(b"\x1bO"), []);
+        assert_eq!(parser.feed(b"A"), keys(&[RawKey::Up]));
+    }
+
+    #[test]
+    fn unknown_sequences_are_skipped_whole() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            parser.feed(b"\x1b[1;5C\x1b[15~\x1b[200~h"),
+            keys(&[RawKey::Home])
+        );
+        assert_eq!(parser.feed(b"\x1bOP"), []);
+    }
+
+    #[test]
+    fn a_lone_escape_waits_for_the_flush() {
+        let mut parser = InputParser::default();
+        assert_eq!(parser.feed(b"\x1b"), []);
+        assert_eq!(parser.flush(), keys(&[RawKey::Back]));
+        assert_eq!(parser.flush(), []);
+    }
+
+    #[test]
+    fn the_byte_after_an_escape_is_kept() {
+        let mut parser = InputParser::default();
+        assert_eq!(parser.feed(b"\x1be"), keys(&[RawKey::Back, RawKey::Exe]));
+        assert_eq!(
+            parser.feed(b"\x1b\x1b[A"),
+            keys(&[RawKey::Back, RawKey::Up])
+        );
+    }
+
+    #[test]
+    fn an_unfinished_sequence_is_dropped_on_flush() {
+        let mut parser = InputParser::default();
+        assert_eq!(parser.feed(b"\x1b[1;"), []);
+        assert_eq!(parser.flush(), []);
+        assert_eq!(parser.feed(b"5"), keys(&[RawKey::Five]));
+    }
+
+    #[test]
+    fn ctrl_c_quits_and_drops_the_rest() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            parser.feed(b"1\x032"),
+            [Input::Key(RawKey::One), Input::Quit]
+        );
+        assert_eq!(parser.flush(), []);
+    }
+}
@@ -0,0 +1,142 @@
+//! End-to-end tests: the game is played with scripted keys, from the main menu.
+
+use golf::{
+    PopMessage,
+    controls::{Control, default_controls},
+    game::{game::GameState, game_over::GameOverState},
+    menu::MainMenuState,
+    save::{self, SavedRound},
+};
+use graphmgr::{
+    LifecycleEvent,
+    dialog::DialogState,
+    headless::{HeadlessRunner, InputScript},
+};
+use libnw::{host, keyboard::RawKey};
+
+#[test]
+fn the_course_is_played_in_two_strokes() {
+    host::reset();
+    let script = InputScript::new()
+        // "Play" is the first button of the menu
+        .tap(5, RawKey::Ok)
+        // pull the club to the left, the ball goes right to the end of the first row
+        .hold(30, RawKey::Left, 14)
+        .tap(60, RawKey::Ok)
+        // once the ball has stopped, pull up, the ball goes down into the hole
+        .hold(220, RawKey::Up, 14)
+        .tap(260, RawKey::Ok);
+    let mut runner = HeadlessRunner::<PopMessage>::new(
+        Box::new(MainMenuState::new(default_controls())),
+        script,
+        60,
+    );
+
+    let over = runner.run_until(600, |manager| {
+        manager
+            .top()
+            .is_some_and(|state| state.as_any().is::<GameOverState>())
+    });
+    assert!(over, "the game did not end after {} frames", runner.frame());
+    let game_over = runner.top_as::<GameOverState>().unwrap();
+    assert_eq!(game_over.attempts, 2);
+    assert!(game_over.new_best);
+    assert_eq!(save::best_score(), Some(2));
+}
+
+/// Starts a game, asks to leave it, then picks the choice of the dialog with `keys`.
+///
+/// Returns the runner and the value picked.
+fn leave_dialog(keys: &[RawKey]) -> (HeadlessRunner<PopMessage>, Option<bool>) {
+    host::reset();
+    let mut script = InputScript::new().tap(5, RawKey::Ok).tap(30, RawKey::Back);
+    for (i, &key) in keys.iter().enumerate() {
+        script = script.tap(50 + 10 * i as u64, key);
+    }
+    let mut runner = HeadlessRunner::<PopMessage>::new(
+        Box::new(MainMenuState::new(default_controls())),
+        script,
+        60,
+    );
+    runner.run_frames(45);
+    let choice = runner
+        .top_as::<DialogState<PopMessage, bool>>()
+        .expect("the dialog is not open")
+        .choice();
+    runner.run_frames(105);
+    (runner, choice.get())
+}
+
+#[test]
+fn leaving_a_course_goes_back_to_the_menu() {
+    let (runner, leave) = leave_dialog(&[RawKey::Ok]);
+    assert_eq!(leave, Some(true));
+    assert!(runner.top_as::<MainMenuState>().is_some());
+}
+
+#[test]
+fn staying_on_a_course_keeps_playing() {
+    let (runner, leave) = leave_dialog(&[RawKey::Down, RawKey::Ok]);
+    assert_eq!(leave, Some(false));
+    assert!(runner.top_as::<GameState>().is_some());
+}
+
+#[test]
+fn cancelling_the_dialog_keeps_playing() {
+    let (runner, leave) = leave_dialog(&[RawKey::Back]);
+    assert_eq!(leave, None);
+    assert!(runner.top_as::<GameState>().is_some());
+}
+
+#[test]
+fn the_dialog_follows_the_bindings() {
+    host::reset();
+    let controls = default_controls();
+    controls.borrow_mut().rebind(Control::Confirm, RawKey::Exe);
+    let script = InputScript::new()
+        .tap(5, RawKey::Exe)
+        .tap(30, RawKey::Back)
+        // not bound anymore
+        .tap(50, RawKey::Ok)
+        .tap(70, RawKey::Exe);
+    let mut runner =
+        HeadlessRunner::<PopMessage>::new(Box::new(MainMenuState::new(controls)), script, 60);
+    runner.run_frames(65);
+    assert!(runner.top_as::<DialogState<PopMessage, bool>>().is_some());
+    runner.run_frames(60);
+    assert!(runner.top_as::<MainMenuState>().is_some());
+}
+
+#[test]
+fn home_saves_the_round_to_continue_it_on_the_next_launch() {
+    host::reset();
+    // one shot, then home while the ball rolls
+    let script = InputScript::new()
+        .tap(5, RawKey::Ok)
+        .hold(30, RawKey::Left, 14)
+        .tap(60, RawKey::Ok)
+        .tap(70, RawKey::Home);
+    let mut runner = HeadlessRunner::<PopMessage>::new(
+        Box::new(MainMenuState::new(save::load_controls())),
+        script,
+        60,
+    );
+    assert!(runner.run_until_empty(100));
+    assert_eq!(
+        runner.manager().lifecycle_event(),
+        Some(LifecycleEvent::Exit)
+    );
+    let round = SavedRound::load().expect("the round was not saved");
+    assert_eq!(round.attempts, 1);
+
+    // "Continue" comes first when a round is saved
+    let script = InputScript::new().tap(5, RawKey::Ok);
+    let mut runner = HeadlessRunner::<PopMessage>::new(
+        Box::new(MainMenuState::new(save::load_controls())),
+        script,
+        60,
+    );
+    runner.run_frames(40);
+    assert!(runner.top_as::<GameState>().is_some());
+    assert!(SavedRound::load().is_none());
+}
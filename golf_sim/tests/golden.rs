@@ -0,0 +1,86 @@
+//! Golden-image tests: the game is drawn on the emulated screen and compared against
+//! the reference images of `tests/snapshots`.
+//!
+//! Run with `LIBNW_UPDATE_SNAPSHOTS=1` to accept a new rendering.
+
+use golf::{
+    controls::default_controls,
+    game::{
+        ball::Ball,
+        game::GameState,
+        module::{EmptyModule, Module, SquareEndModule},
+    },
+    utils::vec::Vec2,
+};
+use graphmgr::State;
+use libnw::{
+    display::{self, Color, Rect},
+    host::{self, snapshot::assert_snapshot_rect},
+};
+
+/// Where the modules and the ball are drawn, away from the borders.
+const AREA: Rect = Rect {
+    x: 0,
+    y: 0,
+    width: 160,
+    height: 120,
+};
+/// A camera putting (0, 0) of the world inside `AREA`.
+const CAM_POS: Vec2<f32> = Vec2 { x: -8., y: -6. };
+const UNIT_SIZE: i32 = 3;
+
+fn snapshot_path(name: &str) -> String {
+    format!("{}/tests/snapshots/{name}.ppm", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Clears the emulated screen, with the background of the game.
+fn clear() {
+    host::reset();
+    display::clear_screen(Color::GREEN);
+}
+
+#[test]
+fn empty_module() {
+    clear();
+    EmptyModule::new_rect(Vec2 { x: 0, y: 0 }, (3, 2)).render(&CAM_POS, UNIT_SIZE, AREA);
+    assert_snapshot_rect(snapshot_path("empty_module"), AREA);
+}
+
+#[test]
+fn square_end_module() {
+    clear();
+    SquareEndModule::new_4x4(Vec2 { x: 0, y: 0 }).render(&CAM_POS, UNIT_SIZE, AREA);
+    assert_snapshot_rect(snapshot_path("square_end_module"), AREA);
+}
+
+#[test]
+fn ball() {
+    clear();
+    Ball::new(Vec2 { x: 4., y: 4. }).render(&CAM_POS, UNIT_SIZE, false, AREA);
+    assert_snapshot_rect(snapshot_path("ball"), AREA);
+}
+
+#[test]
+fn ball_aiming() {
+    clear();
+    let mut ball = Ball::new(Vec2 { x: 4., y: 4. });
+    ball.move_launch(Vec2 { x: 1.5, y: -1. });
+    ball.render(&CAM_POS, UNIT_SIZE, true, AREA);
+    assert_snapshot_rect(snapshot_path("ball_aiming"), AREA);
+}
+
+#[test]
+fn game_state() {
+    host::reset();
+    let mut game = GameState::new(
+        default_controls(),
+        vec![
+            Box::new(EmptyModule::new_rect(Vec2 { x: -8, y: -8 }, (8, 4))),
+            Box::new(EmptyModule::new_rect(Vec2 { x: 24, y: -8 }, (2, 7))),
+            Box::new(SquareEndModule::new_4x4(Vec2 { x: 20, y: 20 })),
+        ],
+        Ball::new(Vec2 { x: 0., y: 0. }),
+    );
+    game.render(1.);
+    assert_snapshot_rect(snapshot_path("game_state"), Rect::SCREEN);
+}
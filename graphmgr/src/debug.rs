@@ -0,0 +1,142 @@
+//! An overlay telling how the app performs, drawn by `StateManager` over the states.
+//!
+//! It shows the frame rate (current and average), the longest of the last frames,
+//! the number of states on the stack, the heap usage (if set up with `init_heap!`)
+//! and the battery level.
+//!
+//! # Example :
+//! ```
+//! use graphmgr::StateManager;
+//! use libnw::{input::Chord, keyboard::RawKey};
+//!
+//! let mut state_mgr = StateManager::<()>::new();
+//! // shown from the start, and toggled with shift + var
+//! state_mgr.debug_overlay().set_visible(true);
+//! state_mgr
+//!     .debug_overlay()
+//!     .set_toggle(Some(Chord::new(&[RawKey::Shift, RawKey::Var])));
+//! ```
+
+extern crate alloc;
+
+use alloc::format;
+use libnw::{
+    battery,
+    display::{CHAR_HEIGHT, CHAR_WIDTH, Color, Font, Rect, SCREEN_WIDTH},
+    heap,
+    input::Chord,
+    keyboard::{KeyboardTimedState, RawKey},
+    time::{FrameClock, Instant},
+};
+
+/// The number of characters of the longest line.
+const LINE_LENGTH: u16 = 22;
+/// The number of lines.
+const LINE_COUNT: u16 = 5;
+/// Where the overlay is drawn, in the top-right corner.
+const OVERLAY_BOX: Rect = Rect {
+    x: SCREEN_WIDTH - LINE_LENGTH * CHAR_WIDTH,
+    y: 0,
+    width: LINE_LENGTH * CHAR_WIDTH,
+    height: LINE_COUNT * CHAR_HEIGHT,
+};
+
+/// The debug overlay of a `StateManager`, see `StateManager::debug_overlay`.
+pub struct DebugOverlay {
+    visible: bool,
+    toggle: Option<Chord>,
+    /// Measures the time between two draws, `None` until the first one.
+    frames: Option<FrameClock>,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugOverlay {
+    /// A hidden overlay, toggled with shift + toolbox.
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            toggle: Some(Chord::new(&[RawKey::Shift, RawKey::Toolbox])),
+            frames: None,
+        }
+    }
+
+    /// Checks if the overlay is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_visible(&mut self, visible: bool) {
+        if !visible {
+            // the measures start again when shown
+            self.frames = None;
+        }
+        self.visible = visible;
+    }
+
+    /// The combination of keys showing and hiding the overlay.
+    pub fn toggle(&self) -> Option<Chord> {
+        self.toggle
+    }
+
+    /// Sets the combination of keys showing and hiding the overlay, `None` to disable it.
+    pub fn set_toggle(&mut self, toggle: Option<Chord>) {
+        self.toggle = toggle;
+    }
+
+    /// Shows or hides the overlay if the combination was just pressed.
+    ///
+    /// Returns `true` if it was hidden, the screen below having to be drawn again.
+    pub(crate) fn update(&mut self, keyboard_state: &KeyboardTimedState) -> bool {
+        if !self
+            .toggle
+            .is_some_and(|toggle| toggle.is_just_pressed(keyboard_state))
+        {
+            return false;
+        }
+        self.set_visible(!self.visible);
+        !self.visible
+    }
+
+    /// Measures the frame ending at `now`, then draws the overlay if it is visible.
+    pub(crate) fn draw(&mut self, now: Instant, depth: usize) {
+        if !self.visible {
+            return;
+        }
+        let frames = self
+            .frames
+            .get_or_insert_with(|| FrameClock::starting_at(0, now));
+        let delta = frames.tick_at(now);
+        let current_fps = match delta.is_zero() {
+            true => 0.,
+            false => 1. / delta.as_secs_f32(),
+        };
+        let heap = match heap::stats() {
+            Some(stats) => format!("heap {}/{} B", stats.used, stats.size),
+            None => "heap ?".into(),
+        };
+        let charging = if battery::is_charging() { " +" } else { "" };
+        let lines = [
+            format!("fps {current_fps:.0} (avg {:.1})", frames.fps()),
+            format!("worst {} ms", frames.max_frame_time().as_millis()),
+            format!("states {depth}"),
+            heap,
+            format!("battery {}%{charging}", battery::get_level()),
+        ];
+        OVERLAY_BOX.fill(Color::BLACK);
+        for (i, line) in lines.iter().enumerate() {
+            Font::Small.draw(
+                line,
+                OVERLAY_BOX.x,
+                OVERLAY_BOX.y + i as u16 * CHAR_HEIGHT,
+                Color::WHITE,
+                Some(Color::BLACK),
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! A dialog asking to pick one of a few choices, drawn over the state below.
+//!
+//! # Example :
+//! ```
+//! use graphmgr::{StackAction, dialog::{DialogChoice, DialogState}};
+//!
+//! // in `State::update`, the message is the one the state below gets anyway
+//! let dialog = DialogState::new(
+//!     "Leave ?",
+//!     "The current game will be lost.",
+//!     &[("Leave", true), ("Stay", false)],
+//!     (),
+//! );
+//! let leave: DialogChoice<bool> = dialog.choice();
+//! let action: StackAction = StackAction::Push(Box::new(dialog));
+//!
+//! // in `State::resume`
+//! if leave.get() == Some(true) {
+//!     // leave
+//! }
+//! ```
+//! The state pops itself with its message when a choice is picked with OK / EXE,
+//! or on back. The value of the choice is then read from the [`DialogChoice`] kept
+//! by the state below, `None` meaning the dialog was cancelled.
+
+extern crate alloc;
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use libnw::{display::SCREEN_WIDTH, keyboard::KeyboardTimedState};
+
+use crate::{
+    FrameContext, StackAction, State,
+    ui::{Button, Event, Label, List, PADDING, Panel, Theme, Ui},
+};
+
+/// The widest a dialog can be, the state below staying visible around.
+const DIALOG_WIDTH: u16 = SCREEN_WIDTH - 60;
+
+/// The value picked in a [`DialogState`], shared with the state that pushed it.
+///
+/// It holds `None` until a choice is picked, and if the dialog was cancelled.
+#[derive(Debug)]
+pub struct DialogChoice<T>(Rc<RefCell<Option<T>>>);
+
+impl<T> DialogChoice<T> {
+    /// Takes the value picked, leaving `None`.
+    pub fn take(&self) -> Option<T> {
+        self.0.borrow_mut().take()
+    }
+}
+
+impl<T: Clone> DialogChoice<T> {
+    /// The value picked, `None` if there is none (yet).
+    pub fn get(&self) -> Option<T> {
+        self.0.borrow().clone()
+    }
+}
+
+impl<T> Clone for DialogChoice<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for DialogChoice<T> {
+    fn default() -> Self {
+        Self(Rc::new(RefCell::new(None)))
+    }
+}
+
+/// A state showing a title, some text and the choices below, picked with the arrows.
+pub struct DialogState<M, T> {
+    /// Emits the value of the choice, `None` on back.
+    ui: Ui<Option<T>>,
+    choice: DialogChoice<T>,
+    /// The message popped with, taken when the dialog closes.
+    pop_message: Option<M>,
+}
+
+impl<M, T: Clone + 'static> DialogState<M, T> {
+    /// Creates a dialog centered on screen, `body` being wrapped to its width.
+    ///
+    /// Each choice is a label and the value it stands for. The dialog pops itself with
+    /// `pop_message`, the value being read with [`Self::choice`].
+    pub fn new(title: &str, body: &str, choices: &[(&str, T)], pop_message: M) -> Self {
+        // the panel adds its frame and its padding around the list
+        let mut list = List::new().with(Label::new(body).max_width(DIALOG_WIDTH - 2 * PADDING - 2));
+        for (label, value) in choices {
+            list.push(Button::new(label, Some(value.clone())));
+        }
+        Self {
+            ui: Ui::centered(Panel::new(title, list))
+                .on_back(None)
+                .without_vblank(),
+            choice: DialogChoice::default(),
+            pop_message: Some(pop_message),
+        }
+    }
+
+    /// Sets the colors and the font of the dialog.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.ui = self.ui.with_theme(theme);
+        self
+    }
+
+    /// Reads the events with `events` instead of the default keys, see [`Ui::with_events`].
+    pub fn with_events(
+        mut self,
+        events: impl Fn(&KeyboardTimedState) -> Option<Event> + 'static,
+    ) -> Self {
+        self.ui = self.ui.with_events(events);
+        self
+    }
+
+    /// A handle to the value that will be picked, to keep until the dialog is popped.
+    pub fn choice(&self) -> DialogChoice<T> {
+        self.choice.clone()
+    }
+}
+
+impl<M: 'static, T: Clone + 'static> State<M> for DialogState<M, T> {
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn update(
+        &mut self,
+        keyboard_state: &KeyboardTimedState,
+        _ctx: &FrameContext,
+    ) -> StackAction<M> {
+        let Some(choice) = self.ui.update(keyboard_state) else {
+            return StackAction::Nop;
+        };
+        *self.choice.0.borrow_mut() = choice;
+        match self.pop_message.take() {
+            Some(pop_message) => StackAction::Pop(pop_message),
+            None => StackAction::Nop,
+        }
+    }
+
+    fn render(&mut self, _alpha: f32) {
+        // the state below is drawn every frame, and may have drawn over the dialog
+        self.ui.invalidate();
+        self.ui.render();
+    }
+}
@@ -0,0 +1,326 @@
+//! Run a `StateManager` frame by frame, without a calculator.
+//!
+//! Only available with the `host` feature: the keyboard is driven by an `InputScript`,
+//! and the clock is the emulated one of `libnw::host` (or any other `Clock`).
+//!
+//! # Example :
+//! ```
+//! use graphmgr::{FrameContext, StackAction, State, headless::{HeadlessRunner, InputScript}};
+//! use libnw::keyboard::{KeyboardTimedState, RawKey};
+//!
+//! /// Counts the presses on right, leaves on OK.
+//! struct Counter(u32);
+//!
+//! impl State for Counter {
+//!     fn update(&mut self, keyboard: &KeyboardTimedState, _ctx: &FrameContext) -> StackAction {
+//!         let pressed = keyboard.just_pressed_state();
+//!         if pressed.is_pressed(RawKey::Right) {
+//!             self.0 += 1;
+//!         }
+//!         match pressed.is_pressed(RawKey::Ok) {
+//!             true => StackAction::Pop(()),
+//!             false => StackAction::Nop,
+//!         }
+//!     }
+//!
+//!     fn render(&mut self, _alpha: f32) {}
+//! }
+//!
+//! libnw::host::reset();
+//! let script = InputScript::new()
+//!     .tap(10, RawKey::Right)
+//!     .tap(20, RawKey::Right)
+//!     .tap(40, RawKey::Ok);
+//! let mut runner = HeadlessRunner::new(Box::new(Counter(0)), script, 60);
+//! assert!(runner.run_until(1000, |manager| {
+//!     let counter = manager.top().and_then(|state| state.as_any().downcast_ref::<Counter>());
+//!     counter.is_some_and(|counter| counter.0 == 2)
+//! }));
+//! assert!(runner.run_until_empty(1000));
+//! ```
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use libnw::{
+    host,
+    keyboard::{InputSource, KeyboardState, KeyboardTimedState, RawKey},
+    time::{Clock, Duration, EadkClock},
+};
+
+use crate::{FrameContext, State, StateManager};
+
+/// Something to do with a key at a given frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    /// The key goes down and stays down.
+    Press(RawKey),
+    /// The key goes up.
+    Release(RawKey),
+}
+
+/// A timeline of key presses and releases, indexed by frame.
+///
+/// Events are applied at the very beginning of their frame, before the keyboard is read,
+/// so a key pressed at frame `n` is seen as just pressed by the update of frame `n`.
+#[derive(Clone, Default, Debug)]
+pub struct InputScript {
+    /// (frame, event), sorted by frame.
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl InputScript {
+    /// Creates an empty script.
+    pub fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    /// Adds an event at `frame`.
+    ///
+    /// Events of the same frame are applied in the order they were added.
+    pub fn event(mut self, frame: u64, event: InputEvent) -> Self {
+        let index = self.events.partition_point(|(f, _)| *f <= frame);
+        self.events.insert(index, (frame, event));
+        self
+    }
+
+    /// Presses `key` at `frame`, and keeps it down.
+    pub fn press(self, frame: u64, key: RawKey) -> Self {
+        self.event(frame, InputEvent::Press(key))
+    }
+
+    /// Releases `key` at `frame`.
+    pub fn release(self, frame: u64, key: RawKey) -> Self {
+        self.event(frame, InputEvent::Release(key))
+    }
+
+    /// Holds `key` down for `frames` frames, starting at `frame`.
+    pub fn hold(self, frame: u64, key: RawKey, frames: u64) -> Self {
+        self.press(frame, key).release(frame + frames.max(1), key)
+    }
+
+    /// Presses `key` at `frame` and releases it on the next one.
+    pub fn tap(self, frame: u64, key: RawKey) -> Self {
+        self.hold(frame, key, 1)
+    }
+
+    /// The frame of the last event, if any.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.events.last().map(|(frame, _)| *frame)
+    }
+
+    /// The events of `frame`.
+    fn events_at(&self, frame: u64) -> impl Iterator<Item = InputEvent> + '_ {
+        let start = self.events.partition_point(|(f, _)| *f < frame);
+        self.events[start..]
+            .iter()
+            .take_while(move |(f, _)| *f == frame)
+            .map(|(_, event)| *event)
+    }
+}
+
+/// Plays an `InputScript`, the input source of a `HeadlessRunner`.
+///
+/// The keys are also set on the emulated keyboard, for the code scanning it directly.
+#[derive(Clone)]
+pub struct ScriptedInput {
+    script: InputScript,
+    state: KeyboardState,
+}
+
+impl ScriptedInput {
+    /// Creates a source with no key pressed, until `play_frame` is called.
+    pub fn new(script: InputScript) -> Self {
+        Self {
+            script,
+            state: KeyboardState(0),
+        }
+    }
+
+    /// Applies the events of `frame`.
+    pub fn play_frame(&mut self, frame: u64) {
+        for event in self.script.events_at(frame) {
+            match event {
+                InputEvent::Press(key) => self.state.0 |= 1 << key as u64,
+                InputEvent::Release(key) => self.state.0 &= !(1 << key as u64),
+            }
+        }
+        host::set_keyboard_state(self.state);
+    }
+
+    /// The script being played.
+    pub fn script(&self) -> &InputScript {
+        &self.script
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn scan(&mut self) -> KeyboardState {
+        self.state
+    }
+}
+
+/// Drives a `StateManager` from an `InputScript`, frame by frame.
+///
+/// The manager reads the keyboard from a `ScriptedInput` and the time from `C`, the
+/// emulated clock of `libnw::host` by default. Each frame, the script events of that frame
+/// are applied, the keyboard is fetched, the manager runs one `step`, and the clock
+/// sleeps for one frame.
+pub struct HeadlessRunner<M = (), C = EadkClock> {
+    manager: StateManager<M, ScriptedInput, C>,
+    keyboard: KeyboardTimedState,
+    frame: u64,
+    dt: Duration,
+}
+
+impl<M: 'static> HeadlessRunner<M> {
+    /// Creates a runner on the emulated clock, and pushes the initial state.
+    ///
+    /// `fps` sets how much the clock moves forward at each frame, see `FrameContext::dt_for`.
+    pub fn new(initial_state: Box<dyn State<M>>, script: InputScript, fps: u64) -> Self {
+        Self::with_clock(initial_state, script, fps, EadkClock)
+    }
+}
+
+impl<M: 'static, C: Clock> HeadlessRunner<M, C> {
+    /// Creates a runner on `clock`, and pushes the initial state.
+    pub fn with_clock(
+        initial_state: Box<dyn State<M>>,
+        script: InputScript,
+        fps: u64,
+        clock: C,
+    ) -> Self {
+        let mut input = ScriptedInput::new(script);
+        let keyboard = KeyboardTimedState::from_source(&mut input, &clock);
+        let mut manager = StateManager::with_sources(input, clock);
+        manager.start(initial_state);
+        Self {
+            manager,
+            keyboard,
+            frame: 0,
+            dt: FrameContext::dt_for(fps),
+        }
+    }
+
+    /// Runs one frame.
+    ///
+    /// Returns `false` if there was nothing to run (the stack is empty).
+    pub fn step(&mut self) -> bool {
+        if self.manager.is_empty() {
+            return false;
+        }
+        self.manager.input_mut().play_frame(self.frame);
+        self.manager.fetch_input(&mut self.keyboard);
+        self.manager
+            .step(&self.keyboard, &FrameContext::new(self.dt, self.frame));
+        self.manager.clock_mut().sleep(self.dt);
+        self.frame += 1;
+        true
+    }
+
+    /// Runs `frames` frames, or less if the stack gets empty.
+    ///
+    /// Returns `false` if the stack got empty.
+    pub fn run_frames(&mut self, frames: u64) -> bool {
+        for _ in 0..frames {
+            if !self.step() {
+                return false;
+            }
+        }
+        !self.manager.is_empty()
+    }
+
+    /// Runs until `condition` is true, checking it before every frame.
+    ///
+    /// Gives up after `max_frames` frames or when the stack gets empty,
+    /// and returns whether `condition` was met.
+    pub fn run_until(
+        &mut self,
+        max_frames: u64,
+        mut condition: impl FnMut(&StateManager<M, ScriptedInput, C>) -> bool,
+    ) -> bool {
+        for _ in 0..max_frames {
+            if condition(&self.manager) {
+                return true;
+            }
+            if !self.step() {
+                break;
+            }
+        }
+        condition(&self.manager)
+    }
+
+    /// Runs until the stack gets empty, for at most `max_frames` frames.
+    ///
+    /// Returns `true` if the stack got empty.
+    pub fn run_until_empty(&mut self, max_frames: u64) -> bool {
+        self.run_until(max_frames, |manager| manager.is_empty())
+    }
+
+    /// The number of frames run so far, i.e. the frame about to run.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The driven manager.
+    pub fn manager(&self) -> &StateManager<M, ScriptedInput, C> {
+        &self.manager
+    }
+
+    /// The state on top of the stack, if it is a `S`.
+    pub fn top_as<S: State<M>>(&self) -> Option<&S> {
+        self.manager.top()?.as_any().downcast_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use libnw::time::ManualClock;
+
+    use super::*;
+    use crate::StackAction;
+
+    /// Writes down the frames OK was pressed at.
+    struct Recorder(Vec<u64>);
+
+    impl State for Recorder {
+        fn update(&mut self, keyboard: &KeyboardTimedState, ctx: &FrameContext) -> StackAction {
+            if keyboard.is_key_just_pressed(RawKey::Ok) {
+                self.0.push(ctx.frame);
+            }
+            StackAction::Nop
+        }
+
+        fn render(&mut self, _alpha: f32) {}
+    }
+
+    #[test]
+    fn the_script_is_played_on_its_frames() {
+        host::reset();
+        let script = InputScript::new().tap(3, RawKey::Ok).hold(6, RawKey::Ok, 3);
+        let mut runner = HeadlessRunner::new(Box::new(Recorder(Vec::new())), script, 50);
+        runner.run_frames(12);
+        assert_eq!(runner.top_as::<Recorder>().unwrap().0, [3, 6]);
+        assert_eq!(host::time_us(), 12 * 20_000);
+        // the emulated keyboard follows the script
+        assert!(!libnw::keyboard::is_pressed(RawKey::Ok));
+    }
+
+    #[test]
+    fn a_virtual_clock_leaves_the_emulated_one_alone() {
+        host::reset();
+        let script = InputScript::new().tap(2, RawKey::Ok);
+        let mut runner = HeadlessRunner::with_clock(
+            Box::new(Recorder(Vec::new())),
+            script,
+            0,
+            ManualClock::new(),
+        );
+        runner.run_frames(4);
+        assert_eq!(runner.top_as::<Recorder>().unwrap().0, vec![2]);
+        // an update per second at 0 fps
+        assert_eq!(runner.manager().clock().now().as_millis(), 4000);
+        assert_eq!(host::time_us(), 0);
+    }
+}
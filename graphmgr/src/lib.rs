@@ -2,176 +2,574 @@
 
 extern crate alloc;
 
-use alloc::{boxed::Box, vec::Vec};
-use libnw::{keyboard::KeyboardTimedState, time};
+pub mod debug;
+pub mod dialog;
+#[cfg(feature = "host")]
+pub mod headless;
+pub mod text_input;
+pub mod transition;
+pub mod ui;
+
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::{
+    any::{Any, TypeId},
+    mem,
+};
+use debug::DebugOverlay;
+use libnw::{
+    keyboard::{EadkInput, InputSource, KeyboardTimedState, RawKey},
+    time::{Clock, Duration, EadkClock, FrameClock},
+};
+use transition::Transition;
 
 /// Represents an action to apply on the states stack.
 ///
 /// `M` is a pop message (transferred to the state below).
 ///
 /// Should only be used with `StateManager`.
+///
+/// The actions returned by the callbacks of the states (`create`, `pause`, `resume`
+/// and `quit`) are queued, and applied in order once the current action is done.
 pub enum StackAction<M = ()> {
+    /// Removes the top state, the message is given to the one below.
     Pop(M),
+    /// Removes the `n` top states, the message is given to the one below them.
+    PopN(usize, M),
+    /// Removes the states above the topmost one of this type, which gets the message.
+    ///
+    /// If that state is already on top, nothing is removed and it still gets the message.
+    /// If there is no such state, nothing happens and the message is dropped.
+    /// See `StackAction::pop_to`.
+    PopTo(TypeId, M),
+    /// Adds a state on top, pausing the current one.
     Push(Box<dyn State<M>>),
+    /// Replaces the top state.
     Replace(Box<dyn State<M>>),
+    /// Removes every state, then adds this one.
+    ClearAndPush(Box<dyn State<M>>),
+    /// Removes every state, ending the app.
+    ///
+    /// The actions still queued are dropped.
+    Exit,
+    /// Plays a transition, the action being applied once the screen is covered.
+    Transition(Transition, Box<StackAction<M>>),
     Nop,
 }
 
+impl<M> StackAction<M> {
+    /// Removes the states above the topmost one of type `S`, which gets `msg`.
+    ///
+    /// See `StackAction::PopTo` for the cases where nothing is removed.
+    pub fn pop_to<S: State<M>>(msg: M) -> Self {
+        Self::PopTo(TypeId::of::<S>(), msg)
+    }
+
+    /// Plays `transition` around the action.
+    ///
+    /// # Example :
+    /// ```
+    /// use graphmgr::{StackAction, transition::Transition};
+    /// use libnw::display::Color;
+    ///
+    /// let action: StackAction =
+    ///     StackAction::Pop(()).with_transition(Transition::fade(Color::BLACK, 20));
+    /// ```
+    pub fn with_transition(self, transition: Transition) -> Self {
+        Self::Transition(transition, Box::new(self))
+    }
+}
+
+/// The timing of an update, given to `State::update`.
+///
+/// Updates run at a fixed rate, so `dt` is always the same, even if the rendering is slow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameContext {
+    /// The time simulated by each update.
+    pub dt: Duration,
+    /// The index of this update, from 0.
+    pub frame: u64,
+    /// The time simulated since the start, before this update.
+    pub elapsed: Duration,
+}
+
+impl FrameContext {
+    /// The context of the update number `frame`, each update simulating `dt`.
+    pub fn new(dt: Duration, frame: u64) -> Self {
+        Self {
+            dt,
+            frame,
+            elapsed: dt * frame as u32,
+        }
+    }
+
+    /// The time simulated by each update at `fps` updates per second.
+    ///
+    /// `fps` is clamped between 1 and `MAX_FPS`, the clocks counting in milliseconds.
+    pub fn dt_for(fps: u64) -> Duration {
+        Duration::from_millis(1000 / fps.clamp(1, MAX_FPS))
+    }
+
+    /// The time simulated by each update in seconds.
+    pub fn dt_secs(&self) -> f32 {
+        self.dt.as_secs_f32()
+    }
+}
+
+/// A request of the system to leave the app, given to the states by `State::lifecycle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// Home was pressed, going back to the apps of the calculator.
+    Exit,
+    /// On/Off was pressed, the calculator is about to be turned off.
+    Suspend,
+}
+
+impl LifecycleEvent {
+    /// The request made by the keys just pressed, if any.
+    pub fn from_keyboard(keyboard_state: &KeyboardTimedState) -> Option<Self> {
+        if keyboard_state.is_key_just_pressed(RawKey::Home) {
+            Some(Self::Exit)
+        } else if keyboard_state.is_key_just_pressed(RawKey::OnOff) {
+            Some(Self::Suspend)
+        } else {
+            None
+        }
+    }
+}
+
+/// The highest update rate, each update simulating at least a millisecond.
+pub const MAX_FPS: u64 = 1000;
+
+/// Above this frame duration, the updates are late and slow down the game
+/// instead of trying to catch up forever (unless a single update lasts longer).
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
 /// The state manager.
 ///
 /// # Example :
-/// (too basic, todo : make it better)
 /// ```
+/// use graphmgr::{FrameContext, StackAction, State, StateManager};
+/// use libnw::keyboard::KeyboardTimedState;
+///
+/// /// Leaves after a few updates.
+/// struct Countdown(u8);
+///
+/// impl State for Countdown {
+///     fn update(&mut self, _keyboard: &KeyboardTimedState, _ctx: &FrameContext) -> StackAction {
+///         self.0 -= 1;
+///         match self.0 {
+///             0 => StackAction::Pop(()),
+///             _ => StackAction::Nop,
+///         }
+///     }
+///
+///     fn render(&mut self, _alpha: f32) {}
+/// }
+///
 /// let mut state_mgr = StateManager::<()>::new();
-/// state_mgr.run(Box::new(MyState::new()));
+/// state_mgr.run(Box::new(Countdown(3)), 60);
 /// ```
-pub struct StateManager<M = ()> {
+///
+/// `run` reads the keyboard from `I` and paces frames with `C`, the ones of the
+/// calculator by default. Other sources (a recording, a virtual clock...) are given
+/// with `StateManager::with_sources`.
+pub struct StateManager<M = (), I = EadkInput, C = EadkClock> {
     stack: Vec<Box<dyn State<M>>>,
+    transition: Option<Playing<M>>,
+    input: I,
+    clock: C,
+    debug: DebugOverlay,
+    /// The states must draw everything on the next render.
+    repaint: bool,
+    /// The request that ended the app, if any.
+    lifecycle_event: Option<LifecycleEvent>,
+}
+
+/// A transition being played.
+struct Playing<M> {
+    transition: Transition,
+    /// The action to apply once the screen is covered.
+    action: Option<StackAction<M>>,
+    /// The number of updates played.
+    frame: u16,
+    /// How much the screen was covered by the last render, during the first half.
+    covered: f32,
 }
 
-impl<M> StateManager<M> {
-    /// Creates a new instance.
+impl<M: 'static> StateManager<M> {
+    /// Creates a new instance, using the keyboard and the clock of the calculator.
     pub fn new() -> Self {
-        Self { stack: Vec::new() }
+        Self::with_sources(EadkInput, EadkClock)
+    }
+}
+
+impl<M: 'static, I: InputSource, C: Clock> StateManager<M, I, C> {
+    /// Creates a new instance, reading the keyboard from `input` and the time from `clock`.
+    ///
+    /// # Example :
+    /// ```
+    /// use graphmgr::{FrameContext, StackAction, State, StateManager};
+    /// use libnw::{
+    ///     keyboard::{KeyboardState, KeyboardTimedState, RawKey},
+    ///     time::ManualClock,
+    /// };
+    ///
+    /// /// Leaves when OK is pressed.
+    /// struct WaitOk;
+    ///
+    /// impl State for WaitOk {
+    ///     fn update(
+    ///         &mut self,
+    ///         keyboard: &KeyboardTimedState,
+    ///         _ctx: &FrameContext,
+    ///     ) -> StackAction {
+    ///         match keyboard.is_key_just_pressed(RawKey::Ok) {
+    ///             true => StackAction::Pop(()),
+    ///             false => StackAction::Nop,
+    ///         }
+    ///     }
+    ///
+    ///     fn render(&mut self, _alpha: f32) {}
+    /// }
+    ///
+    /// let mut recorded_states = vec![KeyboardState(0); 5];
+    /// recorded_states.push(KeyboardState(1 << RawKey::Ok as u64));
+    /// // replays the recording as fast as possible
+    /// let mut recording = recorded_states.into_iter();
+    /// let input = move || recording.next().unwrap_or(KeyboardState(0));
+    /// let mut state_mgr = StateManager::with_sources(input, ManualClock::new());
+    /// state_mgr.run(Box::new(WaitOk), 60);
+    /// ```
+    pub fn with_sources(input: I, clock: C) -> Self {
+        Self {
+            stack: Vec::new(),
+            transition: None,
+            input,
+            clock,
+            debug: DebugOverlay::new(),
+            repaint: false,
+            lifecycle_event: None,
+        }
+    }
+
+    /// The debug overlay, drawn over the states.
+    pub fn debug_overlay(&mut self) -> &mut DebugOverlay {
+        &mut self.debug
+    }
+
+    /// The clock the frames are paced with.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// The clock the frames are paced with, to move a virtual one forward.
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    /// The source the keyboard is read from.
+    pub fn input_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Reads the keyboard from the input source into `keyboard_state`, at the time of the clock.
+    ///
+    /// `run` does it before each update, use this with `step` to drive the manager yourself.
+    pub fn fetch_input(&mut self, keyboard_state: &mut KeyboardTimedState) {
+        keyboard_state.fetch_from(&mut self.input, &self.clock);
     }
 
     // should i make these methods public ???
 
-    fn push_to_stack(&mut self, mut state: Box<dyn State<M>>) {
-        if let Some(f) = self.stack.last_mut() {
-            match f.pause() {
-                StackAction::Pop(msg) => {
-                    self.pop_from_stack(msg);
+    /// Applies an action, then the ones returned by the callbacks it calls, in order.
+    fn apply(&mut self, action: StackAction<M>) {
+        let mut queue = VecDeque::from([action]);
+        while let Some(action) = queue.pop_front() {
+            match action {
+                StackAction::Pop(msg) => self.pop(1, msg, &mut queue),
+                StackAction::PopN(n, msg) => self.pop(n, msg, &mut queue),
+                StackAction::PopTo(type_id, msg) => {
+                    let target = self
+                        .stack
+                        .iter()
+                        .rposition(|state| state.as_ref().as_any().type_id() == type_id);
+                    match target {
+                        // nothing to remove, the top state is still told
+                        Some(index) if index + 1 == self.stack.len() => {
+                            queue.push_back(self.stack[index].resume(msg));
+                        }
+                        Some(index) => self.pop(self.stack.len() - 1 - index, msg, &mut queue),
+                        // no state to give the message to
+                        None => (),
+                    }
                 }
                 StackAction::Push(state) => {
-                    self.push_to_stack(state);
+                    if let Some(f) = self.stack.last_mut() {
+                        queue.push_back(f.pause());
+                    }
+                    self.push(state, &mut queue);
                 }
                 StackAction::Replace(state) => {
-                    self.replace_top_stack(state);
+                    self.remove(1, &mut queue);
+                    self.push(state, &mut queue);
                 }
+                StackAction::ClearAndPush(state) => {
+                    self.remove(self.stack.len(), &mut queue);
+                    self.push(state, &mut queue);
+                }
+                StackAction::Exit => {
+                    self.remove(self.stack.len(), &mut queue);
+                    queue.clear();
+                }
+                StackAction::Transition(transition, action) => match self.transition {
+                    // the screen is already covered, or about to be
+                    Some(_) => queue.push_front(*action),
+                    None => {
+                        self.transition = Some(Playing {
+                            transition,
+                            action: Some(*action),
+                            frame: 0,
+                            covered: 0.,
+                        })
+                    }
+                },
                 StackAction::Nop => (),
             }
         }
-        match state.create() {
-            StackAction::Pop(msg) => {
-                self.pop_from_stack(msg);
-            }
-            StackAction::Push(state) => {
-                self.push_to_stack(state);
-            }
-            StackAction::Replace(state) => {
-                self.replace_top_stack(state);
-            }
-            StackAction::Nop => (),
-        }
+    }
+
+    /// Creates a state and adds it on top.
+    fn push(&mut self, mut state: Box<dyn State<M>>, queue: &mut VecDeque<StackAction<M>>) {
+        queue.push_back(state.create());
         self.stack.push(state);
     }
 
-    fn pop_from_stack(&mut self, msg: M) -> Option<Box<dyn State<M>>> {
-        let mut poped_frame = self.stack.pop();
-        match &mut poped_frame {
-            Some(f) => match f.quit() {
-                StackAction::Pop(msg) => {
-                    self.pop_from_stack(msg);
-                }
-                StackAction::Push(state) => {
-                    self.push_to_stack(state);
-                }
-                StackAction::Replace(state) => {
-                    self.replace_top_stack(state);
-                }
-                StackAction::Nop => (),
-            },
-            None => return None,
+    /// Removes the `n` top states (from the top one), without resuming the one below.
+    fn remove(&mut self, n: usize, queue: &mut VecDeque<StackAction<M>>) {
+        for _ in 0..n {
+            let Some(mut f) = self.stack.pop() else {
+                return;
+            };
+            queue.push_back(f.quit());
+        }
+    }
+
+    /// Removes the `n` top states, then resumes the one below with `msg`.
+    fn pop(&mut self, n: usize, msg: M, queue: &mut VecDeque<StackAction<M>>) {
+        if n == 0 {
+            return;
         }
+        self.remove(n, queue);
         if let Some(f) = self.stack.last_mut() {
-            match f.resume(msg) {
-                StackAction::Pop(msg) => {
-                    self.pop_from_stack(msg);
-                }
-                StackAction::Push(state) => {
-                    self.push_to_stack(state);
-                }
-                StackAction::Replace(state) => {
-                    self.replace_top_stack(state);
-                }
-                StackAction::Nop => (),
-            }
+            queue.push_back(f.resume(msg));
         }
-        poped_frame
     }
 
-    fn replace_top_stack(&mut self, mut state: Box<dyn State<M>>) -> Option<Box<dyn State<M>>> {
-        let mut poped_frame = self.stack.pop();
-        if let Some(f) = &mut poped_frame {
-            match f.quit() {
-                StackAction::Pop(msg) => {
-                    self.pop_from_stack(msg);
-                }
-                StackAction::Push(state) => {
-                    self.push_to_stack(state);
+    // --------
+
+    /// Gives `event` to every state, from the top one, then removes them all.
+    ///
+    /// This is done by `update` when home or on/off is pressed.
+    pub fn request_exit(&mut self, event: LifecycleEvent) {
+        for state in self.stack.iter_mut().rev() {
+            state.lifecycle(event);
+        }
+        self.transition = None;
+        self.apply(StackAction::Exit);
+        self.lifecycle_event = Some(event);
+    }
+
+    /// The request that ended the app, `None` if it ended by itself or is still running.
+    pub fn lifecycle_event(&self) -> Option<LifecycleEvent> {
+        self.lifecycle_event
+    }
+
+    /// Pushes the initial state, without running anything.
+    ///
+    /// Use this with `step` to drive the manager yourself, `run` does it for you.
+    pub fn start(&mut self, initial_state: Box<dyn State<M>>) {
+        self.lifecycle_event = None;
+        self.apply(StackAction::Push(initial_state));
+    }
+
+    /// Updates the top state and applies the action it returns.
+    ///
+    /// While a transition plays, the states are not updated, the transition moves forward instead.
+    /// Home and on/off end the app, see `request_exit`.
+    ///
+    /// Returns `true` if there is something to render, i.e. the stack didn't change
+    /// or a transition plays.
+    pub fn update(&mut self, keyboard_state: &KeyboardTimedState, ctx: &FrameContext) -> bool {
+        if let Some(event) = LifecycleEvent::from_keyboard(keyboard_state) {
+            self.request_exit(event);
+            return false;
+        }
+        if self.debug.update(keyboard_state) {
+            self.repaint = true;
+        }
+        if let Some(playing) = &mut self.transition {
+            if playing.frame < playing.transition.frames() {
+                playing.frame += 1;
+                if playing.frame == playing.transition.out_frames()
+                    && let Some(action) = playing.action.take()
+                {
+                    self.apply(action);
                 }
-                StackAction::Replace(state) => {
-                    self.replace_top_stack(state);
+                if self.is_empty() {
+                    self.transition = None;
                 }
-                StackAction::Nop => (),
+                return !self.is_empty();
             }
+            // the last frame was rendered uncovered
+            self.transition = None;
         }
-
-        match state.create() {
-            StackAction::Pop(msg) => {
-                self.pop_from_stack(msg);
+        let Some(frame) = self.stack.last_mut() else {
+            return false;
+        };
+        match frame.update(keyboard_state, ctx) {
+            StackAction::Nop => true,
+            action => {
+                self.apply(action);
+                // a transition starts from what is on screen, which is still valid
+                self.transition.is_some()
             }
-            StackAction::Push(state) => {
-                self.push_to_stack(state);
+        }
+    }
+
+    /// Renders the top state, `alpha` going from 0 (at the previous update) to 1 (at the last one).
+    ///
+    /// If the top state is an overlay, the states below are rendered first, from the
+    /// bottom-most one visible. They are not updated anymore, so they are rendered
+    /// as of their last update.
+    ///
+    /// The debug overlay is drawn last, if visible.
+    pub fn render(&mut self, alpha: f32) {
+        self.render_frame(alpha);
+        self.debug.draw(self.clock.now(), self.stack.len());
+    }
+
+    /// Renders the states, or the transition being played.
+    fn render_frame(&mut self, alpha: f32) {
+        let Some(playing) = &mut self.transition else {
+            let repaint = mem::take(&mut self.repaint);
+            self.render_stack(alpha, repaint);
+            return;
+        };
+        let transition = playing.transition;
+        let coverage = transition.coverage(playing.frame);
+        if playing.frame <= transition.out_frames() {
+            // the leaving state stays on screen, it is only covered
+            if coverage > playing.covered {
+                transition.draw_out(playing.covered, coverage);
+                playing.covered = coverage;
             }
-            StackAction::Replace(state) => {
-                self.replace_top_stack(state);
+        } else {
+            // the screen was covered by the previous frame
+            self.render_stack(1., true);
+            transition.draw_in(coverage);
+        }
+    }
+
+    /// Renders the visible states, see `render`.
+    ///
+    /// With `invalidate`, they are told to draw everything.
+    fn render_stack(&mut self, alpha: f32, invalidate: bool) {
+        let bottom = self
+            .stack
+            .iter()
+            .rposition(|state| !state.is_overlay())
+            .unwrap_or(0);
+        let top = self.stack.len().saturating_sub(1);
+        for (i, frame) in self.stack.iter_mut().enumerate().skip(bottom) {
+            if invalidate {
+                frame.invalidate();
             }
-            StackAction::Nop => (),
+            frame.render(if i == top { alpha } else { 1. });
         }
-        self.stack.push(state);
-        poped_frame
     }
 
-    // --------
+    /// Runs one frame: updates the top state then, if it didn't alter the stack, renders it
+    /// as of the last update.
+    ///
+    /// Returns `true` if a frame was rendered.
+    pub fn step(&mut self, keyboard_state: &KeyboardTimedState, ctx: &FrameContext) -> bool {
+        let rendered = self.update(keyboard_state, ctx);
+        if rendered {
+            self.render(1.);
+        }
+        rendered
+    }
+
+    /// Checks if a transition is being played.
+    pub fn is_in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Checks if there is no state left, i.e. the app is over.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// The number of states on the stack.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The state on top of the stack, the one being updated and rendered.
+    ///
+    /// Use `as_any` to get the concrete state back.
+    pub fn top(&self) -> Option<&dyn State<M>> {
+        self.stack.last().map(|state| state.as_ref())
+    }
 
     /// Here we go !!!\
     /// (with initial state)
-    pub fn run(&mut self, initial_state: Box<dyn State<M>>, fps: u64) {
-        let time_interval = 1000 / fps;
-        let mut kb_handler = KeyboardTimedState::new();
-        self.push_to_stack(initial_state);
-        let mut now = time::monotonic();
-        while let Some(frame) = self.stack.last_mut() {
-            kb_handler.fetch();
-            match frame.update(&kb_handler) {
-                StackAction::Pop(msg) => {
-                    self.pop_from_stack(msg);
-                    continue;
-                }
-                StackAction::Push(state) => {
-                    self.push_to_stack(state);
-                    continue;
-                }
-                StackAction::Replace(state) => {
-                    self.replace_top_stack(state);
-                    continue;
-                }
-                StackAction::Nop => (),
+    ///
+    /// States are updated `fps` times per second, whatever the time the rendering takes:
+    /// a slow frame is followed by several updates to catch up.
+    /// `fps` is clamped between 1 and `MAX_FPS`.
+    ///
+    /// Returns the request that ended the app, `None` if the states all popped by themselves.
+    pub fn run(&mut self, initial_state: Box<dyn State<M>>, fps: u64) -> Option<LifecycleEvent> {
+        let fps = fps.clamp(1, MAX_FPS);
+        let dt = FrameContext::dt_for(fps);
+        let mut kb_handler = KeyboardTimedState::from_source(&mut self.input, &self.clock);
+        self.start(initial_state);
+        let mut clock = FrameClock::starting_at(fps, self.clock.now());
+        let mut frame = 0;
+        // the time not simulated yet, start with one update
+        let mut accumulator = dt;
+        while !self.is_empty() {
+            while accumulator >= dt && !self.is_empty() {
+                self.fetch_input(&mut kb_handler);
+                self.update(&kb_handler, &FrameContext::new(dt, frame));
+                frame += 1;
+                accumulator -= dt;
             }
-            frame.render();
-            let new_now = time::monotonic();
-            let elapsed = new_now - now;
-            if elapsed < time_interval {
-                time::msleep((time_interval - elapsed) as u32);
-            }
-            now = time::monotonic();
+            self.render(accumulator.as_secs_f32() / dt.as_secs_f32());
+            accumulator += clock
+                .wait_next_frame_with(&mut self.clock)
+                .min(MAX_FRAME_TIME.max(dt));
         }
+        self.lifecycle_event
+    }
+}
+
+/// Gives access to the concrete type behind a `dyn State`.
+///
+/// Implemented for every type, there is no need to implement it yourself.
+pub trait AsAny: Any {
+    /// Returns `self` as `Any`, to be downcasted.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 }
 
-pub trait State<M = ()> {
+pub trait State<M = ()>: AsAny {
     /// called when adding state to stack
     fn create(&mut self) -> StackAction<M> {
         StackAction::Nop
@@ -188,10 +586,342 @@ pub trait State<M = ()> {
     fn quit(&mut self) -> StackAction<M> {
         StackAction::Nop
     }
+    /// called on every state, from the top one, when the system asks to leave the app\
+    /// they are all quit just after, this is the time to save what must be kept
+    fn lifecycle(&mut self, _event: LifecycleEvent) {}
+    /// if true, the states below are rendered before this one,
+    /// which only draws over them (e.g. a dialog or a pause menu)\
+    /// only the top state is updated, overlay or not
+    fn is_overlay(&self) -> bool {
+        false
+    }
+    /// called when the screen was drawn over by something else (e.g. a transition)\
+    /// the next render must draw everything, not only what changed
+    fn invalidate(&mut self) {}
     /// when frame is active, called frequently\
     /// intended for logic update
-    fn update(&mut self, keyboard_state: &KeyboardTimedState) -> StackAction<M>;
+    fn update(&mut self, keyboard_state: &KeyboardTimedState, ctx: &FrameContext)
+    -> StackAction<M>;
     /// when frame is active called frequently\
     /// intended for render logic
-    fn render(&mut self);
+    ///
+    /// `alpha` tells how far the time is between the previous update (0) and the last one (1),
+    /// to interpolate what moves
+    fn render(&mut self, alpha: f32);
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use alloc::{
+        format,
+        rc::Rc,
+        string::{String, ToString},
+    };
+    use core::{cell::RefCell, marker::PhantomData};
+    use libnw::{display::Color, host, keyboard::KeyboardState, time::ManualClock};
+
+    use super::*;
+    use crate::headless::{HeadlessRunner, InputScript};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Marks the probes found by `PopTo`, the others being `Probe<()>`.
+    struct Root;
+
+    /// A state writing its callbacks to a log, and returning the actions it is given.
+    struct Probe<T = ()> {
+        name: &'static str,
+        log: Log,
+        create: Option<StackAction<&'static str>>,
+        resume: Option<StackAction<&'static str>>,
+        quit: Option<StackAction<&'static str>>,
+        update: Option<StackAction<&'static str>>,
+        marker: PhantomData<T>,
+    }
+
+    impl<T> Probe<T> {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+                create: None,
+                resume: None,
+                quit: None,
+                update: None,
+                marker: PhantomData,
+            }
+        }
+
+        fn on_create(mut self, action: StackAction<&'static str>) -> Self {
+            self.create = Some(action);
+            self
+        }
+
+        fn on_resume(mut self, action: StackAction<&'static str>) -> Self {
+            self.resume = Some(action);
+            self
+        }
+
+        fn on_quit(mut self, action: StackAction<&'static str>) -> Self {
+            self.quit = Some(action);
+            self
+        }
+
+        /// The action returned by the first update.
+        fn then(mut self, action: StackAction<&'static str>) -> Self {
+            self.update = Some(action);
+            self
+        }
+
+        fn write(&self, event: &str) {
+            self.log.borrow_mut().push(format!("{} {event}", self.name));
+        }
+    }
+
+    impl<T: 'static> State<&'static str> for Probe<T> {
+        fn create(&mut self) -> StackAction<&'static str> {
+            self.write("create");
+            self.create.take().unwrap_or(StackAction::Nop)
+        }
+
+        fn pause(&mut self) -> StackAction<&'static str> {
+            self.write("pause");
+            StackAction::Nop
+        }
+
+        fn resume(&mut self, pop_message: &'static str) -> StackAction<&'static str> {
+            self.write(&format!("resume {pop_message}"));
+            self.resume.take().unwrap_or(StackAction::Nop)
+        }
+
+        fn quit(&mut self) -> StackAction<&'static str> {
+            self.write("quit");
+            self.quit.take().unwrap_or(StackAction::Nop)
+        }
+
+        fn update(
+            &mut self,
+            _keyboard_state: &KeyboardTimedState,
+            _ctx: &FrameContext,
+        ) -> StackAction<&'static str> {
+            self.update.take().unwrap_or(StackAction::Nop)
+        }
+
+        fn render(&mut self, _alpha: f32) {}
+    }
+
+    /// Runs `initial` for a few frames, returning the log and the number of states left.
+    fn run(initial: impl State<&'static str>, log: &Log) -> (Vec<String>, usize) {
+        host::reset();
+        let mut runner = HeadlessRunner::new(Box::new(initial), InputScript::new(), 60);
+        runner.run_frames(10);
+        (log.take(), runner.manager().stack.len())
+    }
+
+    /// Pops after `updates` updates.
+    struct Countdown(u8);
+
+    impl State<&'static str> for Countdown {
+        fn update(
+            &mut self,
+            _keyboard_state: &KeyboardTimedState,
+            _ctx: &FrameContext,
+        ) -> StackAction<&'static str> {
+            self.0 -= 1;
+            match self.0 {
+                0 => StackAction::Pop(""),
+                _ => StackAction::Nop,
+            }
+        }
+
+        fn render(&mut self, _alpha: f32) {}
+    }
+
+    fn expect(events: &[&str]) -> Vec<String> {
+        events.iter().map(|event| event.to_string()).collect()
+    }
+
+    #[test]
+    fn push_pauses_then_creates() {
+        let log = Log::default();
+        let b = Probe::<()>::new("b", &log);
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        assert_eq!(events, expect(&["a create", "a pause", "b create"]));
+        assert_eq!(left, 2);
+    }
+
+    #[test]
+    fn replace_quits_then_creates() {
+        let log = Log::default();
+        let c = Probe::<()>::new("c", &log);
+        let b = Probe::<()>::new("b", &log).then(StackAction::Replace(Box::new(c)));
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = ["a create", "a pause", "b create", "b quit", "c create"];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 2);
+    }
+
+    #[test]
+    fn pop_quits_then_resumes_with_the_message() {
+        let log = Log::default();
+        let b = Probe::<()>::new("b", &log).then(StackAction::Pop("done"));
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = ["a create", "a pause", "b create", "b quit", "a resume done"];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn pop_n_quits_from_the_top() {
+        let log = Log::default();
+        let c = Probe::<()>::new("c", &log).then(StackAction::PopN(2, "done"));
+        let b = Probe::<()>::new("b", &log).then(StackAction::Push(Box::new(c)));
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = [
+            "a create",
+            "a pause",
+            "b create",
+            "b pause",
+            "c create",
+            "c quit",
+            "b quit",
+            "a resume done",
+        ];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn pop_to_stops_at_the_topmost_state_of_the_type() {
+        let log = Log::default();
+        let d = Probe::<()>::new("d", &log).then(StackAction::pop_to::<Probe<Root>>("done"));
+        let c = Probe::<()>::new("c", &log).then(StackAction::Push(Box::new(d)));
+        let b = Probe::<Root>::new("b", &log).then(StackAction::Push(Box::new(c)));
+        let a = Probe::<Root>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        assert_eq!(
+            events[events.len() - 3..],
+            expect(&["d quit", "c quit", "b resume done"])
+        );
+        assert_eq!(left, 2);
+    }
+
+    #[test]
+    fn pop_to_a_missing_type_drops_the_message() {
+        let log = Log::default();
+        let b = Probe::<()>::new("b", &log).then(StackAction::pop_to::<Probe<Root>>("done"));
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        // nothing is removed, and no state is resumed
+        assert_eq!(events, expect(&["a create", "a pause", "b create"]));
+        assert_eq!(left, 2);
+    }
+
+    #[test]
+    fn pop_to_the_top_state_only_resumes_it() {
+        let log = Log::default();
+        let b = Probe::<Root>::new("b", &log).then(StackAction::pop_to::<Probe<Root>>("done"));
+        let a = Probe::<Root>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        assert_eq!(
+            events,
+            expect(&["a create", "a pause", "b create", "b resume done"])
+        );
+        assert_eq!(left, 2);
+    }
+
+    #[test]
+    fn clear_and_push_quits_everything_then_creates() {
+        let log = Log::default();
+        let c = Probe::<()>::new("c", &log);
+        let b = Probe::<()>::new("b", &log).then(StackAction::ClearAndPush(Box::new(c)));
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = [
+            "a create", "a pause", "b create", "b quit", "a quit", "c create",
+        ];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn exit_quits_everything_and_drops_the_actions_left() {
+        let log = Log::default();
+        let c = Probe::<()>::new("c", &log);
+        let b = Probe::<()>::new("b", &log)
+            .on_quit(StackAction::Push(Box::new(c)))
+            .then(StackAction::Exit);
+        let a = Probe::<()>::new("a", &log).then(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = ["a create", "a pause", "b create", "b quit", "a quit"];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn actions_returned_by_callbacks_are_applied_in_order() {
+        let log = Log::default();
+        let c = Probe::<()>::new("c", &log).then(StackAction::Pop("from c"));
+        let b = Probe::<()>::new("b", &log)
+            .on_create(StackAction::Push(Box::new(c)))
+            .on_resume(StackAction::Pop("from b"));
+        let a = Probe::<()>::new("a", &log).on_create(StackAction::Push(Box::new(b)));
+        let (events, left) = run(a, &log);
+        let expected = [
+            "a create",
+            "a pause",
+            "b create",
+            "b pause",
+            "c create",
+            "c quit",
+            "b resume from c",
+            "b quit",
+            "a resume from b",
+        ];
+        assert_eq!(events, expect(&expected));
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn a_transition_applies_its_action_once_in_the_middle() {
+        host::reset();
+        let log = Log::default();
+        let b = Probe::<()>::new("b", &log);
+        let transition = Transition::fade(Color::BLACK, 6);
+        let a = Probe::<()>::new("a", &log)
+            .then(StackAction::Push(Box::new(b)).with_transition(transition));
+        let mut runner = HeadlessRunner::new(Box::new(a), InputScript::new(), 60);
+        // the update starting the transition, then all but the last frame covering the screen
+        runner.run_frames(transition.out_frames() as u64);
+        assert_eq!(log.take(), expect(&["a create"]));
+        assert!(runner.manager().is_in_transition());
+        runner.step();
+        assert_eq!(log.take(), expect(&["a pause", "b create"]));
+        runner.run_frames(transition.in_frames() as u64);
+        assert!(runner.manager().is_in_transition());
+        runner.step();
+        assert!(!runner.manager().is_in_transition());
+        runner.run_frames(5);
+        assert!(log.take().is_empty());
+        assert_eq!(runner.manager().stack.len(), 2);
+    }
+
+    #[test]
+    fn the_update_rate_is_clamped() {
+        assert_eq!(FrameContext::dt_for(0), Duration::from_millis(1000));
+        assert_eq!(FrameContext::dt_for(60), Duration::from_millis(16));
+        assert_eq!(FrameContext::dt_for(MAX_FPS), Duration::from_millis(1));
+        assert_eq!(FrameContext::dt_for(u64::MAX), Duration::from_millis(1));
+
+        // each update takes the virtual clock forward by the clamped period
+        for (fps, expected) in [(60, 48), (0, 3000), (MAX_FPS, 3), (5000, 3)] {
+            let mut manager = StateManager::with_sources(|| KeyboardState(0), ManualClock::new());
+            manager.run(Box::new(Countdown(3)), fps);
+            assert_eq!(manager.clock().now().as_millis(), expected);
+        }
+    }
 }
@@ -0,0 +1,395 @@
+//! A state to type text, with the shift and alpha modes of the calculator.
+//!
+//! # Example :
+//! ```
+//! use graphmgr::{StackAction, text_input::TextInputState};
+//!
+//! enum Msg {
+//!     Name(Option<String>),
+//! }
+//!
+//! let action: StackAction<Msg> = StackAction::Push(Box::new(
+//!     TextInputState::new("Your name ?", 12, Msg::Name).with_text("player"),
+//! ));
+//! ```
+//! The state pops itself with `Msg::Name(Some(text))` on OK / EXE,
+//! or with `Msg::Name(None)` on back.
+
+extern crate alloc;
+
+use alloc::string::String;
+use libnw::{
+    display::{self, Align, Color, Font, LARGE_CHAR_WIDTH, Rect, SCREEN_WIDTH, TextStyle, VAlign},
+    keyboard::{Key, KeyboardTimedState, RawKey},
+};
+
+use crate::{FrameContext, StackAction, State};
+
+/// How the next keys are interpreted, like the modes of the calculator.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum InputMode {
+    /// Digits and operators.
+    #[default]
+    Normal,
+    /// The second function of the next key.
+    Shift,
+    /// The next key types a lowercase letter.
+    Alpha,
+    /// The next key types an uppercase letter.
+    AlphaUpper,
+    /// Every key types a lowercase letter.
+    AlphaLock,
+    /// Every key types an uppercase letter.
+    AlphaUpperLock,
+}
+
+impl InputMode {
+    /// The mode after pressing shift.
+    ///
+    /// Outside of the alpha modes, it toggles shift, otherwise it toggles the case.
+    pub fn with_shift(self) -> Self {
+        match self {
+            Self::Normal => Self::Shift,
+            Self::Shift => Self::Normal,
+            Self::Alpha => Self::AlphaUpper,
+            Self::AlphaUpper => Self::Alpha,
+            Self::AlphaLock => Self::AlphaUpperLock,
+            Self::AlphaUpperLock => Self::AlphaLock,
+        }
+    }
+
+    /// The mode after pressing alpha.
+    ///
+    /// Pressing it once types one letter, twice locks the alpha mode, thrice goes back to normal.
+    pub fn with_alpha(self) -> Self {
+        match self {
+            Self::Normal => Self::Alpha,
+            Self::Shift => Self::AlphaUpper,
+            Self::Alpha => Self::AlphaLock,
+            Self::AlphaUpper => Self::AlphaUpperLock,
+            Self::AlphaLock | Self::AlphaUpperLock => Self::Normal,
+        }
+    }
+
+    /// The mode after typing with a key, the locked modes being the only ones to stay.
+    pub fn after_key(self) -> Self {
+        match self {
+            Self::AlphaLock | Self::AlphaUpperLock => self,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Checks if keys are pressed with shift.
+    pub fn is_shift(self) -> bool {
+        matches!(self, Self::Shift | Self::AlphaUpper | Self::AlphaUpperLock)
+    }
+
+    /// Checks if keys are pressed with alpha.
+    pub fn is_alpha(self) -> bool {
+        !matches!(self, Self::Normal | Self::Shift)
+    }
+
+    /// A short text telling the mode, empty in normal mode.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "",
+            Self::Shift => "shift",
+            Self::Alpha => "a",
+            Self::AlphaUpper => "A",
+            Self::AlphaLock => "a (lock)",
+            Self::AlphaUpperLock => "A (lock)",
+        }
+    }
+}
+
+/// The box the text is typed in.
+const TEXT_BOX: Rect = Rect {
+    x: 8,
+    y: 108,
+    width: SCREEN_WIDTH - 16,
+    height: 24,
+};
+/// The space between the box and the text.
+const TEXT_PADDING: u16 = 4;
+
+/// A state to type a line of text.
+///
+/// Arrows move the cursor (up and down to the start and the end), backspace deletes
+/// the character before the cursor (in every mode), and shift + backspace clears everything.
+pub struct TextInputState<M> {
+    prompt: String,
+    text: String,
+    /// The position of the cursor, in chars.
+    cursor: usize,
+    max_len: usize,
+    mode: InputMode,
+    to_message: fn(Option<String>) -> M,
+    needs_redraw: bool,
+}
+
+impl<M> TextInputState<M> {
+    /// Creates an empty text input, showing `prompt` above the text.
+    ///
+    /// The text is at most `max_len` chars long, and never more than what fits on screen.
+    /// `to_message` makes the pop message from the typed text, `None` if it was cancelled.
+    pub fn new(prompt: &str, max_len: usize, to_message: fn(Option<String>) -> M) -> Self {
+        let fitting = ((TEXT_BOX.width - 2 * TEXT_PADDING) / LARGE_CHAR_WIDTH) as usize - 1;
+        Self {
+            prompt: String::from(prompt),
+            text: String::new(),
+            cursor: 0,
+            max_len: max_len.min(fitting),
+            mode: InputMode::Normal,
+            to_message,
+            needs_redraw: true,
+        }
+    }
+
+    /// Starts with `text` (cut to the max length), the cursor at its end.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.chars().take(self.max_len).collect();
+        self.cursor = self.text.chars().count();
+        self
+    }
+
+    /// The text typed so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The position of the cursor, in chars.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// How the next keys are interpreted.
+    pub fn mode(&self) -> InputMode {
+        self.mode
+    }
+
+    /// Handles a press of `raw`, returning the action to do if the input is over.
+    pub fn press(&mut self, raw: RawKey) -> Option<StackAction<M>> {
+        self.needs_redraw = true;
+        let len = self.text.chars().count();
+        match raw {
+            RawKey::Ok | RawKey::Exe => {
+                let text = core::mem::take(&mut self.text);
+                return Some(StackAction::Pop((self.to_message)(Some(text))));
+            }
+            RawKey::Back => return Some(StackAction::Pop((self.to_message)(None))),
+            RawKey::Shift => self.mode = self.mode.with_shift(),
+            RawKey::Alpha => self.mode = self.mode.with_alpha(),
+            RawKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            RawKey::Right => self.cursor = (self.cursor + 1).min(len),
+            RawKey::Up => self.cursor = 0,
+            RawKey::Down => self.cursor = len,
+            // with alpha, backspace would type '%' (or nothing), it must still delete
+            RawKey::Backspace => {
+                if self.mode == InputMode::Shift {
+                    self.text.clear();
+                    self.cursor = 0;
+                } else if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.text.remove(self.byte_index(self.cursor));
+                }
+                self.mode = self.mode.after_key();
+            }
+            _ => {
+                if let Some(ch) = Key::from_raw(raw, self.mode.is_shift(), self.mode.is_alpha())
+                    .and_then(|key| key.to_char())
+                    && len < self.max_len
+                {
+                    self.text.insert(self.byte_index(self.cursor), ch);
+                    self.cursor += 1;
+                }
+                self.mode = self.mode.after_key();
+            }
+        }
+        None
+    }
+
+    /// The index in bytes of the char at `index`.
+    fn byte_index(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+}
+
+/// Whether holding `raw` repeats it: the modes and the keys ending the input only
+/// act once per press, a held OK would otherwise validate the next input too.
+fn repeats(raw: RawKey) -> bool {
+    !matches!(
+        raw,
+        RawKey::Shift | RawKey::Alpha | RawKey::Ok | RawKey::Exe | RawKey::Back
+    )
+}
+
+impl<M: 'static> State<M> for TextInputState<M> {
+    fn create(&mut self) -> StackAction<M> {
+        self.needs_redraw = true;
+        StackAction::Nop
+    }
+
+    fn resume(&mut self, _pop_message: M) -> StackAction<M> {
+        self.needs_redraw = true;
+        StackAction::Nop
+    }
+
+    fn invalidate(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    fn update(
+        &mut self,
+        keyboard_state: &KeyboardTimedState,
+        _ctx: &FrameContext,
+    ) -> StackAction<M> {
+        let repeated = keyboard_state.repeated_state().0;
+        for i in 0..64u8 {
+            if (repeated >> i) & 1 == 0 {
+                continue;
+            }
+            if let Ok(raw) = RawKey::try_from(i)
+                && (repeats(raw) || keyboard_state.is_key_just_pressed(raw))
+                && let Some(action) = self.press(raw)
+            {
+                return action;
+            }
+        }
+        StackAction::Nop
+    }
+
+    fn render(&mut self, _alpha: f32) {
+        if !self.needs_redraw {
+            return;
+        }
+        self.needs_redraw = false;
+        display::eadk::wait_for_vblank();
+        display::clear_screen(Color::WHITE);
+        display::draw_text(
+            &self.prompt,
+            Rect::new(TEXT_BOX.x, 0, TEXT_BOX.width, TEXT_BOX.y - 8),
+            TextStyle::new(Font::Large, Color::BLACK)
+                .background(Color::WHITE)
+                .align(Align::Center, VAlign::Bottom),
+        );
+        // a black frame around the text
+        TEXT_BOX.fill(Color::BLACK);
+        Rect::new(
+            TEXT_BOX.x + 1,
+            TEXT_BOX.y + 1,
+            TEXT_BOX.width - 2,
+            TEXT_BOX.height - 2,
+        )
+        .fill(Color::WHITE);
+        let text_x = TEXT_BOX.x + TEXT_PADDING;
+        let text_y = TEXT_BOX.y + TEXT_PADDING;
+        Font::Large.draw(&self.text, text_x, text_y, Color::BLACK, Some(Color::WHITE));
+        let before_cursor = &self.text[..self.byte_index(self.cursor)];
+        Rect::new(
+            text_x + Font::Large.line_width(before_cursor),
+            text_y,
+            1,
+            Font::Large.line_height(),
+        )
+        .fill(Color::BLUE);
+        Font::Small.draw(
+            self.mode.label(),
+            TEXT_BOX.x,
+            TEXT_BOX.y + TEXT_BOX.height + 4,
+            Color::BLUE,
+            Some(Color::WHITE),
+        );
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    extern crate alloc;
+
+    use alloc::boxed::Box;
+    use libnw::{host, keyboard::RawKey};
+
+    use super::{InputMode, TextInputState};
+    use crate::headless::{HeadlessRunner, InputScript};
+
+    /// Runs a text input for a second and a half of `script`.
+    fn run(script: InputScript) -> HeadlessRunner<()> {
+        host::reset();
+        let input = TextInputState::new("Name ?", 12, |_| ());
+        let mut runner = HeadlessRunner::new(Box::new(input), script, 60);
+        runner.run_frames(90);
+        runner
+    }
+
+    #[test]
+    fn a_held_mode_key_only_acts_once() {
+        host::reset();
+        let input = TextInputState::new("Name ?", 12, |_| ());
+        let script = InputScript::new().hold(5, RawKey::Shift, 60);
+        let mut runner = HeadlessRunner::new(Box::new(input), script, 60);
+        runner.run_frames(6);
+        for _ in 0..80 {
+            let input = runner.top_as::<TextInputState<()>>().unwrap();
+            assert_eq!(input.mode(), InputMode::Shift, "frame {}", runner.frame());
+            runner.run_frames(1);
+        }
+    }
+
+    #[test]
+    fn backspace_deletes_in_every_alpha_mode() {
+        for mode_keys in [
+            &[RawKey::Alpha][..],
+            &[RawKey::Alpha, RawKey::Alpha],
+            &[RawKey::Alpha, RawKey::Shift],
+            &[RawKey::Alpha, RawKey::Alpha, RawKey::Shift],
+        ] {
+            let mut input = TextInputState::new("Name ?", 12, |_| ()).with_text("golf");
+            for &key in mode_keys {
+                input.press(key);
+            }
+            let mode = input.mode();
+            input.press(RawKey::Backspace);
+            assert_eq!(input.text(), "gol", "{mode:?}");
+            assert_eq!(input.mode(), mode.after_key());
+        }
+    }
+
+    #[test]
+    fn alpha_lock_types_and_deletes_letters() {
+        let mut input = TextInputState::new("Name ?", 12, |_| ());
+        for key in [RawKey::Alpha, RawKey::Alpha, RawKey::Exp, RawKey::Ln] {
+            input.press(key);
+        }
+        assert_eq!(input.text(), "ab");
+        input.press(RawKey::Backspace);
+        input.press(RawKey::Log);
+        assert_eq!(input.text(), "ac");
+        assert_eq!(input.mode(), InputMode::AlphaLock);
+    }
+
+    #[test]
+    fn shift_backspace_clears() {
+        let mut input = TextInputState::new("Name ?", 12, |_| ()).with_text("golf");
+        input.press(RawKey::Left);
+        input.press(RawKey::Backspace);
+        assert_eq!((input.text(), input.cursor()), ("gof", 2));
+        input.press(RawKey::Shift);
+        input.press(RawKey::Backspace);
+        assert_eq!((input.text(), input.cursor()), ("", 0));
+        assert_eq!(input.mode(), InputMode::Normal);
+        // nothing to delete
+        input.press(RawKey::Backspace);
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn a_held_char_key_repeats() {
+        let runner = run(InputScript::new().hold(5, RawKey::One, 60));
+        let input = runner.top_as::<TextInputState<()>>().unwrap();
+        assert!(input.text().len() > 1, "typed {:?}", input.text());
+        assert!(input.text().chars().all(|ch| ch == '1'));
+    }
+}
@@ -0,0 +1,309 @@
+//! Animated transitions between states.
+//!
+//! There is no room to keep whole frames in memory, so the screen itself is used:
+//! the first half of a transition covers what is on screen (the last frame of the
+//! leaving state), then the stack action is applied, and the second half renders the
+//! new top state every frame and uncovers it.
+//!
+//! # Example :
+//! ```
+//! use graphmgr::{FrameContext, StackAction, State, transition::Transition};
+//! use libnw::{display::Color, keyboard::KeyboardTimedState};
+//!
+//! struct GameState;
+//!
+//! impl State for GameState {
+//!     fn update(&mut self, _keyboard: &KeyboardTimedState, _ctx: &FrameContext) -> StackAction {
+//!         StackAction::Nop
+//!     }
+//!
+//!     fn render(&mut self, _alpha: f32) {}
+//! }
+//!
+//! let action: StackAction =
+//!     StackAction::Push(Box::new(GameState)).with_transition(Transition::fade(Color::BLACK, 20));
+//! ```
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use libnw::display::{Color, Rect, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// A side of the screen, or the way something moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// The unit vector of the direction, y going down.
+    fn vector(self) -> (i32, i32) {
+        match self {
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+        }
+    }
+
+    /// The size of the screen along the direction.
+    fn extent(self) -> u16 {
+        match self {
+            Self::Left | Self::Right => SCREEN_WIDTH,
+            Self::Up | Self::Down => SCREEN_HEIGHT,
+        }
+    }
+}
+
+/// How the screen is covered then uncovered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Fades to the color, then from it.
+    Fade,
+    /// The old screen slides out towards the direction, the new one comes from the other side.
+    Slide(Direction),
+    /// A sheet of color sweeps the screen towards the direction, and goes on to uncover it.
+    Wipe(Direction),
+    /// A circle shrinks to the center of the screen, then grows back.
+    Iris,
+}
+
+/// A transition played by `StateManager` around a stack action, see `StackAction::with_transition`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    kind: TransitionKind,
+    /// The color the screen goes through, between the two states.
+    color: Color,
+    /// The number of updates the whole transition lasts, at least 2.
+    frames: u16,
+}
+
+impl Transition {
+    /// Creates a transition lasting `frames` updates (at least 2).
+    pub fn new(kind: TransitionKind, color: Color, frames: u16) -> Self {
+        Self {
+            kind,
+            color,
+            frames: frames.max(2),
+        }
+    }
+
+    /// Fades to `color`, then to the new state.
+    pub fn fade(color: Color, frames: u16) -> Self {
+        Self::new(TransitionKind::Fade, color, frames)
+    }
+
+    /// Slides the screen out towards `direction`, revealing `color`, then slides the new state in.
+    pub fn slide(direction: Direction, color: Color, frames: u16) -> Self {
+        Self::new(TransitionKind::Slide(direction), color, frames)
+    }
+
+    /// Covers the screen with `color` towards `direction`, then uncovers it the same way.
+    pub fn wipe(direction: Direction, color: Color, frames: u16) -> Self {
+        Self::new(TransitionKind::Wipe(direction), color, frames)
+    }
+
+    /// Closes a circle on the center of the screen, then opens it on the new state.
+    pub fn iris(color: Color, frames: u16) -> Self {
+        Self::new(TransitionKind::Iris, color, frames)
+    }
+
+    /// How the screen is covered then uncovered.
+    pub fn kind(&self) -> TransitionKind {
+        self.kind
+    }
+
+    /// The color the screen goes through, between the two states.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The number of updates the whole transition lasts, at least 2.
+    pub fn frames(&self) -> u16 {
+        self.frames
+    }
+
+    /// The number of updates covering the old state, the action being applied after them.
+    ///
+    /// At least 1, so that the action is always applied.
+    pub fn out_frames(&self) -> u16 {
+        self.frames / 2
+    }
+
+    /// The number of updates uncovering the new state, at least 1.
+    pub fn in_frames(&self) -> u16 {
+        self.frames - self.out_frames()
+    }
+
+    /// How much the screen is covered after `frame` updates, from 0 to 1 at the end
+    /// of the first half, then back to 0 at the end.
+    pub fn coverage(&self, frame: u16) -> f32 {
+        let out_frames = self.out_frames();
+        if frame <= out_frames {
+            frame as f32 / out_frames as f32
+        } else {
+            let in_frames = self.in_frames();
+            let left = in_frames - (frame - out_frames).min(in_frames);
+            left as f32 / in_frames as f32
+        }
+    }
+
+    /// Covers the screen, from `from` to `to` (0 is uncovered, 1 is covered).
+    ///
+    /// What is on screen is the last frame of the leaving state, as left by the previous call.
+    pub fn draw_out(&self, from: f32, to: f32) {
+        match self.kind {
+            TransitionKind::Fade => {
+                // what is left of the screen after `from` must be scaled down to what is left after `to`
+                let alpha = 255. * (to - from) / (1. - from);
+                for y in 0..SCREEN_HEIGHT {
+                    Rect::new(0, y, SCREEN_WIDTH, 1).blend(self.color, alpha as u8);
+                }
+            }
+            TransitionKind::Slide(direction) => {
+                let extent = direction.extent() as f32;
+                let moved = (to * extent) as i32 - (from * extent) as i32;
+                let (dx, dy) = direction.vector();
+                shift_screen(dx * moved, dy * moved, self.color);
+            }
+            TransitionKind::Wipe(direction) => self.wipe_cover(direction, to, true),
+            TransitionKind::Iris => self.iris_cover(to),
+        }
+    }
+
+    /// Covers the freshly rendered new state, by `coverage` (1 is covered, 0 is uncovered).
+    pub fn draw_in(&self, coverage: f32) {
+        match self.kind {
+            TransitionKind::Fade => {
+                for y in 0..SCREEN_HEIGHT {
+                    Rect::new(0, y, SCREEN_WIDTH, 1).blend(self.color, (coverage * 255.) as u8);
+                }
+            }
+            TransitionKind::Slide(direction) => {
+                // the new state comes from the opposite side
+                let remaining = (coverage * direction.extent() as f32) as i32;
+                let (dx, dy) = direction.vector();
+                shift_screen(-dx * remaining, -dy * remaining, self.color);
+            }
+            TransitionKind::Wipe(direction) => self.wipe_cover(direction, coverage, false),
+            TransitionKind::Iris => self.iris_cover(coverage),
+        }
+    }
+
+    /// Fills the part of the screen a wipe covers, the sheet entering from the side
+    /// opposite to `direction` when covering, and leaving by `direction` when uncovering.
+    fn wipe_cover(&self, direction: Direction, coverage: f32, entering: bool) {
+        let size = (coverage * direction.extent() as f32) as u16;
+        let rest = direction.extent() - size;
+        // the offset of the cover along its axis
+        let start = match (direction, entering) {
+            (Direction::Right | Direction::Down, true)
+            | (Direction::Left | Direction::Up, false) => 0,
+            _ => rest,
+        };
+        match direction {
+            Direction::Left | Direction::Right => Rect::new(start, 0, size, SCREEN_HEIGHT),
+            Direction::Up | Direction::Down => Rect::new(0, start, SCREEN_WIDTH, size),
+        }
+        .fill(self.color);
+    }
+
+    /// Fills the screen outside a centered circle, which is a point when `coverage` is 1.
+    fn iris_cover(&self, coverage: f32) {
+        let (cx, cy) = (SCREEN_WIDTH as i32 / 2, SCREEN_HEIGHT as i32 / 2);
+        // the distance from the center to the corners
+        let max_radius = ((cx * cx + cy * cy) as u32).isqrt() + 1;
+        let radius = ((1. - coverage) * max_radius as f32) as i32;
+        for y in 0..SCREEN_HEIGHT {
+            let dy = y as i32 - cy;
+            if dy.abs() >= radius {
+                Rect::new(0, y, SCREEN_WIDTH, 1).fill(self.color);
+                continue;
+            }
+            let half = ((radius * radius - dy * dy) as u32).isqrt() as i32;
+            Rect::screen_space_clipping(0, y as i32, (cx - half).max(0) as u16, 1).fill(self.color);
+            Rect::screen_space_clipping(cx + half, y as i32, (cx - half).max(0) as u16, 1)
+                .fill(self.color);
+        }
+    }
+}
+
+/// Moves what is on screen by (`dx`, `dy`), the uncovered pixels being filled with `color`.
+///
+/// Done line by line, to never hold more than a line in memory.
+fn shift_screen(dx: i32, dy: i32, color: Color) {
+    if dx == 0 && dy == 0 {
+        return;
+    }
+    let (width, height) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+    let mut line = Vec::with_capacity(SCREEN_WIDTH as usize);
+    // going down, the lines below are overwritten first so that they are read before
+    let rows: &mut dyn Iterator<Item = i32> = match dy > 0 {
+        true => &mut (0..height).rev(),
+        false => &mut (0..height),
+    };
+    for y in rows {
+        let row = Rect::new(0, y as u16, SCREEN_WIDTH, 1);
+        let source_y = y - dy;
+        if !(0..height).contains(&source_y) {
+            row.fill(color);
+            continue;
+        }
+        let source = Rect::new(0, source_y as u16, SCREEN_WIDTH, 1).get_pixels();
+        line.clear();
+        line.extend((0..width).map(|x| {
+            let source_x = x - dx;
+            match (0..width).contains(&source_x) {
+                true => source[source_x as usize],
+                false => color,
+            }
+        }));
+        row.fill_with_buf(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverages(transition: Transition) -> Vec<f32> {
+        (0..=transition.frames())
+            .map(|frame| transition.coverage(frame))
+            .collect()
+    }
+
+    #[test]
+    fn the_screen_is_covered_then_uncovered() {
+        let transition = Transition::fade(Color::BLACK, 6);
+        assert_eq!(transition.out_frames(), 3);
+        assert_eq!(transition.in_frames(), 3);
+        assert_eq!(
+            coverages(transition),
+            [0., 1. / 3., 2. / 3., 1., 2. / 3., 1. / 3., 0.]
+        );
+    }
+
+    #[test]
+    fn odd_lengths_uncover_longer() {
+        let transition = Transition::iris(Color::BLACK, 5);
+        assert_eq!(transition.out_frames(), 2);
+        assert_eq!(transition.in_frames(), 3);
+        assert_eq!(coverages(transition), [0., 0.5, 1., 2. / 3., 1. / 3., 0.]);
+    }
+
+    #[test]
+    fn short_transitions_last_two_frames() {
+        for frames in [0, 1, 2] {
+            let transition = Transition::wipe(Direction::Left, Color::WHITE, frames);
+            assert_eq!(transition.frames(), 2);
+            assert_eq!(transition.out_frames(), 1);
+            assert_eq!(transition.in_frames(), 1);
+            assert_eq!(coverages(transition), [0., 1., 0.]);
+        }
+        // past the end, the screen stays uncovered
+        assert_eq!(Transition::fade(Color::BLACK, 2).coverage(10), 0.);
+    }
+}
@@ -0,0 +1,421 @@
+//! The widgets holding other widgets.
+
+extern crate alloc;
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::cell::Cell;
+use libnw::display::{Align, Rect, TextStyle, VAlign, draw_text};
+
+use super::{Event, PADDING, Response, Theme, Widget};
+
+/// The width of the scroll bar of a list.
+const SCROLL_BAR_WIDTH: u16 = 3;
+
+/// Widgets on top of each other, the focus moved with up and down.
+///
+/// When they don't all fit, the list scrolls to keep the focused one visible.
+pub struct List<A> {
+    children: Vec<Box<dyn Widget<A>>>,
+    /// The index of the focused child, if any can get the focus.
+    focus: Option<usize>,
+    /// The index of the first child drawn, updated when drawing.
+    first: Cell<usize>,
+}
+
+impl<A: 'static> Default for List<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: 'static> List<A> {
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+            focus: None,
+            first: Cell::new(0),
+        }
+    }
+
+    /// Adds a widget at the bottom, to declare the list in one expression.
+    pub fn with(mut self, child: impl Widget<A>) -> Self {
+        self.push(child);
+        self
+    }
+
+    /// Adds a widget at the bottom.
+    ///
+    /// The first one that can get the focus gets it.
+    pub fn push(&mut self, child: impl Widget<A>) {
+        if self.focus.is_none() && child.is_focusable() {
+            self.focus = Some(self.children.len());
+        }
+        self.children.push(Box::new(child));
+    }
+
+    /// The number of widgets.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Checks if there is no widget.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The widget at `index`.
+    ///
+    /// Use `as_any` to get the concrete widget back.
+    pub fn child(&self, index: usize) -> Option<&dyn Widget<A>> {
+        self.children.get(index).map(|child| child.as_ref())
+    }
+
+    /// The widget at `index`, to change it.
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut dyn Widget<A>> {
+        self.children.get_mut(index).map(|child| child.as_mut())
+    }
+
+    /// The index of the focused widget.
+    pub fn focus(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Gives the focus to the widget at `index`, if it can get it.
+    pub fn set_focus(&mut self, index: usize) {
+        if self
+            .children
+            .get(index)
+            .is_some_and(|child| child.is_focusable())
+        {
+            self.focus = Some(index);
+        }
+    }
+
+    /// Moves the focus to the next widget that can get it, before (`-1`) or after (`1`).
+    ///
+    /// Returns `false` if there is none.
+    fn move_focus(&mut self, direction: isize) -> bool {
+        let Some(focus) = self.focus else {
+            return false;
+        };
+        let mut index = focus as isize + direction;
+        while index >= 0
+            && let Some(child) = self.children.get(index as usize)
+        {
+            if child.is_focusable() {
+                self.focus = Some(index as usize);
+                return true;
+            }
+            index += direction;
+        }
+        false
+    }
+
+    /// The index of the first child to draw so that the focused one is visible in `height`.
+    fn scroll(&self, height: u16, theme: &Theme) -> usize {
+        let mut first = self.first.get().min(self.children.len().saturating_sub(1));
+        let Some(focus) = self.focus else {
+            return first;
+        };
+        if focus < first {
+            return focus;
+        }
+        let heights = |from: usize| -> u32 {
+            self.children[from..=focus]
+                .iter()
+                .map(|child| child.size(theme).1 as u32)
+                .sum()
+        };
+        while first < focus && heights(first) > height as u32 {
+            first += 1;
+        }
+        first
+    }
+}
+
+impl<A: 'static> Widget<A> for List<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        self.children
+            .iter()
+            .map(|child| child.size(theme))
+            .fold((0, 0), |(width, height), (w, h)| {
+                (width.max(w), height.saturating_add(h))
+            })
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.focus.is_some()
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        if let Some(focus) = self.focus {
+            match self.children[focus].handle(event) {
+                Response::Ignored => (),
+                response => return response,
+            }
+        }
+        let moved = match event {
+            Event::Up => self.move_focus(-1),
+            Event::Down => self.move_focus(1),
+            _ => false,
+        };
+        match moved {
+            true => Response::Handled,
+            false => Response::Ignored,
+        }
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        rect.fill(theme.background);
+        let first = self.scroll(rect.height, theme);
+        self.first.set(first);
+        let (width, height) = self.size(theme);
+        let scrolls = height > rect.height;
+        let child_width = match scrolls {
+            true => rect.width.saturating_sub(SCROLL_BAR_WIDTH),
+            false => rect.width,
+        };
+        let mut y = rect.y;
+        // the number of children drawn, none if the first one is taller than the list
+        let mut shown = 0;
+        for (i, child) in self.children.iter().enumerate().skip(first) {
+            let child_height = child.size(theme).1;
+            if y as u32 + child_height as u32 > rect.y as u32 + rect.height as u32 {
+                break;
+            }
+            let child_focused = focused && self.focus == Some(i);
+            child.render(
+                Rect::new(rect.x, y, child_width, child_height),
+                child_focused,
+                theme,
+            );
+            y += child_height;
+            shown += 1;
+        }
+        if scrolls && width > 0 {
+            // the part of the children shown, out of all of them
+            let count = self.children.len() as u32;
+            let bar_y = rect.height as u32 * first as u32 / count;
+            let bar_height = rect.height as u32 * shown / count;
+            Rect::new(
+                rect.x + rect.width - SCROLL_BAR_WIDTH,
+                rect.y + bar_y as u16,
+                SCROLL_BAR_WIDTH,
+                bar_height.max(1) as u16,
+            )
+            .fill(theme.foreground);
+        }
+    }
+}
+
+/// A frame with a title around a widget.
+pub struct Panel<A> {
+    title: String,
+    child: Box<dyn Widget<A>>,
+}
+
+impl<A: 'static> Panel<A> {
+    /// Creates a panel around `child`, an empty title hiding the title bar.
+    pub fn new(title: &str, child: impl Widget<A>) -> Self {
+        Self {
+            title: String::from(title),
+            child: Box::new(child),
+        }
+    }
+
+    /// The widget inside.
+    ///
+    /// Use `as_any` to get the concrete widget back.
+    pub fn child(&self) -> &dyn Widget<A> {
+        self.child.as_ref()
+    }
+
+    /// The widget inside, to change it.
+    pub fn child_mut(&mut self) -> &mut dyn Widget<A> {
+        self.child.as_mut()
+    }
+
+    /// The height of the title bar.
+    fn title_height(&self, theme: &Theme) -> u16 {
+        match self.title.is_empty() {
+            true => 0,
+            false => theme.font.line_height() + 2 * PADDING,
+        }
+    }
+}
+
+impl<A: 'static> Widget<A> for Panel<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        let (width, height) = self.child.size(theme);
+        let title_width = theme.font.line_width(&self.title) + 2 * PADDING;
+        (
+            width.max(title_width) + 2 * PADDING + 2,
+            height + self.title_height(theme) + 2 * PADDING + 2,
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.child.is_focusable()
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        self.child.handle(event)
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        // a 1 pixel frame, then the title bar
+        rect.fill(theme.foreground);
+        let inside = Rect::new(
+            rect.x + 1,
+            rect.y + 1,
+            rect.width.saturating_sub(2),
+            rect.height.saturating_sub(2),
+        );
+        let title_height = self.title_height(theme);
+        Rect::new(inside.x, inside.y, inside.width, title_height).fill(theme.foreground);
+        draw_text(
+            &self.title,
+            Rect::new(inside.x, inside.y, inside.width, title_height),
+            TextStyle::new(theme.font, theme.background)
+                .background(theme.foreground)
+                .align(Align::Center, VAlign::Middle),
+        );
+        let body = Rect::new(
+            inside.x,
+            inside.y + title_height,
+            inside.width,
+            inside.height.saturating_sub(title_height),
+        );
+        body.fill(theme.background);
+        self.child.render(
+            Rect::new(
+                body.x + PADDING,
+                body.y + PADDING,
+                body.width.saturating_sub(2 * PADDING),
+                body.height.saturating_sub(2 * PADDING),
+            ),
+            focused,
+            theme,
+        );
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use libnw::{display::Color, host};
+
+    use super::*;
+    use crate::ui::{Button, Label, Ui};
+
+    fn focus(ui: &Ui<usize>) -> Option<usize> {
+        ui.root()
+            .as_any()
+            .downcast_ref::<List<usize>>()
+            .unwrap()
+            .focus()
+    }
+
+    /// The index of the first child drawn by the last render.
+    fn first(ui: &Ui<usize>) -> usize {
+        ui.root()
+            .as_any()
+            .downcast_ref::<List<usize>>()
+            .unwrap()
+            .first
+            .get()
+    }
+
+    /// A list of `count` buttons emitting their index.
+    fn buttons(count: usize) -> List<usize> {
+        (0..count).fold(List::new(), |list, i| list.with(Button::new("Button", i)))
+    }
+
+    /// The height of a button.
+    fn button_height() -> u16 {
+        Widget::<usize>::size(&Button::new("Button", 0), &Theme::DEFAULT).1
+    }
+
+    #[test]
+    fn the_focus_skips_the_unfocusable_children() {
+        let list = List::new()
+            .with(Label::new("Title"))
+            .with(Button::new("First", 1))
+            .with(Label::new("Some text"))
+            .with(Label::new("More text"))
+            .with(Button::new("Second", 4))
+            .with(Label::new("The end"));
+        let mut ui = Ui::centered(list);
+        assert_eq!(focus(&ui), Some(1));
+        assert_eq!(ui.send(Event::Down), None);
+        assert_eq!(focus(&ui), Some(4));
+        // nothing to focus below
+        ui.send(Event::Down);
+        assert_eq!(focus(&ui), Some(4));
+        assert_eq!(ui.send(Event::Ok), Some(4));
+        ui.send(Event::Up);
+        ui.send(Event::Up);
+        assert_eq!(focus(&ui), Some(1));
+        assert_eq!(ui.send(Event::Ok), Some(1));
+    }
+
+    #[test]
+    fn a_list_without_focusable_children_ignores_the_arrows() {
+        let mut ui =
+            Ui::centered(List::new().with(Label::new("Nothing to pick"))).on_back(usize::MAX);
+        assert_eq!(focus(&ui), None);
+        assert_eq!(ui.send(Event::Down), None);
+        assert_eq!(ui.send(Event::Back), Some(usize::MAX));
+    }
+
+    #[test]
+    fn scrolling_keeps_the_focus_visible() {
+        host::reset();
+        // room for 3 buttons out of 10
+        let rect = Rect::new(0, 0, 200, 3 * button_height() + 2);
+        let mut ui = Ui::new(rect, buttons(10)).without_vblank();
+        ui.render();
+        assert_eq!(first(&ui), 0);
+        for focused in 1..10 {
+            ui.send(Event::Down);
+            ui.render();
+            assert_eq!(focus(&ui), Some(focused));
+            assert_eq!(first(&ui), focused.saturating_sub(2));
+        }
+        // going back up scrolls as soon as the focus leaves the top
+        ui.send(Event::Up);
+        ui.render();
+        assert_eq!(first(&ui), 7);
+        for _ in 0..6 {
+            ui.send(Event::Up);
+        }
+        ui.render();
+        assert_eq!((focus(&ui), first(&ui)), (Some(2), 2));
+    }
+
+    #[test]
+    fn a_child_taller_than_the_list_is_not_counted_as_shown() {
+        host::reset();
+        let rect = Rect::new(0, 0, 100, 40);
+        let list = List::<usize>::new()
+            .with(Label::new("1\n2\n3\n4\n5"))
+            .with(Label::new("6"));
+        let mut ui = Ui::new(rect, list).without_vblank();
+        ui.render();
+        let bar = Rect::new(rect.width - SCROLL_BAR_WIDTH, 0, 1, rect.height).get_pixels();
+        let filled = bar.iter().filter(|&&pixel| pixel == Color::BLACK).count();
+        // nothing is drawn, so the bar is as small as it gets
+        assert_eq!(filled, 1);
+    }
+
+    #[test]
+    fn the_scroll_bar_shows_the_part_drawn() {
+        host::reset();
+        let rect = Rect::new(0, 0, 200, 2 * button_height());
+        let mut ui = Ui::new(rect, buttons(4)).without_vblank();
+        ui.render();
+        let bar = Rect::new(rect.width - SCROLL_BAR_WIDTH, 0, 1, rect.height).get_pixels();
+        let filled = bar.iter().filter(|&&pixel| pixel == Color::BLACK).count();
+        assert_eq!(filled, rect.height as usize / 2);
+    }
+}
@@ -0,0 +1,363 @@
+//! A small retained-mode widget toolkit, to build menus and dialogs.
+//!
+//! Widgets are built once and kept in a [`Ui`], which reads the keyboard, moves the focus
+//! with the arrows, and only draws again when something changed. The keys can be remapped
+//! with [`Ui::with_events`]. Widgets emit actions
+//! (of any `Clone` type) when activated, which the state turns into stack actions.
+//!
+//! - [`Label`] : some text.
+//! - [`Button`] : emits an action on OK.
+//! - [`Checkbox`] : toggled on OK.
+//! - [`Slider`] and [`Spinner`] : a number changed with left and right.
+//! - [`List`] : widgets on top of each other, scrolled to keep the focus visible.
+//! - [`Panel`] : a frame with a title around a widget.
+//!
+//! # Example :
+//! ```
+//! use graphmgr::{
+//!     FrameContext, StackAction, State,
+//!     ui::{Button, List, Panel, Slider, Ui},
+//! };
+//! use libnw::keyboard::KeyboardTimedState;
+//!
+//! #[derive(Clone)]
+//! enum MenuAction {
+//!     Play,
+//!     Volume(i32),
+//!     Quit,
+//! }
+//!
+//! struct MenuState {
+//!     ui: Ui<MenuAction>,
+//!     volume: i32,
+//! }
+//!
+//! impl MenuState {
+//!     fn new() -> Self {
+//!         let ui = Ui::centered(Panel::new(
+//!             "Golf",
+//!             List::new()
+//!                 .with(Button::new("Play", MenuAction::Play))
+//!                 .with(Slider::new("Volume", 0, 10, 5).on_change(MenuAction::Volume))
+//!                 .with(Button::new("Quit", MenuAction::Quit)),
+//!         ))
+//!         .on_back(MenuAction::Quit);
+//!         Self { ui, volume: 5 }
+//!     }
+//! }
+//!
+//! impl State for MenuState {
+//!     fn update(&mut self, keyboard: &KeyboardTimedState, _ctx: &FrameContext) -> StackAction {
+//!         match self.ui.update(keyboard) {
+//!             Some(MenuAction::Play) => StackAction::Push(Box::new(MenuState::new())),
+//!             Some(MenuAction::Volume(volume)) => {
+//!                 self.volume = volume;
+//!                 StackAction::Nop
+//!             }
+//!             Some(MenuAction::Quit) => StackAction::Pop(()),
+//!             None => StackAction::Nop,
+//!         }
+//!     }
+//!
+//!     fn render(&mut self, _alpha: f32) {
+//!         self.ui.render();
+//!     }
+//! }
+//! ```
+
+extern crate alloc;
+
+mod containers;
+mod widgets;
+
+pub use containers::{List, Panel};
+pub use widgets::{Button, Checkbox, Label, Slider, Spinner};
+
+use alloc::boxed::Box;
+use libnw::{
+    display::{self, Color, Font, Rect, SCREEN_HEIGHT, SCREEN_WIDTH},
+    keyboard::{KeyboardTimedState, RawKey},
+};
+
+use crate::AsAny;
+
+/// The space between the border of a widget and its content, in pixels.
+pub const PADDING: u16 = 4;
+
+/// The colors and the font of the widgets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub font: Font<'static>,
+    pub background: Color,
+    pub foreground: Color,
+    /// The background of the focused widget.
+    pub focus_background: Color,
+    /// The text of the focused widget.
+    pub focus_foreground: Color,
+    /// The filled part of sliders and checkboxes.
+    pub accent: Color,
+}
+
+impl Theme {
+    /// Black on white, the focus in blue.
+    pub const DEFAULT: Theme = Theme {
+        font: Font::Large,
+        background: Color::WHITE,
+        foreground: Color::BLACK,
+        focus_background: Color::BLUE,
+        focus_foreground: Color::WHITE,
+        accent: Color::BLUE,
+    };
+
+    /// The background and the text color of a widget.
+    pub fn colors(&self, focused: bool) -> (Color, Color) {
+        match focused {
+            true => (self.focus_background, self.focus_foreground),
+            false => (self.background, self.foreground),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What a widget is asked to handle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// OK or EXE.
+    Ok,
+    Back,
+}
+
+impl Event {
+    /// The event of the keys pressed, with the arrows, OK / EXE and back.
+    ///
+    /// The arrows repeat when held.
+    pub fn from_keyboard(keyboard_state: &KeyboardTimedState) -> Option<Event> {
+        let repeated = [
+            (RawKey::Up, Event::Up),
+            (RawKey::Down, Event::Down),
+            (RawKey::Left, Event::Left),
+            (RawKey::Right, Event::Right),
+        ];
+        let just_pressed = [
+            (RawKey::Ok, Event::Ok),
+            (RawKey::Exe, Event::Ok),
+            (RawKey::Back, Event::Back),
+        ];
+        repeated
+            .into_iter()
+            .find(|(key, _)| keyboard_state.is_key_repeated(*key))
+            .or_else(|| {
+                just_pressed
+                    .into_iter()
+                    .find(|(key, _)| keyboard_state.is_key_just_pressed(*key))
+            })
+            .map(|(_, event)| event)
+    }
+}
+
+/// Turns the keys pressed into the event sent to the widgets, see [`Ui::with_events`].
+pub type EventMap = Box<dyn Fn(&KeyboardTimedState) -> Option<Event>>;
+
+/// How a widget handled an event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Response<A> {
+    /// The event is not for this widget, its parent may use it.
+    Ignored,
+    /// The widget changed, it must be drawn again.
+    Handled,
+    /// The widget changed and emits an action.
+    Action(A),
+}
+
+/// Something drawn in a [`Ui`], emitting actions of type `A`.
+pub trait Widget<A>: AsAny {
+    /// The size the widget needs (width, height) in pixels.
+    ///
+    /// It may be drawn wider, never narrower.
+    fn size(&self, theme: &Theme) -> (u16, u16);
+
+    /// Checks if the widget can get the focus.
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    /// Handles an event, only called when the widget has the focus.
+    fn handle(&mut self, _event: Event) -> Response<A> {
+        Response::Ignored
+    }
+
+    /// Draws the whole widget in `rect`, background included.
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme);
+}
+
+/// The root of a widget tree, reading the keyboard and drawing the widgets when they change.
+pub struct Ui<A> {
+    root: Box<dyn Widget<A>>,
+    /// Where the widgets are drawn, `None` to center them with their size.
+    rect: Option<Rect>,
+    theme: Theme,
+    /// The color of the rest of the screen, `None` to keep what is drawn there.
+    screen: Option<Color>,
+    /// The action emitted by back, when no widget used it.
+    back: Option<A>,
+    /// Whether to wait for the vblank before drawing.
+    vblank: bool,
+    /// The event of the keys pressed, [`Event::from_keyboard`] by default.
+    events: EventMap,
+    needs_redraw: bool,
+}
+
+impl<A: Clone + 'static> Ui<A> {
+    /// Creates a ui drawn in `rect`.
+    pub fn new(rect: Rect, root: impl Widget<A>) -> Self {
+        Self {
+            root: Box::new(root),
+            rect: Some(rect),
+            theme: Theme::DEFAULT,
+            screen: None,
+            back: None,
+            vblank: true,
+            events: Box::new(Event::from_keyboard),
+            needs_redraw: true,
+        }
+    }
+
+    /// Creates a ui drawn in the middle of the screen, as large as its root needs.
+    pub fn centered(root: impl Widget<A>) -> Self {
+        Self {
+            rect: None,
+            ..Self::new(Rect::SCREEN, root)
+        }
+    }
+
+    /// Sets the theme.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Clears the rest of the screen with `color` before drawing everything.
+    pub fn clear_screen(mut self, color: Color) -> Self {
+        self.screen = Some(color);
+        self
+    }
+
+    /// Emits `action` when back is pressed and no widget used it.
+    pub fn on_back(mut self, action: A) -> Self {
+        self.back = Some(action);
+        self
+    }
+
+    /// Draws without waiting for the vblank, for a ui drawn over a state that already waited.
+    pub fn without_vblank(mut self) -> Self {
+        self.vblank = false;
+        self
+    }
+
+    /// Reads the events with `events` instead of the default keys, to follow the bindings
+    /// of the app.
+    ///
+    /// # Example :
+    /// ```
+    /// use graphmgr::ui::{Event, Label, Ui};
+    /// use libnw::keyboard::RawKey;
+    ///
+    /// // EXE doesn't confirm anymore
+    /// let ui: Ui<()> = Ui::centered(Label::new("Hello")).with_events(|keyboard| {
+    ///     match keyboard.is_key_just_pressed(RawKey::Exe) {
+    ///         true => None,
+    ///         false => Event::from_keyboard(keyboard),
+    ///     }
+    /// });
+    /// ```
+    pub fn with_events(
+        mut self,
+        events: impl Fn(&KeyboardTimedState) -> Option<Event> + 'static,
+    ) -> Self {
+        self.events = Box::new(events);
+        self
+    }
+
+    /// The root widget.
+    ///
+    /// Use `as_any` to get the concrete widget back.
+    pub fn root(&self) -> &dyn Widget<A> {
+        self.root.as_ref()
+    }
+
+    /// The root widget, to change it.
+    ///
+    /// The ui is drawn again on the next render.
+    pub fn root_mut(&mut self) -> &mut dyn Widget<A> {
+        self.needs_redraw = true;
+        self.root.as_mut()
+    }
+
+    /// Where the widgets are drawn.
+    pub fn rect(&self) -> Rect {
+        self.rect.unwrap_or_else(|| {
+            let (width, height) = self.root.size(&self.theme);
+            let (width, height) = (width.min(SCREEN_WIDTH), height.min(SCREEN_HEIGHT));
+            Rect::new(
+                (SCREEN_WIDTH - width) / 2,
+                (SCREEN_HEIGHT - height) / 2,
+                width,
+                height,
+            )
+        })
+    }
+
+    /// Draws everything on the next render, e.g. after the screen was drawn over.
+    pub fn invalidate(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    /// Sends the event of the keys pressed to the focused widget, see [`Self::with_events`].
+    ///
+    /// Returns the action emitted, if any.
+    pub fn update(&mut self, keyboard_state: &KeyboardTimedState) -> Option<A> {
+        (self.events)(keyboard_state).and_then(|event| self.send(event))
+    }
+
+    /// Sends an event to the focused widget, as if its key was pressed.
+    ///
+    /// Returns the action emitted, if any.
+    pub fn send(&mut self, event: Event) -> Option<A> {
+        match self.root.handle(event) {
+            Response::Ignored if event == Event::Back => self.back.clone(),
+            Response::Ignored => None,
+            Response::Handled => {
+                self.needs_redraw = true;
+                None
+            }
+            Response::Action(action) => {
+                self.needs_redraw = true;
+                Some(action)
+            }
+        }
+    }
+
+    /// Draws the widgets, if they changed since the last render.
+    pub fn render(&mut self) {
+        if !self.needs_redraw {
+            return;
+        }
+        self.needs_redraw = false;
+        if self.vblank {
+            display::eadk::wait_for_vblank();
+        }
+        if let Some(color) = self.screen {
+            display::clear_screen(color);
+        }
+        self.root.render(self.rect(), true, &self.theme);
+    }
+}
@@ -0,0 +1,509 @@
+//! The widgets without children.
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+use libnw::display::{self, Align, Rect, TextStyle, VAlign};
+
+use super::{Event, PADDING, Response, Theme, Widget};
+
+/// The size of the box of a checkbox, and the thickness of a slider.
+const MARK_SIZE: u16 = 10;
+/// The width of the bar of a slider.
+const SLIDER_WIDTH: u16 = 100;
+
+/// The height of a widget showing one line of text.
+fn line_height(theme: &Theme) -> u16 {
+    theme.font.line_height() + 2 * PADDING
+}
+
+/// Draws a line of text in `rect`, vertically centered, after the padding.
+fn draw_line(text: &str, rect: Rect, align: Align, focused: bool, theme: &Theme) {
+    let (background, foreground) = theme.colors(focused);
+    display::draw_text(
+        text,
+        Rect::new(
+            rect.x + PADDING,
+            rect.y,
+            rect.width.saturating_sub(2 * PADDING),
+            rect.height,
+        ),
+        TextStyle::new(theme.font, foreground)
+            .background(background)
+            .align(align, VAlign::Middle),
+    );
+}
+
+/// Some text, wrapped to the width it is drawn with.
+pub struct Label {
+    text: String,
+    align: Align,
+    /// The width the text is wrapped to when measured, `None` to keep its lines.
+    max_width: Option<u16>,
+}
+
+impl Label {
+    /// Creates a label, its text aligned to the left.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: String::from(text),
+            align: Align::Left,
+            max_width: None,
+        }
+    }
+
+    /// Sets how the text is aligned.
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Wraps the text so that the label is never wider than `max_width`, padding included.
+    pub fn max_width(mut self, max_width: u16) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// The text shown.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Changes the text shown.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = String::from(text);
+    }
+}
+
+impl<A> Widget<A> for Label {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        let (width, height) = match self.max_width {
+            Some(max_width) => {
+                let lines = theme
+                    .font
+                    .wrap(&self.text, max_width.saturating_sub(2 * PADDING));
+                theme.font.measure(&lines.join("\n"))
+            }
+            None => theme.font.measure(&self.text),
+        };
+        (width + 2 * PADDING, height + 2 * PADDING)
+    }
+
+    fn render(&self, rect: Rect, _focused: bool, theme: &Theme) {
+        rect.fill(theme.background);
+        display::draw_text(
+            &self.text,
+            Rect::new(
+                rect.x + PADDING,
+                rect.y + PADDING,
+                rect.width.saturating_sub(2 * PADDING),
+                rect.height.saturating_sub(2 * PADDING),
+            ),
+            TextStyle::new(theme.font, theme.foreground)
+                .background(theme.background)
+                .align(self.align, VAlign::Middle),
+        );
+    }
+}
+
+/// Some text emitting an action when OK is pressed.
+pub struct Button<A> {
+    text: String,
+    action: A,
+}
+
+impl<A> Button<A> {
+    /// Creates a button emitting `action`.
+    pub fn new(text: &str, action: A) -> Self {
+        Self {
+            text: String::from(text),
+            action,
+        }
+    }
+}
+
+impl<A: Clone + 'static> Widget<A> for Button<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        (
+            theme.font.line_width(&self.text) + 2 * PADDING,
+            line_height(theme),
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        match event {
+            Event::Ok => Response::Action(self.action.clone()),
+            _ => Response::Ignored,
+        }
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        rect.fill(theme.colors(focused).0);
+        draw_line(&self.text, rect, Align::Center, focused, theme);
+    }
+}
+
+/// A box checked or not, toggled with OK.
+pub struct Checkbox<A> {
+    text: String,
+    checked: bool,
+    on_change: Option<fn(bool) -> A>,
+}
+
+impl<A> Checkbox<A> {
+    /// Creates a checkbox, with its box before `text`.
+    pub fn new(text: &str, checked: bool) -> Self {
+        Self {
+            text: String::from(text),
+            checked,
+            on_change: None,
+        }
+    }
+
+    /// Emits `on_change(checked)` when toggled.
+    pub fn on_change(mut self, on_change: fn(bool) -> A) -> Self {
+        self.on_change = Some(on_change);
+        self
+    }
+
+    /// Checks if the box is checked.
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    /// Checks or unchecks the box, without emitting anything.
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+}
+
+impl<A: 'static> Widget<A> for Checkbox<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        (
+            MARK_SIZE + theme.font.line_width(&self.text) + 3 * PADDING,
+            line_height(theme),
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        if event != Event::Ok {
+            return Response::Ignored;
+        }
+        self.checked = !self.checked;
+        match self.on_change {
+            Some(on_change) => Response::Action(on_change(self.checked)),
+            None => Response::Handled,
+        }
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        let (background, foreground) = theme.colors(focused);
+        rect.fill(background);
+        let mark = Rect::new(
+            rect.x + PADDING,
+            rect.y + rect.height.saturating_sub(MARK_SIZE) / 2,
+            MARK_SIZE,
+            MARK_SIZE,
+        );
+        // a frame, filled if checked
+        mark.fill(foreground);
+        Rect::new(mark.x + 1, mark.y + 1, MARK_SIZE - 2, MARK_SIZE - 2).fill(background);
+        if self.checked {
+            Rect::new(mark.x + 2, mark.y + 2, MARK_SIZE - 4, MARK_SIZE - 4).fill(theme.accent);
+        }
+        let text_x = MARK_SIZE + PADDING;
+        draw_line(
+            &self.text,
+            Rect::new(
+                rect.x + text_x,
+                rect.y,
+                rect.width.saturating_sub(text_x),
+                rect.height,
+            ),
+            Align::Left,
+            focused,
+            theme,
+        );
+    }
+}
+
+/// A number in a range, changed by `step` with left and right.
+struct Value {
+    value: i32,
+    min: i32,
+    max: i32,
+    step: i32,
+}
+
+impl Value {
+    /// A value from `min` to `max` (included), a range ending before `min` only holding `min`.
+    fn new(min: i32, max: i32, value: i32) -> Self {
+        let max = max.max(min);
+        Self {
+            value: value.clamp(min, max),
+            min,
+            max,
+            step: 1,
+        }
+    }
+
+    /// Moves the value by `steps` steps, returning `true` if it changed.
+    fn change(&mut self, steps: i32) -> bool {
+        let value = (self.value + steps * self.step).clamp(self.min, self.max);
+        let changed = value != self.value;
+        self.value = value;
+        changed
+    }
+
+    /// Handles left and right.
+    fn handle<A>(&mut self, event: Event, on_change: Option<fn(i32) -> A>) -> Response<A> {
+        let steps = match event {
+            Event::Left => -1,
+            Event::Right => 1,
+            _ => return Response::Ignored,
+        };
+        if !self.change(steps) {
+            // at a bound, the event is used anyway
+            return Response::Handled;
+        }
+        match on_change {
+            Some(on_change) => Response::Action(on_change(self.value)),
+            None => Response::Handled,
+        }
+    }
+}
+
+/// A number in a range, shown as a bar.
+pub struct Slider<A> {
+    text: String,
+    value: Value,
+    on_change: Option<fn(i32) -> A>,
+}
+
+impl<A> Slider<A> {
+    /// Creates a slider from `min` to `max` (included), moving by 1.
+    pub fn new(text: &str, min: i32, max: i32, value: i32) -> Self {
+        Self {
+            text: String::from(text),
+            value: Value::new(min, max, value),
+            on_change: None,
+        }
+    }
+
+    /// Sets how much left and right move the slider.
+    pub fn step(mut self, step: i32) -> Self {
+        self.value.step = step.max(1);
+        self
+    }
+
+    /// Emits `on_change(value)` when moved.
+    pub fn on_change(mut self, on_change: fn(i32) -> A) -> Self {
+        self.on_change = Some(on_change);
+        self
+    }
+
+    /// The current value.
+    pub fn value(&self) -> i32 {
+        self.value.value
+    }
+
+    /// Changes the value (clamped to the range), without emitting anything.
+    pub fn set_value(&mut self, value: i32) {
+        self.value.value = value.clamp(self.value.min, self.value.max);
+    }
+}
+
+impl<A: 'static> Widget<A> for Slider<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        (
+            theme.font.line_width(&self.text) + SLIDER_WIDTH + 3 * PADDING,
+            line_height(theme),
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        self.value.handle(event, self.on_change)
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        let (background, foreground) = theme.colors(focused);
+        rect.fill(background);
+        draw_line(&self.text, rect, Align::Left, focused, theme);
+        let bar = Rect::new(
+            (rect.x + rect.width).saturating_sub(SLIDER_WIDTH + PADDING),
+            rect.y + rect.height.saturating_sub(MARK_SIZE) / 2,
+            SLIDER_WIDTH,
+            MARK_SIZE,
+        );
+        bar.fill(foreground);
+        let inside = Rect::new(bar.x + 1, bar.y + 1, bar.width - 2, bar.height - 2);
+        inside.fill(background);
+        let Value {
+            value, min, max, ..
+        } = self.value;
+        let filled = match max > min {
+            true => (inside.width as i32 * (value - min) / (max - min)) as u16,
+            false => inside.width,
+        };
+        Rect::new(inside.x, inside.y, filled, inside.height).fill(theme.accent);
+    }
+}
+
+/// A number in a range, shown as `< value >`.
+pub struct Spinner<A> {
+    text: String,
+    value: Value,
+    on_change: Option<fn(i32) -> A>,
+}
+
+impl<A> Spinner<A> {
+    /// Creates a spinner from `min` to `max` (included), moving by 1.
+    pub fn new(text: &str, min: i32, max: i32, value: i32) -> Self {
+        Self {
+            text: String::from(text),
+            value: Value::new(min, max, value),
+            on_change: None,
+        }
+    }
+
+    /// Sets how much left and right change the value.
+    pub fn step(mut self, step: i32) -> Self {
+        self.value.step = step.max(1);
+        self
+    }
+
+    /// Emits `on_change(value)` when changed.
+    pub fn on_change(mut self, on_change: fn(i32) -> A) -> Self {
+        self.on_change = Some(on_change);
+        self
+    }
+
+    /// The current value.
+    pub fn value(&self) -> i32 {
+        self.value.value
+    }
+
+    /// Changes the value (clamped to the range), without emitting anything.
+    pub fn set_value(&mut self, value: i32) {
+        self.value.value = value.clamp(self.value.min, self.value.max);
+    }
+
+    /// The value as shown, with the arrows.
+    fn value_text(&self) -> String {
+        let Value {
+            value, min, max, ..
+        } = self.value;
+        let left = if value > min { '<' } else { ' ' };
+        let right = if value < max { '>' } else { ' ' };
+        format!("{left} {value} {right}")
+    }
+}
+
+impl<A: 'static> Widget<A> for Spinner<A> {
+    fn size(&self, theme: &Theme) -> (u16, u16) {
+        // room for the widest value, sign included
+        let widest = format!("< {} >", self.value.min.min(-self.value.max.abs()));
+        (
+            theme.font.line_width(&self.text) + theme.font.line_width(&widest) + 3 * PADDING,
+            line_height(theme),
+        )
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle(&mut self, event: Event) -> Response<A> {
+        self.value.handle(event, self.on_change)
+    }
+
+    fn render(&self, rect: Rect, focused: bool, theme: &Theme) {
+        rect.fill(theme.colors(focused).0);
+        draw_line(&self.text, rect, Align::Left, focused, theme);
+        draw_line(&self.value_text(), rect, Align::Right, focused, theme);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::Ui;
+
+    fn value(ui: &Ui<i32>) -> i32 {
+        let root = ui.root().as_any();
+        match root.downcast_ref::<Slider<i32>>() {
+            Some(slider) => slider.value(),
+            None => root.downcast_ref::<Spinner<i32>>().unwrap().value(),
+        }
+    }
+
+    #[test]
+    fn sliders_stay_in_their_range() {
+        let mut ui = Ui::centered(Slider::new("Volume", 0, 10, 15).on_change(|value| value));
+        assert_eq!(value(&ui), 10);
+        // at the bound, nothing is emitted
+        assert_eq!(ui.send(Event::Right), None);
+        assert_eq!(value(&ui), 10);
+        assert_eq!(ui.send(Event::Left), Some(9));
+
+        let mut ui = Ui::centered(Slider::new("Volume", 0, 10, 1).step(3).on_change(|v| v));
+        assert_eq!(ui.send(Event::Left), Some(0));
+        assert_eq!(ui.send(Event::Left), None);
+        assert_eq!(ui.send(Event::Right), Some(3));
+        ui.send(Event::Right);
+        ui.send(Event::Right);
+        assert_eq!(ui.send(Event::Right), Some(10));
+    }
+
+    #[test]
+    fn spinners_stay_in_their_range() {
+        let mut ui = Ui::centered(Spinner::new("Players", 1, 4, -2).on_change(|value| value));
+        assert_eq!(value(&ui), 1);
+        assert_eq!(ui.send(Event::Left), None);
+        assert_eq!(ui.send(Event::Right), Some(2));
+        let spinner = ui.root_mut();
+        spinner.handle(Event::Right);
+        spinner.handle(Event::Right);
+        assert_eq!(value(&ui), 4);
+        assert_eq!(ui.send(Event::Right), None);
+    }
+
+    #[test]
+    fn set_value_is_clamped() {
+        let mut slider = Slider::<()>::new("Volume", -5, 5, 0);
+        slider.set_value(100);
+        assert_eq!(slider.value(), 5);
+        let mut spinner = Spinner::<()>::new("Holes", 1, 18, 9);
+        spinner.set_value(i32::MIN);
+        assert_eq!(spinner.value(), 1);
+    }
+
+    #[test]
+    fn an_empty_range_keeps_its_minimum() {
+        let mut ui = Ui::centered(Slider::new("Empty", 3, 1, 2).on_change(|value| value));
+        assert_eq!(value(&ui), 3);
+        assert_eq!(ui.send(Event::Left), None);
+        assert_eq!(ui.send(Event::Right), None);
+    }
+
+    #[test]
+    fn the_other_keys_are_left_to_the_parent() {
+        let mut slider = Slider::<()>::new("Volume", 0, 10, 5);
+        assert_eq!(slider.handle(Event::Up), Response::Ignored);
+        assert_eq!(slider.handle(Event::Ok), Response::Ignored);
+        assert_eq!(slider.handle(Event::Right), Response::Handled);
+        assert_eq!(slider.value(), 6);
+    }
+}
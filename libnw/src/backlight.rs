@@ -60,6 +60,7 @@ pub fn decrement_brightness() -> Result<u8, ()> {
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// Sets the screen brightness.
@@ -71,3 +72,6 @@ pub mod eadk {
         pub safe fn get_brightness() -> u8;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::backlight as eadk;
@@ -7,6 +7,7 @@ pub use eadk::*;
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// Checks whether the battery is currently charging.
@@ -36,3 +37,6 @@ pub mod eadk {
         pub safe fn get_voltage() -> f32;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::battery as eadk;
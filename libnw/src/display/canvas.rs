@@ -0,0 +1,253 @@
+//! Off-screen drawing surfaces.
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+
+use super::{Color, Rect, clip_rect};
+
+/// A heap-allocated grid of pixels, drawn off-screen then pushed to the screen at once.
+///
+/// Beware of the size : each pixel takes 2 bytes, so a canvas the size of the
+/// screen takes 150 KiB.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Canvas {
+    width: u16,
+    height: u16,
+    /// The pixels, from left to right then top to bottom.
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    /// Creates a canvas filled with `color`.
+    pub fn new(width: u16, height: u16, color: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width as usize * height as usize],
+        }
+    }
+
+    /// Creates a canvas from a copy of the screen in `rect`.
+    pub fn from_screen(rect: Rect) -> Self {
+        Self {
+            width: rect.width,
+            height: rect.height,
+            pixels: rect.get_pixels(),
+        }
+    }
+
+    /// The width of the canvas in pixels.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the canvas in pixels.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The rect covering the whole canvas, at (0, 0).
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// The pixels, from left to right then top to bottom.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// The pixels, from left to right then top to bottom.
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    /// Retrieves the color of a pixel, if it is inside the canvas.
+    pub fn get_pixel(&self, x: u16, y: u16) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[self.index(x, y)])
+        } else {
+            None
+        }
+    }
+
+    /// Colors a pixel, does nothing if it is outside the canvas.
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: Color) {
+        if x < self.width && y < self.height {
+            let index = self.index(x, y);
+            self.pixels[index] = color;
+        }
+    }
+
+    /// Fills the whole canvas with `color`.
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    /// Fills `rect` with `color`, clipped to the canvas.
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let rect = self.clip(rect.x as i32, rect.y as i32, rect.width, rect.height);
+        for y in rect.y..rect.y + rect.height {
+            let start = self.index(rect.x, y);
+            self.pixels[start..start + rect.width as usize].fill(color);
+        }
+    }
+
+    /// Fills a rect given in signed coordinates with `color`, clipped to the canvas.
+    ///
+    /// Handy to draw shapes partially outside of the canvas.
+    pub fn fill_rect_clipped(&mut self, x: i32, y: i32, width: u16, height: u16, color: Color) {
+        let rect = self.clip(x, y, width, height);
+        self.fill_rect(rect, color);
+    }
+
+    /// Copies the pixels of `self` in `src` onto `dst`, with the top-left corner at (`x`, `y`).
+    ///
+    /// Everything is clipped: `src` to `self`, and the destination to `dst`.
+    pub fn blit(&self, src: Rect, dst: &mut Canvas, x: i32, y: i32) {
+        let src = self.clip(src.x as i32, src.y as i32, src.width, src.height);
+        // where src lands in dst, then back to the part of src that is visible
+        let target = dst.clip(x, y, src.width, src.height);
+        if target.area() == 0 {
+            return;
+        }
+        let src_x = src.x as i32 + target.x as i32 - x;
+        let src_y = src.y as i32 + target.y as i32 - y;
+        for row in 0..target.height {
+            let from = self.index(src_x as u16, src_y as u16 + row);
+            let to = dst.index(target.x, target.y + row);
+            dst.pixels[to..to + target.width as usize]
+                .copy_from_slice(&self.pixels[from..from + target.width as usize]);
+        }
+    }
+
+    /// Copies the whole canvas onto `dst`, with the top-left corner at (`x`, `y`).
+    pub fn blit_all(&self, dst: &mut Canvas, x: i32, y: i32) {
+        self.blit(self.bounds(), dst, x, y);
+    }
+
+    /// Pushes the pixels in `rect` to the screen, at the same position.
+    ///
+    /// The canvas is considered to be aligned with the top-left corner of the screen.
+    /// `rect` is clipped to the canvas and to the screen.
+    pub fn present(&self, rect: Rect) {
+        let rect = self.clip(rect.x as i32, rect.y as i32, rect.width, rect.height);
+        let rect =
+            Rect::screen_space_clipping(rect.x as i32, rect.y as i32, rect.width, rect.height);
+        self.push_to_screen(rect, rect.x, rect.y);
+    }
+
+    /// Pushes the whole canvas to the screen, with its top-left corner at (`x`, `y`).
+    ///
+    /// The part outside of the screen is dropped.
+    pub fn present_at(&self, x: i32, y: i32) {
+        let target = Rect::screen_space_clipping(x, y, self.width, self.height);
+        let src = Rect::new(
+            (target.x as i32 - x) as u16,
+            (target.y as i32 - y) as u16,
+            target.width,
+            target.height,
+        );
+        self.push_to_screen(src, target.x, target.y);
+    }
+
+    /// Pushes the (already clipped) `src` rect of the canvas to the screen at (`x`, `y`).
+    fn push_to_screen(&self, src: Rect, x: u16, y: u16) {
+        if src.area() == 0 {
+            return;
+        }
+        let target = Rect::new(x, y, src.width, src.height);
+        if src.x == 0 && src.width == self.width {
+            // the rows are contiguous, no need to copy
+            let start = self.index(0, src.y);
+            target.fill_with_buf(&self.pixels[start..start + src.area() as usize]);
+        } else {
+            let mut buf = Vec::with_capacity(src.area() as usize);
+            for row in src.y..src.y + src.height {
+                let start = self.index(src.x, row);
+                buf.extend_from_slice(&self.pixels[start..start + src.width as usize]);
+            }
+            target.fill_with_buf(&buf);
+        }
+    }
+
+    /// Clips a rect in signed coordinates to the canvas.
+    fn clip(&self, x: i32, y: i32, width: u16, height: u16) -> Rect {
+        clip_rect(x, y, width, height, self.width, self.height)
+    }
+
+    /// The index of the pixel at (`x`, `y`) in `pixels`.
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3×2 canvas with a different color on each pixel.
+    fn source() -> Canvas {
+        let mut canvas = Canvas::new(3, 2, Color::BLACK);
+        for (i, pixel) in canvas.pixels_mut().iter_mut().enumerate() {
+            *pixel = Color(i as u16 + 1);
+        }
+        canvas
+    }
+
+    /// The raw colors of `canvas`, row by row.
+    fn rows(canvas: &Canvas) -> Vec<Vec<u16>> {
+        canvas
+            .pixels()
+            .chunks(canvas.width() as usize)
+            .map(|row| row.iter().map(|color| color.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn blit_copies_at_the_offset() {
+        let mut dst = Canvas::new(4, 3, Color::BLACK);
+        source().blit_all(&mut dst, 1, 1);
+        assert_eq!(rows(&dst), [[0, 0, 0, 0], [0, 1, 2, 3], [0, 4, 5, 6]]);
+    }
+
+    #[test]
+    fn blit_clips_negative_offsets() {
+        let mut dst = Canvas::new(4, 3, Color::BLACK);
+        source().blit_all(&mut dst, -1, -1);
+        assert_eq!(rows(&dst), [[5, 6, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn blit_clips_past_the_edges() {
+        let mut dst = Canvas::new(4, 3, Color::BLACK);
+        source().blit_all(&mut dst, 2, 2);
+        assert_eq!(rows(&dst), [[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 1, 2]]);
+    }
+
+    #[test]
+    fn blit_clips_the_source_rect() {
+        let mut dst = Canvas::new(4, 3, Color::BLACK);
+        source().blit(Rect::new(1, 1, 5, 5), &mut dst, -1, 0);
+        assert_eq!(rows(&dst), [[6, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn blit_fully_outside_does_nothing() {
+        let mut dst = Canvas::new(4, 3, Color::BLACK);
+        let src = source();
+        src.blit_all(&mut dst, -3, 0);
+        src.blit_all(&mut dst, 4, 0);
+        src.blit_all(&mut dst, 0, 3);
+        src.blit_all(&mut dst, i32::MIN, i32::MAX);
+        assert!(dst.pixels().iter().all(|color| *color == Color::BLACK));
+    }
+
+    #[test]
+    fn fill_rect_clipped_stays_inside() {
+        let mut canvas = Canvas::new(3, 2, Color::BLACK);
+        canvas.fill_rect_clipped(-2, 1, 4, 10, Color(7));
+        assert_eq!(rows(&canvas), [[0, 0, 0], [7, 7, 0]]);
+        assert_eq!(canvas.get_pixel(3, 0), None);
+    }
+}
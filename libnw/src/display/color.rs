@@ -0,0 +1,256 @@
+//! Color math: blending, brightness, HSV and dithering.
+//!
+//! Channels are handled with 8 bits of precision, then packed back into RGB565.
+
+use super::Color;
+
+/// The 4 × 4 Bayer matrix, with thresholds from 0 to 15.
+const BAYER_4X4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// The dithering threshold of a pixel, from 0 to 15.
+fn bayer(x: u16, y: u16) -> u8 {
+    BAYER_4X4[(y as usize % 4) * 4 + x as usize % 4]
+}
+
+impl Color {
+    /// The raw RGB565 value.
+    pub const fn rgb565(self) -> u16 {
+        self.0
+    }
+
+    /// Mixes two colors, `t` going from 0 (only `self`) to 255 (only `other`).
+    pub fn lerp(self, other: Color, t: u8) -> Color {
+        let (r0, g0, b0) = self.separate_rgb();
+        let (r1, g1, b1) = other.separate_rgb();
+        let mix = |a: u8, b: u8| (a as i32 + (b as i32 - a as i32) * t as i32 / 255) as u8;
+        Color::from_rgb(mix(r0, r1), mix(g0, g1), mix(b0, b1))
+    }
+
+    /// Draws `self` over `background` with an opacity of `alpha` (0 is invisible, 255 is opaque).
+    pub fn over(self, background: Color, alpha: u8) -> Color {
+        background.lerp(self, alpha)
+    }
+
+    /// Mixes the color with black, by `amount` (0 is unchanged, 255 is black).
+    pub fn darken(self, amount: u8) -> Color {
+        self.lerp(Color::BLACK, amount)
+    }
+
+    /// Mixes the color with white, by `amount` (0 is unchanged, 255 is white).
+    pub fn lighten(self, amount: u8) -> Color {
+        self.lerp(Color::WHITE, amount)
+    }
+
+    /// Multiplies every channel by `factor / 256`, saturating.
+    ///
+    /// 128 halves the brightness, 256 keeps the color unchanged and 512 doubles it.
+    pub fn scale_brightness(self, factor: u16) -> Color {
+        let (r, g, b) = self.separate_rgb();
+        let scale = |c: u8| (c as u32 * factor as u32 / 256).min(255) as u8;
+        Color::from_rgb(scale(r), scale(g), scale(b))
+    }
+
+    /// The perceived brightness, from 0 to 255.
+    pub fn luminance(self) -> u8 {
+        let (r, g, b) = self.separate_rgb();
+        ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+    }
+
+    /// Creates a color from a hue (in degrees), a saturation and a value (0 to 255).
+    pub fn from_hsv(hue: u16, saturation: u8, value: u8) -> Color {
+        if saturation == 0 {
+            return Color::from_rgb(value, value, value);
+        }
+        let hue = hue % 360;
+        let (s, v) = (saturation as u32, value as u32);
+        // position inside the 60° sector, from 0 to 255
+        let rem = (hue % 60) as u32 * 255 / 60;
+        let p = (v * (255 - s) / 255) as u8;
+        let q = (v * (255 - s * rem / 255) / 255) as u8;
+        let t = (v * (255 - s * (255 - rem) / 255) / 255) as u8;
+        let v = value;
+        let (r, g, b) = match hue / 60 {
+            0 => (v, t, p),
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            _ => (v, p, q),
+        };
+        Color::from_rgb(r, g, b)
+    }
+
+    /// Separates the color into a hue (in degrees), a saturation and a value (0 to 255).
+    pub fn to_hsv(self) -> (u16, u8, u8) {
+        let (r, g, b) = self.separate_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = (max - min) as i32;
+        if delta == 0 {
+            return (0, 0, max);
+        }
+        let saturation = (delta * 255 / max as i32) as u8;
+        let (r, g, b) = (r as i32, g as i32, b as i32);
+        let hue = if max as i32 == r {
+            60 * (g - b) / delta
+        } else if max as i32 == g {
+            120 + 60 * (b - r) / delta
+        } else {
+            240 + 60 * (r - g) / delta
+        };
+        (hue.rem_euclid(360) as u16, saturation, max)
+    }
+
+    /// Picks `self` or `other` for the pixel at (`x`, `y`) so that, on average,
+    /// a proportion of `t / 255` pixels are `other`.
+    ///
+    /// Handy to fake a blend without reading the screen, like a shadow.
+    pub fn dither(self, other: Color, t: u8, x: u16, y: u16) -> Color {
+        if t > bayer(x, y) * 16 + 8 {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// Creates a color from 8-bit channels, dithered for the pixel at (`x`, `y`)
+    /// to hide the banding of RGB565.
+    pub fn from_rgb_dithered(r: u8, g: u8, b: u8, x: u16, y: u16) -> Color {
+        let threshold = bayer(x, y);
+        // red and blue lose 3 bits (steps of 8), green loses 2 bits (steps of 4)
+        let r = r.saturating_add(threshold / 2);
+        let g = g.saturating_add(threshold / 4);
+        let b = b.saturating_add(threshold / 2);
+        Color::from_rgb(r, g, b)
+    }
+}
+
+/// Named colors and ready-made palettes.
+pub mod palette {
+    use super::Color;
+
+    /// Yellow (255, 255, 0)
+    pub const YELLOW: Color = Color::from_rgb(255, 255, 0);
+    /// Cyan (0, 255, 255)
+    pub const CYAN: Color = Color::from_rgb(0, 255, 255);
+    /// Magenta (255, 0, 255)
+    pub const MAGENTA: Color = Color::from_rgb(255, 0, 255);
+    /// Orange (255, 165, 0)
+    pub const ORANGE: Color = Color::from_rgb(255, 165, 0);
+    /// Medium grey (128, 128, 128)
+    pub const GREY: Color = Color::from_rgb(128, 128, 128);
+    /// Brown (139, 69, 19)
+    pub const BROWN: Color = Color::from_rgb(139, 69, 19);
+    /// Sand (230, 200, 130)
+    pub const SAND: Color = Color::from_rgb(230, 200, 130);
+    /// Water (40, 110, 220)
+    pub const WATER: Color = Color::from_rgb(40, 110, 220);
+
+    /// 5 greys, from black to white.
+    pub const GREYS: [Color; 5] = [
+        Color::BLACK,
+        Color::from_rgb(64, 64, 64),
+        GREY,
+        Color::from_rgb(192, 192, 192),
+        Color::WHITE,
+    ];
+
+    /// The 16 colors of the PICO-8 fantasy console.
+    pub const PICO8: [Color; 16] = [
+        Color::from_rgb(0, 0, 0),
+        Color::from_rgb(29, 43, 83),
+        Color::from_rgb(126, 37, 83),
+        Color::from_rgb(0, 135, 81),
+        Color::from_rgb(171, 82, 54),
+        Color::from_rgb(95, 87, 79),
+        Color::from_rgb(194, 195, 199),
+        Color::from_rgb(255, 241, 232),
+        Color::from_rgb(255, 0, 77),
+        Color::from_rgb(255, 163, 0),
+        Color::from_rgb(255, 236, 39),
+        Color::from_rgb(0, 228, 54),
+        Color::from_rgb(41, 173, 255),
+        Color::from_rgb(131, 118, 156),
+        Color::from_rgb(255, 119, 168),
+        Color::from_rgb(255, 204, 170),
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A color on every channel, neither black nor white.
+    const TEAL: Color = Color::from_rgb(0x18, 0x9C, 0xF8);
+
+    #[test]
+    fn lerp_ends_on_each_color() {
+        assert_eq!(TEAL.lerp(Color::RED, 0), TEAL);
+        assert_eq!(TEAL.lerp(Color::RED, 255), Color::RED);
+        assert_eq!(
+            Color::BLACK.lerp(Color::WHITE, 128),
+            Color::from_rgb(128, 128, 128)
+        );
+        assert_eq!(TEAL.over(Color::BLACK, 0), Color::BLACK);
+        assert_eq!(TEAL.over(Color::BLACK, 255), TEAL);
+        assert_eq!(TEAL.darken(255), Color::BLACK);
+        assert_eq!(TEAL.lighten(255), Color::WHITE);
+    }
+
+    #[test]
+    fn primaries_round_trip_through_hsv() {
+        let primaries = [
+            (Color::RED, 0),
+            (palette::YELLOW, 60),
+            (Color::GREEN, 120),
+            (palette::CYAN, 180),
+            (Color::BLUE, 240),
+            (palette::MAGENTA, 300),
+        ];
+        for (color, hue) in primaries {
+            assert_eq!(color.to_hsv(), (hue, 255, 255), "{color:?}");
+            assert_eq!(Color::from_hsv(hue, 255, 255), color, "{hue}°");
+            assert_eq!(Color::from_hsv(hue + 360, 255, 255), color, "{hue}° + 360°");
+        }
+        assert_eq!(Color::from_hsv(42, 0, 128), Color::from_rgb(128, 128, 128));
+        assert_eq!(Color::WHITE.to_hsv(), (0, 0, 255));
+    }
+
+    #[test]
+    fn dithering_at_the_ends_picks_a_single_color() {
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(TEAL.dither(Color::RED, 0, x, y), TEAL);
+                assert_eq!(TEAL.dither(Color::RED, 255, x, y), Color::RED);
+            }
+        }
+        // half of the pixels in between
+        let reds = (0..16)
+            .filter(|i| TEAL.dither(Color::RED, 128, i % 4, i / 4) == Color::RED)
+            .count();
+        assert_eq!(reds, 8);
+    }
+
+    #[test]
+    fn brightness_saturates() {
+        assert_eq!(TEAL.scale_brightness(256), TEAL);
+        assert_eq!(TEAL.scale_brightness(0), Color::BLACK);
+        assert_eq!(
+            Color::from_rgb(200, 64, 16).scale_brightness(512),
+            Color::from_rgb(255, 128, 32)
+        );
+        assert_eq!(Color::WHITE.scale_brightness(u16::MAX), Color::WHITE);
+        assert_eq!(
+            Color::from_rgb(200, 200, 200).scale_brightness(128),
+            Color::from_rgb(100, 100, 100)
+        );
+    }
+
+    #[test]
+    fn luminance_goes_from_black_to_white() {
+        assert_eq!(Color::BLACK.luminance(), 0);
+        assert_eq!(Color::WHITE.luminance(), 255);
+        assert!(Color::GREEN.luminance() > Color::RED.luminance());
+        assert!(Color::RED.luminance() > Color::BLUE.luminance());
+    }
+}
@@ -0,0 +1,166 @@
+//! Tracking of the parts of the screen that need to be redrawn.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use super::Rect;
+
+/// The parts of the screen that changed since the last frame.
+///
+/// Each frame, mark the previous and new bounds of everything that moved or changed,
+/// then repaint only the `rects` and `clear` the region.
+/// Overlapping rects are merged together, so no pixel is repainted twice.
+///
+/// # Example :
+/// ```
+/// use libnw::display::{Color, DirtyRegion, Rect};
+///
+/// let mut dirty = DirtyRegion::new();
+/// // the ball moved a bit to the right
+/// dirty.mark_moved(Some(Rect::new(10, 10, 4, 4)), Some(Rect::new(12, 10, 4, 4)));
+/// for rect in dirty.take() {
+///     rect.fill(Color::WHITE);
+/// }
+/// assert!(dirty.is_empty());
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct DirtyRegion {
+    rects: Vec<Rect>,
+}
+
+impl DirtyRegion {
+    /// Above this number of rects, they are all merged into their bounding box,
+    /// pushing many small rects being slower than pushing a bigger one.
+    pub const MAX_RECTS: usize = 8;
+
+    /// Creates an empty region.
+    pub fn new() -> Self {
+        Self { rects: Vec::new() }
+    }
+
+    /// Marks `rect` as needing a redraw.
+    ///
+    /// It is merged with every rect it overlaps.
+    pub fn mark(&mut self, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        let mut rect = rect;
+        // merging may make the rect overlap others, so loop until it doesn't
+        while let Some(i) = self.rects.iter().position(|other| other.intersects(&rect)) {
+            rect = rect.union(&self.rects.swap_remove(i));
+        }
+        self.rects.push(rect);
+        if self.rects.len() > Self::MAX_RECTS {
+            let bounds = self.bounds();
+            self.rects.clear();
+            self.rects.push(bounds);
+        }
+    }
+
+    /// Marks both the old and the new bounds of something, if it moved or appeared or vanished.
+    pub fn mark_moved(&mut self, old: Option<Rect>, new: Option<Rect>) {
+        if old != new {
+            if let Some(old) = old {
+                self.mark(old);
+            }
+            if let Some(new) = new {
+                self.mark(new);
+            }
+        }
+    }
+
+    /// Marks the whole screen as needing a redraw.
+    pub fn mark_all(&mut self) {
+        self.rects.clear();
+        self.rects.push(Rect::SCREEN);
+    }
+
+    /// Checks if there is nothing to redraw.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// The rects to redraw, none of them overlapping.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// The smallest rect containing every rect to redraw.
+    pub fn bounds(&self) -> Rect {
+        self.rects
+            .iter()
+            .fold(Rect::new(0, 0, 0, 0), |bounds, rect| bounds.union(rect))
+    }
+
+    /// Forgets every rect, once they have been redrawn.
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    /// Returns the rects to redraw and clears the region.
+    pub fn take(&mut self) -> Vec<Rect> {
+        core::mem::take(&mut self.rects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separate_rects_are_kept_apart() {
+        let mut dirty = DirtyRegion::new();
+        dirty.mark(Rect::new(0, 0, 2, 2));
+        dirty.mark(Rect::new(2, 0, 2, 2));
+        dirty.mark(Rect::new(0, 0, 0, 5));
+        assert_eq!(
+            dirty.rects(),
+            [Rect::new(0, 0, 2, 2), Rect::new(2, 0, 2, 2)]
+        );
+        assert_eq!(dirty.bounds(), Rect::new(0, 0, 4, 2));
+    }
+
+    #[test]
+    fn overlapping_rects_are_merged() {
+        let mut dirty = DirtyRegion::new();
+        dirty.mark(Rect::new(0, 0, 4, 4));
+        dirty.mark(Rect::new(2, 2, 4, 4));
+        assert_eq!(dirty.rects(), [Rect::new(0, 0, 6, 6)]);
+    }
+
+    #[test]
+    fn merging_repeats_until_nothing_overlaps() {
+        let mut dirty = DirtyRegion::new();
+        dirty.mark(Rect::new(0, 0, 2, 2));
+        dirty.mark(Rect::new(10, 0, 2, 2));
+        // overlaps the first rect only, but their union reaches the second one
+        dirty.mark(Rect::new(1, 1, 2, 2));
+        dirty.mark(Rect::new(2, 0, 9, 1));
+        assert_eq!(dirty.rects(), [Rect::new(0, 0, 12, 3)]);
+    }
+
+    #[test]
+    fn too_many_rects_collapse_into_their_bounds() {
+        let mut dirty = DirtyRegion::new();
+        for i in 0..DirtyRegion::MAX_RECTS as u16 {
+            dirty.mark(Rect::new(i * 10, i, 2, 2));
+        }
+        assert_eq!(dirty.rects().len(), DirtyRegion::MAX_RECTS);
+        dirty.mark(Rect::new(100, 50, 2, 2));
+        assert_eq!(dirty.rects(), [Rect::new(0, 0, 102, 52)]);
+    }
+
+    #[test]
+    fn only_moved_things_are_marked() {
+        let mut dirty = DirtyRegion::new();
+        let rect = Rect::new(5, 5, 2, 2);
+        dirty.mark_moved(Some(rect), Some(rect));
+        dirty.mark_moved(None, None);
+        assert!(dirty.is_empty());
+        dirty.mark_moved(None, Some(rect));
+        assert_eq!(dirty.take(), [rect]);
+        assert!(dirty.is_empty());
+    }
+}
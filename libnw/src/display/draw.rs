@@ -0,0 +1,424 @@
+//! Vector drawing primitives: lines, circles, ellipses and convex polygons.
+//!
+//! Everything is drawn with horizontal or vertical runs of `push_rect_uniform`,
+//! which is much faster than drawing pixel by pixel.
+
+use super::{Color, Rect};
+
+/// Draws shapes on the screen, clipped to a rect.
+///
+/// Coordinates are signed so shapes may be partially (or totally) off-screen.
+///
+/// # Example :
+/// ```
+/// use libnw::display::{Color, Painter};
+///
+/// let painter = Painter::screen();
+/// painter.fill_circle(160, 120, 20, Color::WHITE);
+/// painter.line(0, 0, 319, 239, 3, Color::BLUE);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Painter {
+    clip: Rect,
+    /// The opacity of everything drawn, 255 being opaque.
+    alpha: u8,
+}
+
+impl Painter {
+    /// Creates a painter that only draws inside `clip`.
+    pub fn new(clip: Rect) -> Self {
+        Self {
+            clip: clip
+                .intersection(&Rect::SCREEN)
+                .unwrap_or(Rect::new(0, 0, 0, 0)),
+            alpha: 255,
+        }
+    }
+
+    /// Creates a painter drawing on the whole screen.
+    pub fn screen() -> Self {
+        Self::new(Rect::SCREEN)
+    }
+
+    /// Makes everything drawn translucent, blended over the screen with an opacity of `alpha`.
+    ///
+    /// Only filled shapes should be drawn translucent: the runs of outlines and
+    /// thick lines may overlap, and be blended twice.
+    pub fn with_alpha(self, alpha: u8) -> Self {
+        Self { alpha, ..self }
+    }
+
+    /// The rect outside of which nothing is drawn.
+    pub fn clip(&self) -> Rect {
+        self.clip
+    }
+
+    /// Fills a rect given in signed coordinates.
+    pub fn fill_rect(&self, x: i32, y: i32, width: u16, height: u16, color: Color) {
+        let rect = Rect::screen_space_clipping(x, y, width, height);
+        if self.alpha == 255 {
+            rect.fill_clipped(self.clip, color);
+        } else if let Some(rect) = rect.intersection(&self.clip) {
+            rect.blend(color, self.alpha);
+        }
+    }
+
+    /// Colors a pixel.
+    pub fn pixel(&self, x: i32, y: i32, color: Color) {
+        self.fill_rect(x, y, 1, 1, color);
+    }
+
+    /// Draws a horizontal run from `x0` to `x1` (both included).
+    pub fn hline(&self, x0: i32, x1: i32, y: i32, color: Color) {
+        let (x0, x1) = self.clip_span(x0.min(x1), x0.max(x1), self.clip.x, self.clip.width);
+        self.fill_rect(x0, y, span_len(x0, x1), 1, color);
+    }
+
+    /// Draws a vertical run from `y0` to `y1` (both included).
+    pub fn vline(&self, x: i32, y0: i32, y1: i32, color: Color) {
+        let (y0, y1) = self.clip_span(y0.min(y1), y0.max(y1), self.clip.y, self.clip.height);
+        self.fill_rect(x, y0, 1, span_len(y0, y1), color);
+    }
+
+    /// Shortens the run from `a` to `b` (`a <= b`) to at most one pixel past each side
+    /// of the clip, from `start` and `len` pixels long, so its length fits in a `u16`.
+    fn clip_span(&self, a: i32, b: i32, start: u16, len: u16) -> (i32, i32) {
+        let (low, high) = (start as i32 - 1, start as i32 + len as i32);
+        (a.clamp(low, high), b.clamp(low, high))
+    }
+
+    /// Draws a line from (`x0`, `y0`) to (`x1`, `y1`), `thickness` pixels wide.
+    ///
+    /// Thin lines use Bresenham's algorithm, thick ones are drawn as a filled quad
+    /// with square ends.
+    pub fn line(&self, x0: i32, y0: i32, x1: i32, y1: i32, thickness: u16, color: Color) {
+        if thickness <= 1 {
+            self.thin_line(x0, y0, x1, y1, color);
+            return;
+        }
+        let (dx, dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+        if dx == 0 && dy == 0 {
+            let half = thickness as i32 / 2;
+            self.fill_rect(x0 - half, y0 - half, thickness, thickness, color);
+            return;
+        }
+        // the length in 1/256 of pixel, so the sides land on the nearest pixel
+        let len = ((dx * dx + dy * dy) as u128 * 256 * 256).isqrt() as i64;
+        // the two sides of the line, along the perpendicular of (dx, dy)
+        // at `thickness - 1` pixels from each other
+        let side = |pixels: i64| {
+            (
+                div_round(-dy * pixels * 256, len) as i32,
+                div_round(dx * pixels * 256, len) as i32,
+            )
+        };
+        let (ax, ay) = side((thickness as i64 - 1) - (thickness as i64 - 1) / 2);
+        let (bx, by) = side(-((thickness as i64 - 1) / 2));
+        self.fill_polygon(
+            &[
+                (x0.saturating_add(ax), y0.saturating_add(ay)),
+                (x1.saturating_add(ax), y1.saturating_add(ay)),
+                (x1.saturating_add(bx), y1.saturating_add(by)),
+                (x0.saturating_add(bx), y0.saturating_add(by)),
+            ],
+            color,
+        );
+    }
+
+    /// Bresenham's line, drawn as runs along the major axis.
+    ///
+    /// Only the part of the line crossing the clip rect along the major axis is walked.
+    fn thin_line(&self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let (dx, dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+        let horizontal = dx.abs() >= dy.abs();
+        // (major, minor) axis coordinates
+        let (major0, minor0, d_major, d_minor, clip_start, clip_len) = if horizontal {
+            (x0 as i64, y0 as i64, dx, dy, self.clip.x, self.clip.width)
+        } else {
+            (y0 as i64, x0 as i64, dy, dx, self.clip.y, self.clip.height)
+        };
+        let steps = d_major.abs();
+        let step = if d_major < 0 { -1 } else { 1 };
+        // the steps landing inside the clip along the major axis
+        let (low, high) = (
+            clip_start as i64 - major0,
+            clip_start as i64 + clip_len as i64 - 1 - major0,
+        );
+        let (first, last) = if step > 0 { (low, high) } else { (-high, -low) };
+        let (first, last) = (first.max(0), last.min(steps));
+        if clip_len == 0 || first > last {
+            return;
+        }
+        let minor_at = |i: i64| match steps {
+            0 => minor0,
+            _ => minor0 + div_round(d_minor * i, steps),
+        };
+        let mut run_start = first;
+        for i in first..=last {
+            let minor = minor_at(i);
+            // a run ends when the minor axis moves
+            if i == last || minor_at(i + 1) != minor {
+                let (a, b) = (
+                    (major0 + step * run_start) as i32,
+                    (major0 + step * i) as i32,
+                );
+                if horizontal {
+                    self.hline(a, b, minor as i32, color);
+                } else {
+                    self.vline(minor as i32, a, b, color);
+                }
+                run_start = i + 1;
+            }
+        }
+    }
+
+    /// Draws the outline of the polygon joining `points`, `thickness` pixels wide.
+    pub fn polygon(&self, points: &[(i32, i32)], thickness: u16, color: Color) {
+        for (i, &(x0, y0)) in points.iter().enumerate() {
+            let (x1, y1) = points[(i + 1) % points.len()];
+            self.line(x0, y0, x1, y1, thickness, color);
+        }
+    }
+
+    /// Fills the convex polygon joining `points`.
+    ///
+    /// Concave polygons are drawn as if they were filled between their leftmost
+    /// and rightmost edges on each row.
+    pub fn fill_polygon(&self, points: &[(i32, i32)], color: Color) {
+        let Some(top) = points.iter().map(|p| p.1).min() else {
+            return;
+        };
+        let bottom = points.iter().map(|p| p.1).max().unwrap_or(top);
+        // no need to go through the rows outside of the clip
+        let top = top.max(self.clip.y as i32);
+        let bottom = bottom.min(self.clip.y as i32 + self.clip.height as i32 - 1);
+        for y in top..=bottom {
+            let mut span: Option<(i32, i32)> = None;
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+                if y < y0.min(y1) || y > y0.max(y1) {
+                    continue;
+                }
+                let (xa, xb) = if y0 == y1 {
+                    (x0.min(x1), x0.max(x1))
+                } else {
+                    let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+                    // between x0 and x1, so it fits back in an i32
+                    let x = (x0 + (y as i64 - y0) * (x1 - x0) / (y1 - y0)) as i32;
+                    (x, x)
+                };
+                span = Some(match span {
+                    Some((left, right)) => (left.min(xa), right.max(xb)),
+                    None => (xa, xb),
+                });
+            }
+            if let Some((left, right)) = span {
+                self.hline(left, right, y, color);
+            }
+        }
+    }
+
+    /// Draws the outline of a triangle.
+    pub fn triangle(&self, p0: (i32, i32), p1: (i32, i32), p2: (i32, i32), color: Color) {
+        self.polygon(&[p0, p1, p2], 1, color);
+    }
+
+    /// Fills a triangle.
+    pub fn fill_triangle(&self, p0: (i32, i32), p1: (i32, i32), p2: (i32, i32), color: Color) {
+        self.fill_polygon(&[p0, p1, p2], color);
+    }
+
+    /// Draws the outline of a circle, 1 pixel thick.
+    pub fn circle(&self, cx: i32, cy: i32, radius: u16, color: Color) {
+        self.ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Fills a circle.
+    ///
+    /// It is `2 * radius + 1` pixels wide.
+    pub fn fill_circle(&self, cx: i32, cy: i32, radius: u16, color: Color) {
+        self.fill_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Draws the outline of an axis-aligned ellipse, 1 pixel thick.
+    pub fn ellipse(&self, cx: i32, cy: i32, rx: u16, ry: u16, color: Color) {
+        for dy in 0..=ry as i32 {
+            let outer = ellipse_half_width(rx, ry, dy);
+            // the outline joins this row to the next one, further from the center
+            let inner = if dy < ry as i32 {
+                ellipse_half_width(rx, ry, dy + 1) + 1
+            } else {
+                -outer
+            }
+            .min(outer);
+            for y in [cy - dy, cy + dy] {
+                if inner <= 0 {
+                    self.hline(cx - outer, cx + outer, y, color);
+                } else {
+                    self.hline(cx - outer, cx - inner, y, color);
+                    self.hline(cx + inner, cx + outer, y, color);
+                }
+                if dy == 0 {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Fills an axis-aligned ellipse.
+    ///
+    /// It is `2 * rx + 1` pixels wide and `2 * ry + 1` pixels high.
+    pub fn fill_ellipse(&self, cx: i32, cy: i32, rx: u16, ry: u16, color: Color) {
+        for dy in 0..=ry as i32 {
+            let half = ellipse_half_width(rx, ry, dy);
+            self.hline(cx - half, cx + half, cy - dy, color);
+            if dy != 0 {
+                self.hline(cx - half, cx + half, cy + dy, color);
+            }
+        }
+    }
+}
+
+/// The number of pixels from `a` to `b` (both included), as a rect side length.
+fn span_len(a: i32, b: i32) -> u16 {
+    (b as i64 - a as i64 + 1).clamp(0, u16::MAX as i64) as u16
+}
+
+/// The half width of the row `dy` pixels away from the center of an ellipse.
+fn ellipse_half_width(rx: u16, ry: u16, dy: i32) -> i32 {
+    if ry == 0 {
+        return rx as i32;
+    }
+    let (rx, ry, dy) = (rx as u64, ry as u64, dy.unsigned_abs() as u64);
+    // (x / rx)² + (dy / ry)² <= 1, with a half pixel of slack for rounder shapes
+    let ry2 = ry * ry + ry;
+    (rx * rx * ry2.saturating_sub(dy * dy) / ry2).isqrt() as i32
+}
+
+/// Divides `num` by `den` (positive), rounding half away from zero.
+fn div_round(num: i64, den: i64) -> i64 {
+    (num + num.signum() * (den / 2)) / den
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    extern crate alloc;
+
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::display::SCREEN_WIDTH;
+    use crate::host;
+
+    /// The (x, y) of every pixel of the screen colored in `color`.
+    fn pixels(color: Color) -> Vec<(i32, i32)> {
+        host::with_framebuffer(|framebuffer| {
+            framebuffer
+                .iter()
+                .enumerate()
+                .filter(|(_, pixel)| **pixel == color)
+                .map(|(i, _)| {
+                    let i = i as i32;
+                    (i % SCREEN_WIDTH as i32, i / SCREEN_WIDTH as i32)
+                })
+                .collect()
+        })
+    }
+
+    /// The smallest rect containing the pixels colored in `color`.
+    fn bounds(color: Color) -> Rect {
+        pixels(color)
+            .iter()
+            .fold(Rect::new(0, 0, 0, 0), |bounds, &(x, y)| {
+                bounds.union(&Rect::new_pixel(x as u16, y as u16))
+            })
+    }
+
+    #[test]
+    fn thin_lines_join_their_endpoints() {
+        host::reset();
+        Painter::screen().line(10, 10, 20, 14, 1, Color::RED);
+        let drawn = pixels(Color::RED);
+        assert_eq!(drawn.len(), 11);
+        assert!(drawn.contains(&(10, 10)) && drawn.contains(&(20, 14)));
+        assert_eq!(bounds(Color::RED), Rect::new(10, 10, 11, 5));
+
+        // the same pixels in both directions
+        Painter::screen().line(20, 14, 10, 10, 1, Color::BLUE);
+        assert_eq!(pixels(Color::BLUE), drawn);
+    }
+
+    #[test]
+    fn steep_lines_have_one_pixel_per_row() {
+        host::reset();
+        Painter::screen().line(5, 30, 8, 10, 1, Color::RED);
+        let drawn = pixels(Color::RED);
+        assert_eq!(drawn.len(), 21);
+        assert!(drawn.contains(&(5, 30)) && drawn.contains(&(8, 10)));
+    }
+
+    #[test]
+    fn lines_are_clipped() {
+        host::reset();
+        let painter = Painter::new(Rect::new(10, 10, 5, 5));
+        painter.line(0, 12, 100, 12, 1, Color::RED);
+        painter.line(
+            -1_000_000_000,
+            -1_000_000_000,
+            1_000_000_000,
+            1_000_000_000,
+            1,
+            Color::BLUE,
+        );
+        assert_eq!(bounds(Color::RED), Rect::new(10, 12, 5, 1));
+        assert_eq!(
+            pixels(Color::BLUE),
+            [(10, 10), (11, 11), (12, 12), (13, 13), (14, 14)]
+        );
+    }
+
+    #[test]
+    fn far_lines_are_drawn_across_the_screen() {
+        host::reset();
+        Painter::screen().line(i32::MIN, 100, i32::MAX, 100, 1, Color::RED);
+        assert_eq!(pixels(Color::RED).len(), SCREEN_WIDTH as usize);
+        Painter::screen().line(-5, -5, -1, -500, 1, Color::BLUE);
+        assert!(pixels(Color::BLUE).is_empty());
+    }
+
+    #[test]
+    fn thick_lines_are_as_wide_as_their_thickness() {
+        for thickness in 2..6 {
+            host::reset();
+            Painter::screen().line(20, 50, 60, 50, thickness, Color::RED);
+            Painter::screen().line(100, 20, 100, 60, thickness, Color::BLUE);
+            assert_eq!(bounds(Color::RED).height, thickness);
+            assert_eq!(bounds(Color::BLUE).width, thickness);
+        }
+    }
+
+    #[test]
+    fn huge_polygons_dont_overflow() {
+        host::reset();
+        Painter::screen().fill_triangle(
+            (-2_000_000_000, -10),
+            (2_000_000_000, -10),
+            (0, 2_000_000_000),
+            Color::RED,
+        );
+        assert_eq!(pixels(Color::RED).len(), SCREEN_WIDTH as usize * 240);
+    }
+
+    #[test]
+    fn ellipses_have_the_documented_size() {
+        host::reset();
+        Painter::screen().fill_ellipse(50, 50, 7, 3, Color::RED);
+        assert_eq!(bounds(Color::RED), Rect::new(43, 47, 15, 7));
+        Painter::screen().ellipse(150, 50, 4, 9, Color::BLUE);
+        assert_eq!(bounds(Color::BLUE), Rect::new(146, 41, 9, 19));
+        // the outline is hollow
+        assert!(!pixels(Color::BLUE).contains(&(150, 50)));
+        Painter::screen().fill_circle(250, 50, 0, Color::GREEN);
+        assert_eq!(pixels(Color::GREEN), [(250, 50)]);
+    }
+}
@@ -0,0 +1,451 @@
+//! Text rendering with the built-in fonts or embedded bitmap fonts,
+//! with measurement, alignment and word-wrapping.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use super::{
+    CHAR_HEIGHT, CHAR_WIDTH, Color, LARGE_CHAR_HEIGHT, LARGE_CHAR_WIDTH, Rect, draw_string,
+};
+
+/// The background of the built-in fonts when none is given, the one of the system apps.
+pub const DEFAULT_BACKGROUND: Color = Color::WHITE;
+
+/// One character of a [`BitmapFont`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph<'a> {
+    /// The character drawn by this glyph.
+    pub ch: char,
+    /// The width of the glyph in pixels, its height being the one of the font.
+    pub width: u8,
+    /// One bit per pixel, from left to right then top to bottom, most significant bit first.
+    ///
+    /// Pixels with a bit set to 1 are drawn in the text color.
+    pub bits: &'a [u8],
+}
+
+/// A font made of 1-bit glyphs of the same height but of different widths.
+///
+/// # Example :
+/// ```
+/// use libnw::display::{BitmapFont, Color, Font, Glyph};
+///
+/// const GLYPHS: &[Glyph] = &[
+///     Glyph { ch: '!', width: 1, bits: &[0b11101000] },
+///     Glyph { ch: 'i', width: 1, bits: &[0b10111000] },
+/// ];
+/// const TINY: BitmapFont = BitmapFont {
+///     height: 5,
+///     spacing: 1,
+///     line_spacing: 1,
+///     glyphs: GLYPHS,
+///     fallback: '!',
+/// };
+/// Font::Bitmap(&TINY).draw("hi!", 10, 10, Color::BLACK, None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitmapFont<'a> {
+    /// The height of every glyph in pixels.
+    pub height: u16,
+    /// The space between two glyphs in pixels.
+    pub spacing: u16,
+    /// The space between two lines in pixels.
+    pub line_spacing: u16,
+    /// The glyphs, sorted by `ch`.
+    pub glyphs: &'a [Glyph<'a>],
+    /// The character drawn in place of the ones without a glyph.
+    pub fallback: char,
+}
+
+impl<'a> BitmapFont<'a> {
+    /// The glyph of `ch`, or the fallback one.
+    pub fn glyph(&self, ch: char) -> Option<&Glyph<'a>> {
+        let find = |ch: char| {
+            self.glyphs
+                .binary_search_by_key(&ch, |glyph| glyph.ch)
+                .ok()
+                .map(|i| &self.glyphs[i])
+        };
+        find(ch).or_else(|| find(self.fallback))
+    }
+
+    /// Draws a glyph with its top-left corner at (`x`, `y`).
+    fn draw_glyph(&self, glyph: &Glyph, x: u16, y: u16, color: Color) {
+        let width = glyph.width as usize;
+        for row in 0..self.height {
+            let mut run_start = None;
+            // one more column to end the last run
+            for column in 0..=width {
+                let index = row as usize * width + column;
+                let set = column < width
+                    && glyph
+                        .bits
+                        .get(index / 8)
+                        .is_some_and(|byte| byte & (0x80 >> (index % 8)) != 0);
+                match (set, run_start) {
+                    (true, None) => run_start = Some(column),
+                    (false, Some(start)) => {
+                        Rect::screen_space_clipping(
+                            x as i32 + start as i32,
+                            y as i32 + row as i32,
+                            (column - start) as u16,
+                            1,
+                        )
+                        .fill(color);
+                        run_start = None;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// A font text can be drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Font<'a> {
+    /// The small built-in font.
+    Small,
+    /// The large built-in font.
+    Large,
+    /// An embedded bitmap font.
+    Bitmap(&'a BitmapFont<'a>),
+}
+
+/// Where lines are placed horizontally.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Where the text is placed vertically.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum VAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// How a text is drawn with [`draw_text`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextStyle<'a> {
+    pub font: Font<'a>,
+    pub color: Color,
+    /// The color behind the glyphs, `None` to keep what is already on screen.
+    ///
+    /// Only bitmap fonts can be drawn without a background: the built-in fonts are
+    /// drawn on [`DEFAULT_BACKGROUND`] when it is `None` (see [`Font::draw`]).
+    pub background: Option<Color>,
+    pub align: Align,
+    pub valign: VAlign,
+}
+
+impl<'a> TextStyle<'a> {
+    /// A style without background, aligned to the top-left.
+    pub fn new(font: Font<'a>, color: Color) -> Self {
+        Self {
+            font,
+            color,
+            background: None,
+            align: Align::Left,
+            valign: VAlign::Top,
+        }
+    }
+
+    /// Sets the background color.
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Sets both the horizontal and the vertical alignments.
+    pub fn align(mut self, align: Align, valign: VAlign) -> Self {
+        self.align = align;
+        self.valign = valign;
+        self
+    }
+
+    /// Centers the text both horizontally and vertically.
+    pub fn centered(self) -> Self {
+        self.align(Align::Center, VAlign::Middle)
+    }
+}
+
+impl Font<'_> {
+    /// The height of a line in pixels.
+    pub fn line_height(&self) -> u16 {
+        match self {
+            Font::Small => CHAR_HEIGHT,
+            Font::Large => LARGE_CHAR_HEIGHT,
+            Font::Bitmap(font) => font.height,
+        }
+    }
+
+    /// The space between two lines in pixels.
+    fn line_spacing(&self) -> u16 {
+        match self {
+            Font::Bitmap(font) => font.line_spacing,
+            _ => 0,
+        }
+    }
+
+    /// The width of a character in pixels, without spacing.
+    pub fn char_width(&self, ch: char) -> u16 {
+        match self {
+            Font::Small => CHAR_WIDTH,
+            Font::Large => LARGE_CHAR_WIDTH,
+            Font::Bitmap(font) => font.glyph(ch).map_or(0, |glyph| glyph.width as u16),
+        }
+    }
+
+    /// The width of a single line of text in pixels.
+    pub fn line_width(&self, line: &str) -> u16 {
+        let spacing = match self {
+            Font::Bitmap(font) => font.spacing,
+            _ => 0,
+        };
+        let count = line.chars().count().min(u16::MAX as usize) as u16;
+        line.chars()
+            .fold(0u16, |width, ch| width.saturating_add(self.char_width(ch)))
+            .saturating_add(spacing.saturating_mul(count.saturating_sub(1)))
+    }
+
+    /// The width and height of a text in pixels, each `'\n'` starting a new line.
+    pub fn measure(&self, text: &str) -> (u16, u16) {
+        let width = text.split('\n').map(|line| self.line_width(line)).max();
+        let lines = text.split('\n').count().min(u16::MAX as usize) as u16;
+        (
+            width.unwrap_or(0),
+            lines
+                .saturating_mul(self.line_height())
+                .saturating_add((lines - 1).saturating_mul(self.line_spacing())),
+        )
+    }
+
+    /// Splits a text into lines no wider than `max_width`.
+    ///
+    /// Lines are broken at `'\n'`, then between words, then inside words too long to fit.
+    pub fn wrap<'t>(&self, text: &'t str, max_width: u16) -> Vec<&'t str> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut start = 0;
+            let mut last_space = None;
+            for (i, ch) in paragraph.char_indices() {
+                if ch == ' ' {
+                    // spaces can overflow, they are trimmed
+                    last_space = Some(i);
+                    continue;
+                }
+                let end = i + ch.len_utf8();
+                while i > start && self.line_width(&paragraph[start..end]) > max_width {
+                    match last_space {
+                        Some(space) if space > start => {
+                            // nothing but spaces before the break
+                            let line = paragraph[start..space].trim_end();
+                            if !line.is_empty() {
+                                lines.push(line);
+                            }
+                            start = space + 1;
+                        }
+                        _ => {
+                            lines.push(&paragraph[start..i]);
+                            start = i;
+                        }
+                    }
+                    last_space = None;
+                }
+            }
+            lines.push(paragraph[start..].trim_end());
+        }
+        lines
+    }
+
+    /// Draws a single line of text with its top-left corner at (`x`, `y`).
+    ///
+    /// Without `background`, a bitmap font is drawn over what is already on screen.
+    /// The eadk always fills the background of the built-in fonts, they are drawn on
+    /// [`DEFAULT_BACKGROUND`] instead: use a bitmap font for transparent text.
+    pub fn draw(&self, line: &str, x: u16, y: u16, color: Color, background: Option<Color>) {
+        match self {
+            Font::Small | Font::Large => draw_string(
+                line,
+                x,
+                y,
+                *self == Font::Large,
+                color,
+                background.unwrap_or(DEFAULT_BACKGROUND),
+            ),
+            Font::Bitmap(font) => {
+                if let Some(background) = background {
+                    let (width, height) = self.measure(line);
+                    Rect::screen_space_clipping(x as i32, y as i32, width, height).fill(background);
+                }
+                let mut x = x;
+                for ch in line.chars() {
+                    if let Some(glyph) = font.glyph(ch) {
+                        font.draw_glyph(glyph, x, y, color);
+                        x = x.saturating_add(glyph.width as u16 + font.spacing);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draws a text inside `rect`, wrapped to its width and aligned as described by `style`.
+///
+/// The lines that don't fit in the height of `rect` are not drawn.
+pub fn draw_text(text: &str, rect: Rect, style: TextStyle) {
+    for (line, x, y) in layout(text, rect, style) {
+        style.font.draw(line, x, y, style.color, style.background);
+    }
+}
+
+/// The lines of `text` drawn by [`draw_text`], with the position of their top-left corner.
+fn layout<'t>(text: &'t str, rect: Rect, style: TextStyle) -> Vec<(&'t str, u16, u16)> {
+    let font = style.font;
+    let lines = font.wrap(text, rect.width);
+    let line_step = font.line_height().saturating_add(font.line_spacing());
+    let count = lines.len().min(u16::MAX as usize) as u16;
+    let height = count
+        .saturating_mul(line_step)
+        .saturating_sub(font.line_spacing());
+    let free_height = rect.height.saturating_sub(height);
+    // in u32, rects may reach past the u16 range
+    let bottom = rect.y as u32 + rect.height as u32;
+    let mut y = rect.y as u32
+        + match style.valign {
+            VAlign::Top => 0,
+            VAlign::Middle => free_height / 2,
+            VAlign::Bottom => free_height,
+        } as u32;
+    let mut placed = Vec::with_capacity(lines.len());
+    for line in lines {
+        if y + font.line_height() as u32 > bottom || y > u16::MAX as u32 {
+            break;
+        }
+        let free_width = rect.width.saturating_sub(font.line_width(line));
+        let x = rect.x.saturating_add(match style.align {
+            Align::Left => 0,
+            Align::Center => free_width / 2,
+            Align::Right => free_width,
+        });
+        placed.push((line, x, y as u16));
+        y += line_step as u32;
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLYPHS: &[Glyph] = &[
+        Glyph {
+            ch: '!',
+            width: 1,
+            bits: &[0b11101000],
+        },
+        Glyph {
+            ch: 'w',
+            width: 3,
+            bits: &[0b10110110, 0b11011011, 0b1000_0000],
+        },
+    ];
+    const TINY: BitmapFont = BitmapFont {
+        height: 5,
+        spacing: 1,
+        line_spacing: 2,
+        glyphs: GLYPHS,
+        fallback: '!',
+    };
+
+    /// Wraps `text` in the small font, `chars` characters wide.
+    fn wrap(text: &str, chars: u16) -> Vec<&str> {
+        Font::Small.wrap(text, chars * CHAR_WIDTH)
+    }
+
+    #[test]
+    fn words_are_kept_together() {
+        assert_eq!(wrap("golf is fun", 5), ["golf", "is", "fun"]);
+        assert_eq!(wrap("golf is fun", 10), ["golf is", "fun"]);
+        assert_eq!(wrap("golf is fun", 11), ["golf is fun"]);
+    }
+
+    #[test]
+    fn long_words_are_split() {
+        assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert_eq!(wrap("a bcdefgh", 3), ["a", "bcd", "efg", "h"]);
+        // at least a character per line
+        assert_eq!(wrap("ab", 0), ["a", "b"]);
+    }
+
+    #[test]
+    fn new_lines_are_kept() {
+        assert_eq!(wrap("a\n\nb", 10), ["a", "", "b"]);
+        assert_eq!(wrap("aaaa\nb", 2), ["aa", "aa", "b"]);
+    }
+
+    #[test]
+    fn runs_of_spaces_are_trimmed_at_breaks() {
+        assert_eq!(wrap("aa   bb", 3), ["aa", "bb"]);
+        assert_eq!(wrap("   ab", 2), ["ab"]);
+        assert_eq!(wrap("ab   ", 3), ["ab"]);
+        // but kept inside a line
+        assert_eq!(wrap("a  b", 4), ["a  b"]);
+    }
+
+    #[test]
+    fn non_ascii_text_is_split_on_chars() {
+        assert_eq!(wrap("héllo wörld", 5), ["héllo", "wörld"]);
+        assert_eq!(wrap("ééé", 2), ["éé", "é"]);
+    }
+
+    #[test]
+    fn text_is_measured_with_spacing() {
+        assert_eq!(Font::Small.measure("ab\nabc"), (21, 28));
+        assert_eq!(Font::Large.measure(""), (0, LARGE_CHAR_HEIGHT));
+        let tiny = Font::Bitmap(&TINY);
+        // 'x' has no glyph, the fallback '!' is used
+        assert_eq!(tiny.line_width("w!x"), 3 + 1 + 1 + 1 + 1);
+        assert_eq!(tiny.measure("w\nww\n"), (7, 3 * 5 + 2 * 2));
+        assert_eq!(Font::Small.line_width(&"a".repeat(20_000)), u16::MAX);
+    }
+
+    #[test]
+    fn lines_are_aligned_in_the_rect() {
+        let rect = Rect::new(10, 20, 70, 100);
+        let style = TextStyle::new(Font::Small, Color::BLACK);
+        assert_eq!(layout("abc", rect, style), [("abc", 10, 20)]);
+        assert_eq!(layout("abc", rect, style.centered()), [("abc", 34, 63)]);
+        let bottom_right = style.align(Align::Right, VAlign::Bottom);
+        assert_eq!(
+            layout("abc\na", rect, bottom_right),
+            [("abc", 59, 92), ("a", 73, 106)]
+        );
+        let tiny = TextStyle::new(Font::Bitmap(&TINY), Color::BLACK).centered();
+        assert_eq!(
+            layout("w\nw", Rect::new(0, 0, 7, 20), tiny),
+            [("w", 2, 4), ("w", 2, 11)]
+        );
+    }
+
+    #[test]
+    fn lines_past_the_rect_are_dropped() {
+        let style = TextStyle::new(Font::Small, Color::BLACK);
+        assert_eq!(layout("a\nb", Rect::new(0, 0, 70, 20), style).len(), 1);
+        assert_eq!(layout("a", Rect::new(0, 0, 70, 10), style), []);
+        // without overflowing
+        let far = Rect::new(u16::MAX - 5, u16::MAX - 5, u16::MAX, u16::MAX);
+        assert_eq!(
+            layout("a\nb", far, style.align(Align::Right, VAlign::Top)),
+            [("a", u16::MAX, u16::MAX - 5)]
+        );
+        assert_eq!(layout("a", far, style.centered()), []);
+    }
+}
@@ -5,14 +5,32 @@
 //!
 //! - Pixel-level manipulation (`set_pixel`, `get_pixel`)
 //! - Rectangle drawing and filling (`Rect`)
-//! - Text rendering (`draw_string`)
-//! - RGB color handling with `Color`
+//! - Text rendering (`draw_string`), measurement and wrapping (`Font`, `draw_text`)
+//! - RGB color handling with `Color`, blending and HSV conversion, and a few `palette`s
+//! - Off-screen drawing with `Canvas`
+//! - Partial redraws with `DirtyRegion`
+//! - Lines, circles and polygons with `Painter`
+//! - Images with transparency with `Sprite`
 //!
 //! For low-level control, the internal `eadk` submodule exposes raw FFI bindings.
 //! Prefer using the safe API unless you have specific performance or control needs.
 
 extern crate alloc;
 
+mod canvas;
+mod color;
+mod dirty;
+mod draw;
+mod font;
+mod sprite;
+
+pub use canvas::Canvas;
+pub use color::palette;
+pub use dirty::DirtyRegion;
+pub use draw::Painter;
+pub use font::{Align, BitmapFont, DEFAULT_BACKGROUND, Font, Glyph, TextStyle, VAlign, draw_text};
+pub use sprite::{Flip, SPRITE_MAGIC, Sprite, Transparency};
+
 use alloc::{borrow::Cow, string::String, vec::Vec};
 
 /// The width of the screen in pixels.
@@ -32,7 +50,7 @@ pub const LARGE_CHAR_HEIGHT: u16 = 16;
 
 /// A rectangle on the screen.
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Rect {
     pub x: u16,
     pub y: u16,
@@ -51,17 +69,11 @@ impl Rect {
         }
     }
 
+    /// Creates a rect from signed coordinates, clipped to the screen.
+    ///
+    /// The parts of the rect outside of the screen are cut off, and the result may be empty.
     pub fn screen_space_clipping(x: i32, y: i32, width: u16, height: u16) -> Self {
-        let x = x.clamp(0, SCREEN_WIDTH as i32) as u16;
-        let y = y.clamp(0, SCREEN_HEIGHT as i32) as u16;
-        let ex = (x + width).clamp(0, SCREEN_WIDTH);
-        let ey = (y + height).clamp(0, SCREEN_HEIGHT);
-        Self {
-            x,
-            y,
-            width: ex - x,
-            height: ey - y,
-        }
+        clip_rect(x, y, width, height, SCREEN_WIDTH, SCREEN_HEIGHT)
     }
 
     /// Creates a square.
@@ -79,11 +91,78 @@ impl Rect {
         self.width as u32 * self.height as u32
     }
 
+    /// Checks if the rect covers no pixel.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the part of the rect also covered by `other`, if any.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let ex = (self.x as u32 + self.width as u32).min(other.x as u32 + other.width as u32);
+        let ey = (self.y as u32 + self.height as u32).min(other.y as u32 + other.height as u32);
+        if ex > x as u32 && ey > y as u32 {
+            Some(Rect::new(
+                x,
+                y,
+                (ex - x as u32) as u16,
+                (ey - y as u32) as u16,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the rects share at least one pixel.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Returns the smallest rect containing both rects.
+    ///
+    /// Empty rects are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let ex = (self.x as u32 + self.width as u32).max(other.x as u32 + other.width as u32);
+        let ey = (self.y as u32 + self.height as u32).max(other.y as u32 + other.height as u32);
+        Rect::new(x, y, (ex - x as u32) as u16, (ey - y as u32) as u16)
+    }
+
     /// Fills the rect on the screen with the given color.
     pub fn fill(self, color: Color) {
         eadk::push_rect_uniform(self, color);
     }
 
+    /// Fills the part of the rect inside `clip` on the screen with the given color.
+    pub fn fill_clipped(self, clip: Rect, color: Color) {
+        if let Some(rect) = self.intersection(&clip) {
+            rect.fill(color);
+        }
+    }
+
+    /// Draws `color` over the rect on the screen with an opacity of `alpha`.
+    ///
+    /// The pixels have to be read back from the screen, so this is slower than `fill`.
+    pub fn blend(self, color: Color, alpha: u8) {
+        if self.area() == 0 {
+            return;
+        }
+        let pixels: Vec<Color> = self
+            .get_pixels()
+            .into_iter()
+            .map(|pixel| color.over(pixel, alpha))
+            .collect();
+        self.fill_with_buf(&pixels);
+    }
+
     /// Fills the rect on the screen with the given pixel colors.
     ///
     /// # Panics
@@ -121,12 +200,12 @@ impl Rect {
 
 /// An RGB 5-6-5 color: 5 bits for red, 6 bits for green and 5 bits for blue.
 #[repr(transparent)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Color(u16);
 
 impl Color {
     /// Creates a color directly from a u16.
-    pub fn new(rgb565: u16) -> Self {
+    pub const fn new(rgb565: u16) -> Self {
         Self(rgb565)
     }
 
@@ -134,7 +213,7 @@ impl Color {
     ///
     /// Converts 8-bit per channel RGB values into RGB565 format.
     /// Precision is lost in the conversion: red and blue use 5 bits, green uses 6 bits.
-    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+    pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {
         let r = (r & 0b11111000) as u16;
         let g = (g & 0b11111100) as u16;
         let b = b as u16;
@@ -165,6 +244,27 @@ impl Color {
     pub const BLUE: Self = Self(0x1F);
 }
 
+/// Clips a rect in signed coordinates to a (0, 0, `max_width`, `max_height`) area.
+pub(crate) fn clip_rect(
+    x: i32,
+    y: i32,
+    width: u16,
+    height: u16,
+    max_width: u16,
+    max_height: u16,
+) -> Rect {
+    let sx = x.clamp(0, max_width as i32);
+    let sy = y.clamp(0, max_height as i32);
+    let ex = x.saturating_add(width as i32).clamp(0, max_width as i32);
+    let ey = y.saturating_add(height as i32).clamp(0, max_height as i32);
+    Rect::new(
+        sx as u16,
+        sy as u16,
+        (ex - sx).max(0) as u16,
+        (ey - sy).max(0) as u16,
+    )
+}
+
 /// Returns the pixels' color in the given rect.
 ///
 /// The screen is read from left to right then top to bottom.
@@ -239,6 +339,7 @@ fn terminate_str(s: &str) -> Cow<'_, str> {
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     use super::{Color, Rect};
 
@@ -310,3 +411,6 @@ pub mod eadk {
         pub y: u16,
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::display as eadk;
@@ -0,0 +1,374 @@
+//! Images with transparency, drawn anywhere on the screen.
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+
+use super::{Canvas, Color, Rect, clip_rect};
+
+/// The first bytes of a sprite file, see [`Sprite::from_bytes`].
+pub const SPRITE_MAGIC: &[u8; 4] = b"NWSP";
+
+/// How the transparent pixels of a sprite are found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transparency<'a> {
+    /// Every pixel is drawn.
+    Opaque,
+    /// The pixels of this color are not drawn.
+    Key(Color),
+    /// One bit per pixel, from left to right then top to bottom, most significant bit first.
+    ///
+    /// Pixels with a bit set to 1 are drawn, the others are not.
+    Mask(Cow<'a, [u8]>),
+}
+
+/// How a sprite is flipped when drawn.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Flip {
+    /// Mirror left and right.
+    pub horizontal: bool,
+    /// Mirror top and bottom.
+    pub vertical: bool,
+}
+
+impl Flip {
+    /// Not flipped.
+    pub const NONE: Self = Self {
+        horizontal: false,
+        vertical: false,
+    };
+    /// Mirrored left to right.
+    pub const HORIZONTAL: Self = Self {
+        horizontal: true,
+        vertical: false,
+    };
+    /// Mirrored top to bottom.
+    pub const VERTICAL: Self = Self {
+        horizontal: false,
+        vertical: true,
+    };
+    /// Rotated by 180°.
+    pub const BOTH: Self = Self {
+        horizontal: true,
+        vertical: true,
+    };
+}
+
+/// An RGB565 image with transparent pixels.
+///
+/// The pixels can either be borrowed (e.g. from a `const` array or from the external data)
+/// or owned (e.g. decoded with [`Sprite::from_bytes`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sprite<'a> {
+    width: u16,
+    height: u16,
+    /// The pixels, from left to right then top to bottom.
+    pixels: Cow<'a, [Color]>,
+    transparency: Transparency<'a>,
+}
+
+impl<'a> Sprite<'a> {
+    /// Creates a sprite from borrowed pixels.
+    ///
+    /// # Panics
+    /// Panics if the number of pixels (or of mask bits) doesn't match the size.
+    pub fn new(
+        width: u16,
+        height: u16,
+        pixels: &'a [Color],
+        transparency: Transparency<'a>,
+    ) -> Self {
+        Self::from_cow(width, height, Cow::Borrowed(pixels), transparency)
+    }
+
+    /// Creates a sprite that owns its pixels.
+    ///
+    /// # Panics
+    /// Panics if the number of pixels (or of mask bits) doesn't match the size.
+    pub fn from_vec(
+        width: u16,
+        height: u16,
+        pixels: Vec<Color>,
+        transparency: Transparency<'a>,
+    ) -> Self {
+        Self::from_cow(width, height, Cow::Owned(pixels), transparency)
+    }
+
+    fn from_cow(
+        width: u16,
+        height: u16,
+        pixels: Cow<'a, [Color]>,
+        transparency: Transparency<'a>,
+    ) -> Self {
+        let area = width as usize * height as usize;
+        assert_eq!(pixels.len(), area, "sprite pixels don't match its size");
+        if let Transparency::Mask(mask) = &transparency {
+            assert_eq!(
+                mask.len(),
+                area.div_ceil(8),
+                "sprite mask doesn't match its size"
+            );
+        }
+        Self {
+            width,
+            height,
+            pixels,
+            transparency,
+        }
+    }
+
+    /// Decodes a sprite file.
+    ///
+    /// The format is:
+    /// - the 4 bytes of [`SPRITE_MAGIC`],
+    /// - the width and the height, as little endian u16,
+    /// - a flags byte, with the lowest bit set if there is a mask,
+    /// - the pixels, as little endian RGB565 u16, from left to right then top to bottom,
+    /// - the mask if any, as described in [`Transparency::Mask`].
+    pub fn from_bytes(data: &[u8]) -> Result<Sprite<'static>, String> {
+        let header = data.get(..9).ok_or("Sprite data is too short")?;
+        if &header[..4] != SPRITE_MAGIC {
+            return Err(String::from("Not a sprite (bad magic)"));
+        }
+        let width = u16::from_le_bytes([header[4], header[5]]);
+        let height = u16::from_le_bytes([header[6], header[7]]);
+        let has_mask = header[8] & 1 != 0;
+        let area = width as usize * height as usize;
+        let mask_len = if has_mask { area.div_ceil(8) } else { 0 };
+        let expected = 9 + area * 2 + mask_len;
+        if data.len() != expected {
+            return Err(format!(
+                "Sprite data is {} bytes long, expected {expected}",
+                data.len()
+            ));
+        }
+        let pixels_end = 9 + area * 2;
+        let pixels = data[9..pixels_end]
+            .chunks_exact(2)
+            .map(|bytes| Color::new(u16::from_le_bytes([bytes[0], bytes[1]])))
+            .collect();
+        let transparency = if has_mask {
+            Transparency::Mask(Cow::Owned(data[pixels_end..].to_vec()))
+        } else {
+            Transparency::Opaque
+        };
+        Ok(Sprite::from_cow(
+            width,
+            height,
+            Cow::Owned(pixels),
+            transparency,
+        ))
+    }
+
+    /// The width of the sprite in pixels.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the sprite in pixels.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Checks if the pixel at (`x`, `y`) is drawn.
+    pub fn is_opaque(&self, x: u16, y: u16) -> bool {
+        let index = y as usize * self.width as usize + x as usize;
+        match &self.transparency {
+            Transparency::Opaque => true,
+            Transparency::Key(key) => self.pixels[index] != *key,
+            Transparency::Mask(mask) => mask[index / 8] & (0x80 >> (index % 8)) != 0,
+        }
+    }
+
+    /// Draws the sprite with its top-left corner at (`x`, `y`), clipped to the screen.
+    pub fn draw_at(&self, x: i32, y: i32) {
+        self.draw(x, y, Flip::NONE, Rect::SCREEN);
+    }
+
+    /// Draws the sprite with its top-left corner at (`x`, `y`), flipped,
+    /// and only inside `clip` (and the screen).
+    pub fn draw(&self, x: i32, y: i32, flip: Flip, clip: Rect) {
+        let Some(clip) =
+            Rect::screen_space_clipping(x, y, self.width, self.height).intersection(&clip)
+        else {
+            return;
+        };
+        let mut run = Vec::with_capacity(clip.width as usize);
+        for screen_y in clip.y..clip.y + clip.height {
+            let mut run_start = clip.x;
+            for screen_x in clip.x..clip.x + clip.width {
+                let (sx, sy) = self.source(x, y, screen_x as i32, screen_y as i32, flip);
+                if self.is_opaque(sx, sy) {
+                    run.push(self.pixel(sx, sy));
+                    continue;
+                }
+                // a transparent pixel ends the run of opaque ones
+                if !run.is_empty() {
+                    Rect::new(run_start, screen_y, run.len() as u16, 1).fill_with_buf(&run);
+                    run.clear();
+                }
+                run_start = screen_x + 1;
+            }
+            if !run.is_empty() {
+                Rect::new(run_start, screen_y, run.len() as u16, 1).fill_with_buf(&run);
+                run.clear();
+            }
+        }
+    }
+
+    /// Draws the sprite on a canvas with its top-left corner at (`x`, `y`), flipped.
+    pub fn draw_on(&self, canvas: &mut Canvas, x: i32, y: i32, flip: Flip) {
+        let area = clip_rect(
+            x,
+            y,
+            self.width,
+            self.height,
+            canvas.width(),
+            canvas.height(),
+        );
+        for cy in area.y..area.y + area.height {
+            for cx in area.x..area.x + area.width {
+                let (sx, sy) = self.source(x, y, cx as i32, cy as i32, flip);
+                if self.is_opaque(sx, sy) {
+                    canvas.set_pixel(cx, cy, self.pixel(sx, sy));
+                }
+            }
+        }
+    }
+
+    /// The color of the pixel at (`x`, `y`), transparent or not.
+    fn pixel(&self, x: u16, y: u16) -> Color {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// The sprite pixel drawn at (`tx`, `ty`), when the sprite is drawn at (`x`, `y`).
+    fn source(&self, x: i32, y: i32, tx: i32, ty: i32, flip: Flip) -> (u16, u16) {
+        let mut sx = (tx - x) as u16;
+        let mut sy = (ty - y) as u16;
+        if flip.horizontal {
+            sx = self.width - 1 - sx;
+        }
+        if flip.vertical {
+            sy = self.height - 1 - sy;
+        }
+        (sx, sy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pixels of a 3×2 sprite, a different color on each one.
+    fn pixels() -> Vec<Color> {
+        (0..6).map(Color).collect()
+    }
+
+    /// A sprite file of `pixels`, with `mask` if any.
+    fn file(mask: Option<u8>) -> Vec<u8> {
+        let mut data = Vec::from(*SPRITE_MAGIC);
+        data.extend([3, 0, 2, 0, mask.is_some() as u8]);
+        for color in pixels() {
+            data.extend(color.0.to_le_bytes());
+        }
+        data.extend(mask);
+        data
+    }
+
+    /// The raw colors of `canvas`, row by row.
+    fn rows(canvas: &Canvas) -> Vec<Vec<u16>> {
+        canvas
+            .pixels()
+            .chunks(canvas.width() as usize)
+            .map(|row| row.iter().map(|color| color.0).collect())
+            .collect()
+    }
+
+    /// Draws `sprite` on a 3×2 canvas filled with 9.
+    fn drawn(sprite: &Sprite, x: i32, y: i32, flip: Flip) -> Vec<Vec<u16>> {
+        let mut canvas = Canvas::new(3, 2, Color(9));
+        sprite.draw_on(&mut canvas, x, y, flip);
+        rows(&canvas)
+    }
+
+    #[test]
+    fn files_are_decoded() {
+        let sprite = Sprite::from_bytes(&file(None)).unwrap();
+        assert_eq!((sprite.width(), sprite.height()), (3, 2));
+        assert_eq!(sprite, Sprite::new(3, 2, &pixels(), Transparency::Opaque));
+
+        let sprite = Sprite::from_bytes(&file(Some(0b1101_1000))).unwrap();
+        assert_eq!(drawn(&sprite, 0, 0, Flip::NONE), [[0, 1, 9], [3, 4, 9]]);
+    }
+
+    #[test]
+    fn bad_files_are_rejected() {
+        let data = file(Some(0));
+        // truncated, in the header, the pixels or the mask
+        for len in [0, 8, 9, 14, data.len() - 1] {
+            assert!(Sprite::from_bytes(&data[..len]).is_err(), "{len} bytes");
+        }
+        // a mask that isn't announced
+        assert!(Sprite::from_bytes(&[file(None), Vec::from([0])].concat()).is_err());
+        // a bigger size than the pixels
+        let mut data = file(None);
+        data[4] = 4;
+        assert!(Sprite::from_bytes(&data).is_err());
+        data[..4].copy_from_slice(b"NWSQ");
+        data[4] = 3;
+        assert!(Sprite::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "sprite mask doesn't match its size")]
+    fn a_short_mask_panics() {
+        let mask = Cow::Owned(Vec::new());
+        Sprite::new(3, 2, &pixels(), Transparency::Mask(mask));
+    }
+
+    #[test]
+    fn transparent_pixels_are_skipped() {
+        let pixels = pixels();
+        let keyed = Sprite::new(3, 2, &pixels, Transparency::Key(Color(4)));
+        assert!(!keyed.is_opaque(1, 1) && keyed.is_opaque(0, 0));
+        assert_eq!(drawn(&keyed, 0, 0, Flip::NONE), [[0, 1, 2], [3, 9, 5]]);
+
+        let mask = Cow::Borrowed(&[0b0111_1000][..]);
+        let masked = Sprite::new(3, 2, &pixels, Transparency::Mask(mask));
+        assert_eq!(drawn(&masked, 0, 0, Flip::NONE), [[9, 1, 2], [3, 4, 9]]);
+    }
+
+    #[test]
+    fn flips_mirror_the_pixels() {
+        let pixels = pixels();
+        let sprite = Sprite::new(3, 2, &pixels, Transparency::Opaque);
+        assert_eq!(drawn(&sprite, 0, 0, Flip::NONE), [[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(
+            drawn(&sprite, 0, 0, Flip::HORIZONTAL),
+            [[2, 1, 0], [5, 4, 3]]
+        );
+        assert_eq!(drawn(&sprite, 0, 0, Flip::VERTICAL), [[3, 4, 5], [0, 1, 2]]);
+        assert_eq!(drawn(&sprite, 0, 0, Flip::BOTH), [[5, 4, 3], [2, 1, 0]]);
+        // clipped, the flip applies to the whole sprite
+        assert_eq!(
+            drawn(&sprite, -1, 1, Flip::HORIZONTAL),
+            [[9, 9, 9], [1, 0, 9]]
+        );
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn the_screen_gets_the_same_pixels_as_a_canvas() {
+        let pixels = pixels();
+        let sprite = Sprite::new(3, 2, &pixels, Transparency::Key(Color(4)));
+        for flip in [Flip::NONE, Flip::HORIZONTAL, Flip::VERTICAL, Flip::BOTH] {
+            crate::host::reset();
+            Rect::new(0, 0, 3, 2).fill(Color(9));
+            sprite.draw(-1, 0, flip, Rect::SCREEN);
+            assert_eq!(
+                rows(&Canvas::from_screen(Rect::new(0, 0, 3, 2))),
+                drawn(&sprite, -1, 0, flip)
+            );
+        }
+    }
+}
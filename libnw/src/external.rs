@@ -6,6 +6,7 @@
 
 /// Returns a static slice containing external binary data bundled with the application.
 ///
+#[cfg(not(feature = "host"))]
 pub fn get_data() -> &'static [u8] {
     unsafe {
         // SAFETY: The underlying pointer is provided by EADK. It is assumed to be
@@ -18,10 +19,11 @@ pub fn get_data() -> &'static [u8] {
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// A pointer to the beginning of the external data slice.
-        /// 
+        ///
         /// # Safety
         /// This pointer should always be used with `eadk::data_size`.
         #[link_name = "eadk_external_data"]
@@ -32,3 +34,9 @@ pub mod eadk {
         pub safe static data_size: usize;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::external as eadk;
+
+#[cfg(feature = "host")]
+pub use eadk::get_data;
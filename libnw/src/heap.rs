@@ -1,3 +1,52 @@
+//! The heap allocator, and how much of it is used.
+
+use core::{
+    mem, ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+/// The size of the heap set up by [`init_heap!`].
+static HEAP_SIZE: AtomicUsize = AtomicUsize::new(0);
+/// The `fn() -> usize` telling how much of the heap is used, null before [`init_heap!`].
+static HEAP_USED: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// How much of the heap is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeapStats {
+    /// The size of the heap in bytes.
+    pub size: usize,
+    /// The number of bytes allocated.
+    pub used: usize,
+}
+
+impl HeapStats {
+    /// The number of bytes left.
+    pub fn free(&self) -> usize {
+        self.size.saturating_sub(self.used)
+    }
+}
+
+/// How much of the heap set up by [`init_heap!`] is used, `None` if it was not called.
+pub fn stats() -> Option<HeapStats> {
+    let used = HEAP_USED.load(Ordering::Acquire);
+    if used.is_null() {
+        return None;
+    }
+    // SAFETY: `HEAP_USED` is only set by `register`, from a `fn() -> usize`.
+    let used = unsafe { mem::transmute::<*mut (), fn() -> usize>(used) };
+    Some(HeapStats {
+        size: HEAP_SIZE.load(Ordering::Relaxed),
+        used: used(),
+    })
+}
+
+/// Called by [`init_heap!`], to make [`stats`] available.
+#[doc(hidden)]
+pub fn register(size: usize, used: fn() -> usize) {
+    HEAP_SIZE.store(size, Ordering::Relaxed);
+    HEAP_USED.store(used as *mut (), Ordering::Release);
+}
+
 /// Initializes a global heap allocator using a statically allocated buffer.
 ///
 /// This macro sets up a global allocator using [`embedded_alloc::LlffHeap`],
@@ -7,8 +56,8 @@
 ///
 /// # Example
 ///
-/// ```rust
-/// init_heap!(1024); // Initializes a 1 KB heap
+/// ```no_run
+/// libnw::init_heap!(1024); // Initializes a 1 KB heap
 /// ```
 ///
 /// # Safety
@@ -18,6 +67,8 @@
 /// - The macro is only called once.
 /// - No heap allocations occur before it is called.
 /// - You use it in the `main()` function or at the very start of your app.
+///
+/// Once called, [`stats`] tells how much of the heap is used.
 #[macro_export]
 macro_rules! init_heap {
     ($size:expr) => {{
@@ -38,13 +89,17 @@ macro_rules! init_heap {
             // SAFETY: the heap buffer is allocated with a size of `HEAP_SIZE`.
             HEAP.init(&raw mut HEAP_BUFFER as usize, HEAP_SIZE);
         }
+        $crate::heap::register(HEAP_SIZE, || HEAP.used());
     }};
 }
 
 // These 3 functions are required by the linker
+#[cfg(not(feature = "host"))]
 #[unsafe(no_mangle)]
 extern "C" fn _critical_section_1_0_acquire() {}
+#[cfg(not(feature = "host"))]
 #[unsafe(no_mangle)]
 extern "C" fn _critical_section_1_0_release() {}
+#[cfg(not(feature = "host"))]
 #[unsafe(no_mangle)]
 extern "C" fn __aeabi_unwind_cpp_pr0() {}
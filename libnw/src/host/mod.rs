@@ -0,0 +1,742 @@
+//! Pure-Rust stand-in for the calculator, used when the `host` feature is enabled.
+//!
+//! With this feature, every `eadk` submodule of this crate is swapped for one of
+//! the submodules below, so the whole crate (and anything built on top of it)
+//! can be linked and tested on a regular computer.
+//!
+//! The emulated device is made of:
+//!
+//! - an in-memory 320×240 RGB565 framebuffer,
+//! - a programmable keyboard state and event queue,
+//! - a virtual monotonic clock, only moving forward when sleeping or when told to,
+//! - a seedable random number generator,
+//! - a backlight, a battery, an USB port and an external data slice,
+//! - a file storage, in memory or in a directory (see [`set_storage_dir`]).
+//!
+//! The device is thread local: every thread (and thus every test) gets its own,
+//! starting in the state described by [`reset`].
+//!
+//! The functions at the root of this module control the emulated device,
+//! and [`snapshot`] compares what it displays against reference images.
+
+extern crate alloc;
+extern crate std;
+
+pub mod snapshot;
+
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec, vec::Vec};
+use core::cell::{Cell, RefCell};
+use std::path::PathBuf;
+
+use crate::display::{Color, Rect, SCREEN_AREA, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::keyboard::{Key, KeyboardState, RawKey};
+
+/// Something happening on the emulated device, reported to the hook.
+///
+/// See [`set_hook`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostEvent {
+    /// The app is sleeping, the clock has already been moved forward by `us` microseconds.
+    Sleep { us: u64 },
+    /// The app is waiting for the screen to refresh.
+    VBlank,
+    /// The app is reading the keyboard state.
+    Scan,
+}
+
+/// The seed used by the random number generator after a [`reset`].
+pub const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// The size of the emulated storage in bytes.
+pub const STORAGE_SIZE: usize = 32 * 1024;
+
+/// Where the records of the emulated storage are kept.
+enum Storage {
+    /// In memory, lost on [`reset`].
+    Memory(BTreeMap<String, Vec<u8>>),
+    /// One file per record, kept between runs.
+    Directory(PathBuf),
+}
+
+/// The whole state of the emulated device.
+struct Device {
+    framebuffer: Vec<Color>,
+    keyboard: u64,
+    events: Vec<Key>,
+    time_us: u64,
+    rng: u64,
+    brightness: u8,
+    battery_level: u8,
+    battery_voltage: f32,
+    charging: bool,
+    usb_plugged: bool,
+    external_data: &'static [u8],
+    storage: Storage,
+}
+
+impl Device {
+    fn new() -> Self {
+        Self {
+            framebuffer: vec![Color::BLACK; SCREEN_AREA],
+            keyboard: 0,
+            events: Vec::new(),
+            time_us: 0,
+            rng: DEFAULT_SEED,
+            brightness: crate::backlight::MAX_BRIGHTNESS,
+            battery_level: 100,
+            battery_voltage: 4.2,
+            charging: false,
+            usb_plugged: false,
+            external_data: &[],
+            storage: Storage::Memory(BTreeMap::new()),
+        }
+    }
+
+    /// Iterates over the framebuffer indices covered by `rect`, clipped to the screen.
+    fn rect_indices(rect: Rect) -> impl Iterator<Item = usize> {
+        let ex = (rect.x as u32 + rect.width as u32).min(SCREEN_WIDTH as u32) as usize;
+        let ey = (rect.y as u32 + rect.height as u32).min(SCREEN_HEIGHT as u32) as usize;
+        let (x, y) = (rect.x as usize, rect.y as usize);
+        (y..ey).flat_map(move |py| (x..ex).map(move |px| py * SCREEN_WIDTH as usize + px))
+    }
+}
+
+/// A function called on every [`HostEvent`].
+type Hook = Box<dyn FnMut(HostEvent)>;
+
+std::thread_local! {
+    static DEVICE: RefCell<Device> = RefCell::new(Device::new());
+    static HOOK: RefCell<Option<Hook>> = const { RefCell::new(None) };
+    /// Changed by every `set_hook` and `clear_hook`, to know if a running hook was replaced.
+    static HOOK_GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Runs `f` with exclusive access to the device.
+fn with_device<R>(f: impl FnOnce(&mut Device) -> R) -> R {
+    DEVICE.with(|device| f(&mut device.borrow_mut()))
+}
+
+/// Reports `event` to the hook, if any.
+///
+/// The hook is taken out while it runs, so it is free to use the rest of this module.
+/// It is put back unless it replaced or removed itself.
+fn notify(event: HostEvent) {
+    let hook = HOOK.with(|hook| hook.borrow_mut().take());
+    if let Some(mut hook) = hook {
+        let generation = HOOK_GENERATION.get();
+        hook(event);
+        if HOOK_GENERATION.get() == generation {
+            HOOK.with(|slot| *slot.borrow_mut() = Some(hook));
+        }
+    }
+}
+
+/// Puts the device back in its initial state.
+///
+/// The screen is black, no key is pressed, the clock is at 0, the random number generator
+/// is seeded with [`DEFAULT_SEED`], the brightness is at its maximum, the battery is full
+/// and not charging, USB is unplugged, there is no external data and the storage is
+/// empty, in memory.
+///
+/// The hook is left untouched.
+pub fn reset() {
+    with_device(|device| *device = Device::new());
+}
+
+/// Installs a hook, called every time the app sleeps, waits for vblank or scans the keyboard.
+///
+/// This is the place to forward the framebuffer to a real screen or to feed the keyboard.
+/// It replaces the previous hook, if any.
+pub fn set_hook(hook: impl FnMut(HostEvent) + 'static) {
+    HOOK_GENERATION.set(HOOK_GENERATION.get() + 1);
+    HOOK.with(|slot| *slot.borrow_mut() = Some(Box::new(hook)));
+}
+
+/// Removes the hook, even from inside the hook itself.
+pub fn clear_hook() {
+    HOOK_GENERATION.set(HOOK_GENERATION.get() + 1);
+    HOOK.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Returns a copy of the whole framebuffer.
+///
+/// The screen is read from left to right then top to bottom.
+pub fn framebuffer() -> Vec<Color> {
+    with_device(|device| device.framebuffer.clone())
+}
+
+/// Runs `f` on the framebuffer without copying it.
+///
+/// The screen is read from left to right then top to bottom.
+pub fn with_framebuffer<R>(f: impl FnOnce(&[Color]) -> R) -> R {
+    with_device(|device| f(&device.framebuffer))
+}
+
+/// Replaces the whole keyboard state.
+pub fn set_keyboard_state(state: KeyboardState) {
+    with_device(|device| device.keyboard = state.0);
+}
+
+/// Holds `key` down until it is released.
+pub fn press_key(key: RawKey) {
+    with_device(|device| device.keyboard |= 1 << key as u8);
+}
+
+/// Releases `key`.
+pub fn release_key(key: RawKey) {
+    with_device(|device| device.keyboard &= !(1 << key as u8));
+}
+
+/// Releases every key.
+pub fn release_all_keys() {
+    with_device(|device| device.keyboard = 0);
+}
+
+/// Queues a key event, to be returned by the next `event_get`.
+pub fn queue_event(key: Key) {
+    with_device(|device| device.events.insert(0, key));
+}
+
+/// The virtual clock, in microseconds.
+pub fn time_us() -> u64 {
+    with_device(|device| device.time_us)
+}
+
+/// Moves the virtual clock forward by `ms` milliseconds.
+///
+/// The hook is not called.
+pub fn advance_time(ms: u64) {
+    with_device(|device| device.time_us += ms * 1000);
+}
+
+/// Seeds the random number generator.
+///
+/// A seed of 0 is replaced by [`DEFAULT_SEED`].
+pub fn seed_random(seed: u64) {
+    with_device(|device| device.rng = if seed == 0 { DEFAULT_SEED } else { seed });
+}
+
+/// Sets what the battery reports.
+pub fn set_battery(level: u8, voltage: f32, charging: bool) {
+    with_device(|device| {
+        device.battery_level = level;
+        device.battery_voltage = voltage;
+        device.charging = charging;
+    });
+}
+
+/// Plugs or unplugs the USB cable.
+pub fn set_usb_plugged(plugged: bool) {
+    with_device(|device| device.usb_plugged = plugged);
+}
+
+/// Sets the external data returned by `external::get_data`.
+pub fn set_external_data(data: &'static [u8]) {
+    with_device(|device| device.external_data = data);
+}
+
+/// Keeps the records of the storage in `dir`, one file per record, creating it if needed.
+///
+/// The records already there are kept, so that they survive between runs.
+pub fn set_storage_dir(dir: impl Into<PathBuf>) -> std::io::Result<()> {
+    let dir = dir.into();
+    std::fs::create_dir_all(&dir)?;
+    with_device(|device| device.storage = Storage::Directory(dir));
+    Ok(())
+}
+
+/// Emulation of the backlight `eadk` api.
+pub mod backlight {
+    use super::with_device;
+
+    /// Sets the screen brightness.
+    pub fn set_brightness(brightness: u8) {
+        with_device(|device| device.brightness = brightness);
+    }
+
+    /// Retrieves the screen brightness.
+    pub fn get_brightness() -> u8 {
+        with_device(|device| device.brightness)
+    }
+}
+
+/// Emulation of the battery `eadk` api.
+pub mod battery {
+    use super::with_device;
+
+    /// Checks whether the battery is currently charging.
+    pub fn is_charging() -> bool {
+        with_device(|device| device.charging)
+    }
+
+    /// Returns the battery level.
+    pub fn get_level() -> u8 {
+        with_device(|device| device.battery_level)
+    }
+
+    /// Returns the battery voltage.
+    pub fn get_voltage() -> f32 {
+        with_device(|device| device.battery_voltage)
+    }
+}
+
+/// Emulation of the display `eadk` api.
+pub mod display {
+    use super::{Device, HostEvent, notify, with_device};
+    use crate::display::{
+        CHAR_HEIGHT, CHAR_WIDTH, Color, LARGE_CHAR_HEIGHT, LARGE_CHAR_WIDTH, Rect, SCREEN_HEIGHT,
+        SCREEN_WIDTH,
+    };
+
+    /// Draws a str to the screen.
+    ///
+    /// There are no fonts on the host: each char is drawn as a cell filled with the
+    /// background color, and every non-whitespace char gets a block of the text color
+    /// inside its cell. This is enough to check where text lands.
+    ///
+    /// # Safety
+    /// It is your responsibility to ensure that the str is terminated with a null byte.
+    pub unsafe fn draw_string(
+        text: *const u8,
+        x: u16,
+        y: u16,
+        large_font: bool,
+        text_color: Color,
+        background_color: Color,
+    ) {
+        let text = unsafe {
+            // SAFETY: the caller ensures the str is null terminated.
+            core::ffi::CStr::from_ptr(text.cast())
+        };
+        let text = core::str::from_utf8(text.to_bytes()).unwrap_or("");
+        let (width, height) = if large_font {
+            (LARGE_CHAR_WIDTH, LARGE_CHAR_HEIGHT)
+        } else {
+            (CHAR_WIDTH, CHAR_HEIGHT)
+        };
+        for (i, c) in text.chars().enumerate() {
+            let Some(cx) = (i as u16)
+                .checked_mul(width)
+                .and_then(|dx| x.checked_add(dx))
+            else {
+                break;
+            };
+            push_rect_uniform(Rect::new(cx, y, width, height), background_color);
+            if !c.is_whitespace() {
+                push_rect_uniform(Rect::new(cx + 1, y + 2, width - 2, height - 4), text_color);
+            }
+        }
+    }
+
+    /// Pushes a slice of colors onto the screen.
+    ///
+    /// The screen is filled from left to right then top to bottom.
+    /// Pixels outside of the screen are dropped.
+    ///
+    /// # Safety
+    /// It is your responsibility to ensure that the rect and the slice's length match.
+    pub unsafe fn push_rect(rect: Rect, pixels: *const Color) {
+        let pixels = unsafe {
+            // SAFETY: the caller ensures the slice covers the whole rect.
+            core::slice::from_raw_parts(pixels, rect.area() as usize)
+        };
+        with_device(|device| {
+            for (i, color) in pixels.iter().enumerate() {
+                let px = rect.x as usize + i % rect.width as usize;
+                let py = rect.y as usize + i / rect.width as usize;
+                if px < SCREEN_WIDTH as usize && py < SCREEN_HEIGHT as usize {
+                    device.framebuffer[py * SCREEN_WIDTH as usize + px] = *color;
+                }
+            }
+        });
+    }
+
+    /// Pull pixels from the screen into a slice of colors.
+    ///
+    /// The screen is read from left to right then top to bottom.
+    /// Pixels outside of the screen are read as black.
+    ///
+    /// # Safety
+    /// It is your responsibility to ensure that the rect and the slice's length match.
+    pub unsafe fn pull_rect(rect: Rect, pixels: *mut Color) {
+        let pixels = unsafe {
+            // SAFETY: the caller ensures the slice covers the whole rect.
+            core::slice::from_raw_parts_mut(pixels, rect.area() as usize)
+        };
+        with_device(|device| {
+            for (i, color) in pixels.iter_mut().enumerate() {
+                let px = rect.x as usize + i % rect.width as usize;
+                let py = rect.y as usize + i / rect.width as usize;
+                *color = if px < SCREEN_WIDTH as usize && py < SCREEN_HEIGHT as usize {
+                    device.framebuffer[py * SCREEN_WIDTH as usize + px]
+                } else {
+                    Color::BLACK
+                };
+            }
+        });
+    }
+
+    /// Draws a rect with the given color.
+    pub fn push_rect_uniform(rect: Rect, color: Color) {
+        with_device(|device| {
+            for idx in Device::rect_indices(rect) {
+                device.framebuffer[idx] = color;
+            }
+        });
+    }
+
+    /// Waits for the screen to finish refreshing.
+    ///
+    /// Never waits, only reports a [`HostEvent::VBlank`] to the hook.
+    pub fn wait_for_vblank() -> bool {
+        notify(HostEvent::VBlank);
+        true
+    }
+}
+
+/// Emulation of the external data `eadk` api.
+pub mod external {
+    use super::with_device;
+
+    /// Returns the external data set with [`super::set_external_data`].
+    pub fn get_data() -> &'static [u8] {
+        with_device(|device| device.external_data)
+    }
+}
+
+/// Emulation of the keyboard `eadk` api.
+pub mod keyboard {
+    use super::{HostEvent, notify, with_device};
+    use crate::keyboard::{Key, KeyboardState, RawKey};
+
+    /// Retrieves the current state of the keyboard.
+    pub fn scan() -> KeyboardState {
+        notify(HostEvent::Scan);
+        KeyboardState(with_device(|device| device.keyboard))
+    }
+
+    /// Pops the oldest queued event.
+    ///
+    /// If there is none, sleeps for `timeout` and returns `Key::None`.
+    pub fn event_get(timeout: i32) -> Key {
+        match with_device(|device| device.events.pop()) {
+            Some(key) => key,
+            None => {
+                super::time::msleep(timeout.max(0) as u32);
+                Key::None
+            }
+        }
+    }
+
+    /// Checks if the key was pressed in the given state.
+    pub fn keyboard_key_down(keyboard_state: KeyboardState, key: RawKey) -> bool {
+        (keyboard_state.0 >> (key as u8)) & 1 != 0
+    }
+}
+
+/// Emulation of the random `eadk` api.
+pub mod random {
+    use super::with_device;
+
+    /// Returns a random u32.
+    ///
+    /// This is a xorshift64* generator, see [`super::seed_random`].
+    pub fn random() -> u32 {
+        with_device(|device| {
+            let mut x = device.rng;
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            device.rng = x;
+            (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
+        })
+    }
+}
+
+/// Emulation of the storage api, see [`crate::storage`].
+pub mod storage {
+    extern crate alloc;
+    extern crate std;
+
+    use alloc::{format, string::String, vec::Vec};
+    use std::fs;
+
+    use super::{STORAGE_SIZE, Storage, with_device};
+    use crate::storage::check_name;
+
+    /// The bytes taken by a record: its size on 2 bytes, its name and its content.
+    fn record_size(name: &str, data_len: usize) -> usize {
+        2 + name.len() + 1 + data_len
+    }
+
+    /// The names of the records with this extension (without the dot), sorted.
+    pub fn list(extension: &str) -> Vec<String> {
+        let suffix = format!(".{extension}");
+        let names: Vec<String> = with_device(|device| match &device.storage {
+            Storage::Memory(records) => records.keys().cloned().collect(),
+            Storage::Directory(dir) => fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+        });
+        let mut names: Vec<String> = names
+            .into_iter()
+            .filter(|name| name.ends_with(&suffix) && check_name(name).is_ok())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Checks if a record exists.
+    pub fn exists(name: &str) -> bool {
+        read(name).is_some()
+    }
+
+    /// The content of a record, `None` if there is none with this name.
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        check_name(name).ok()?;
+        with_device(|device| match &device.storage {
+            Storage::Memory(records) => records.get(name).cloned(),
+            Storage::Directory(dir) => fs::read(dir.join(name)).ok(),
+        })
+    }
+
+    /// Writes a record, replacing the one with the same name.
+    ///
+    /// Fails if the name is invalid or if there is not enough space.
+    pub fn write(name: &str, data: &[u8]) -> Result<(), String> {
+        check_name(name)?;
+        let replaced = read(name).map_or(0, |old| record_size(name, old.len()));
+        if used() - replaced + record_size(name, data.len()) > STORAGE_SIZE {
+            return Err(format!("not enough space to write {name:?}"));
+        }
+        with_device(|device| match &mut device.storage {
+            Storage::Memory(records) => {
+                records.insert(name.into(), data.into());
+                Ok(())
+            }
+            Storage::Directory(dir) => {
+                fs::write(dir.join(name), data).map_err(|e| format!("can't write {name:?}: {e}"))
+            }
+        })
+    }
+
+    /// Deletes a record, returning `false` if there was none with this name.
+    pub fn delete(name: &str) -> bool {
+        if check_name(name).is_err() {
+            return false;
+        }
+        with_device(|device| match &mut device.storage {
+            Storage::Memory(records) => records.remove(name).is_some(),
+            Storage::Directory(dir) => fs::remove_file(dir.join(name)).is_ok(),
+        })
+    }
+
+    /// The size of the storage in bytes, see [`STORAGE_SIZE`].
+    pub fn size() -> usize {
+        STORAGE_SIZE
+    }
+
+    /// The number of bytes used by the records, their names and sizes included.
+    pub fn used() -> usize {
+        let records: Vec<(String, usize)> = with_device(|device| match &device.storage {
+            Storage::Memory(records) => records
+                .iter()
+                .map(|(name, data)| (name.clone(), data.len()))
+                .collect(),
+            Storage::Directory(dir) => fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| {
+                    let len = entry.metadata().ok()?.len() as usize;
+                    Some((entry.file_name().into_string().ok()?, len))
+                })
+                .filter(|(name, _)| check_name(name).is_ok())
+                .collect(),
+        });
+        records
+            .iter()
+            .map(|(name, len)| record_size(name, *len))
+            .sum()
+    }
+}
+
+/// Emulation of the time `eadk` api.
+pub mod time {
+    use super::{HostEvent, notify, with_device};
+
+    /// Moves the virtual clock forward by `ms` milliseconds.
+    pub fn msleep(ms: u32) {
+        usleep(ms.saturating_mul(1000));
+    }
+
+    /// Moves the virtual clock forward by `us` microseconds.
+    pub fn usleep(us: u32) {
+        with_device(|device| device.time_us += us as u64);
+        notify(HostEvent::Sleep { us: us as u64 });
+    }
+
+    /// The number or milliseconds since the start of the virtual clock.
+    pub fn monotonic() -> u64 {
+        with_device(|device| device.time_us / 1000)
+    }
+}
+
+/// Emulation of the USB `eadk` api.
+pub mod usb {
+    use super::with_device;
+
+    /// Checks if the USB is plugged.
+    pub fn is_plugged() -> bool {
+        with_device(|device| device.usb_plugged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{backlight, battery, display, keyboard, random, time, usb};
+
+    #[test]
+    fn reset_restores_the_initial_state() {
+        Rect::SCREEN.fill(Color::RED);
+        press_key(RawKey::Ok);
+        advance_time(5);
+        set_usb_plugged(true);
+        reset();
+        assert!(framebuffer().iter().all(|&color| color == Color::BLACK));
+        assert_eq!(keyboard::scan().0, 0);
+        assert_eq!(time::monotonic(), 0);
+        assert!(!usb::is_plugged());
+        assert_eq!(backlight::get_brightness(), backlight::MAX_BRIGHTNESS);
+        assert_eq!(battery::get_level(), 100);
+    }
+
+    #[test]
+    fn rects_are_drawn_and_read_back() {
+        reset();
+        let rect = Rect::new(10, 20, 3, 2);
+        rect.fill(Color::GREEN);
+        assert_eq!(rect.get_pixels(), vec![Color::GREEN; 6]);
+        let pixels = framebuffer();
+        assert_eq!(pixels[20 * SCREEN_WIDTH as usize + 10], Color::GREEN);
+        assert_eq!(pixels[20 * SCREEN_WIDTH as usize + 13], Color::BLACK);
+    }
+
+    #[test]
+    fn drawing_is_clipped_to_the_screen() {
+        reset();
+        Rect::new(SCREEN_WIDTH - 1, 0, 10, 1).fill(Color::WHITE);
+        assert_eq!(framebuffer()[SCREEN_WIDTH as usize - 1], Color::WHITE);
+        assert_eq!(framebuffer()[SCREEN_WIDTH as usize], Color::BLACK);
+    }
+
+    #[test]
+    fn keys_are_pressed_and_released() {
+        reset();
+        press_key(RawKey::Up);
+        press_key(RawKey::Ok);
+        release_key(RawKey::Up);
+        let state = keyboard::scan();
+        assert!(state.is_pressed(RawKey::Ok));
+        assert!(!state.is_pressed(RawKey::Up));
+        release_all_keys();
+        assert_eq!(keyboard::scan().0, 0);
+    }
+
+    #[test]
+    fn events_come_out_in_order() {
+        reset();
+        queue_event(Key::One);
+        queue_event(Key::Two);
+        assert_eq!(keyboard::eadk::event_get(0), Key::One);
+        assert_eq!(keyboard::eadk::event_get(0), Key::Two);
+        assert_eq!(keyboard::eadk::event_get(100), Key::None);
+        // waiting for nothing took the timeout
+        assert_eq!(time::monotonic(), 100);
+    }
+
+    #[test]
+    fn the_clock_only_moves_when_told() {
+        reset();
+        assert_eq!(time::monotonic(), 0);
+        time::msleep(16);
+        advance_time(4);
+        assert_eq!(time::monotonic(), 20);
+        assert_eq!(time_us(), 20_000);
+    }
+
+    #[test]
+    fn random_numbers_depend_on_the_seed() {
+        reset();
+        let first: Vec<u32> = (0..4).map(|_| random::random()).collect();
+        seed_random(DEFAULT_SEED);
+        let again: Vec<u32> = (0..4).map(|_| random::random()).collect();
+        seed_random(1);
+        let other: Vec<u32> = (0..4).map(|_| random::random()).collect();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn the_hook_sees_sleeps_vblanks_and_scans() {
+        reset();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = events.clone();
+        set_hook(move |event| log.borrow_mut().push(event));
+        time::msleep(3);
+        display::eadk::wait_for_vblank();
+        keyboard::scan();
+        clear_hook();
+        time::msleep(3);
+        keyboard::scan();
+        assert_eq!(
+            *events.borrow(),
+            [
+                HostEvent::Sleep { us: 3000 },
+                HostEvent::VBlank,
+                HostEvent::Scan
+            ]
+        );
+    }
+
+    #[test]
+    fn a_hook_can_remove_itself() {
+        reset();
+        let calls = Rc::new(Cell::new(0));
+        let count = calls.clone();
+        set_hook(move |_| {
+            count.set(count.get() + 1);
+            clear_hook();
+        });
+        time::msleep(1);
+        time::msleep(1);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn a_hook_can_replace_itself() {
+        reset();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = events.clone();
+        set_hook(move |_| {
+            let log = log.clone();
+            log.borrow_mut().push("first");
+            set_hook(move |_| log.borrow_mut().push("second"));
+        });
+        time::msleep(1);
+        time::msleep(1);
+        time::msleep(1);
+        clear_hook();
+        assert_eq!(*events.borrow(), ["first", "second", "second"]);
+    }
+}
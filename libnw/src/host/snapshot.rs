@@ -0,0 +1,646 @@
+//! Golden-image snapshots of the emulated screen.
+//!
+//! A [`Snapshot`] is a copy of (part of) the framebuffer. It can be written as a
+//! PPM or PNG image, read back from a PPM image, and compared pixel by pixel with
+//! another snapshot.
+//!
+//! [`assert_snapshot`] does all of this at once: it compares the screen against a
+//! reference PPM file and panics with a diff report if they differ.
+//!
+//! # Example
+//! ```no_run
+//! use libnw::display::{Color, Rect};
+//!
+//! libnw::host::reset();
+//! Rect::new(10, 10, 40, 20).fill(Color::RED);
+//! libnw::host::snapshot::assert_snapshot("tests/snapshots/red_rect.ppm");
+//! ```
+
+extern crate alloc;
+extern crate std;
+
+use alloc::{format, string::String, string::ToString, vec::Vec};
+use core::fmt::{self, Display};
+use std::{env, fs, path::Path};
+
+use super::with_framebuffer;
+use crate::display::{Color, Rect, SCREEN_WIDTH};
+
+/// When this environment variable is set, [`assert_snapshot`] overwrites the
+/// reference images instead of comparing against them.
+pub const UPDATE_ENV_VAR: &str = "LIBNW_UPDATE_SNAPSHOTS";
+
+/// A copy of some pixels of the screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub width: u16,
+    pub height: u16,
+    /// The pixels, from left to right then top to bottom.
+    pub pixels: Vec<Color>,
+}
+
+impl Snapshot {
+    /// Captures the whole screen.
+    pub fn capture() -> Self {
+        Self::capture_rect(Rect::SCREEN)
+    }
+
+    /// Captures the pixels in `rect`, clipped to the screen.
+    pub fn capture_rect(rect: Rect) -> Self {
+        let rect =
+            Rect::screen_space_clipping(rect.x as i32, rect.y as i32, rect.width, rect.height);
+        let pixels = with_framebuffer(|framebuffer| {
+            (rect.y..rect.y + rect.height)
+                .flat_map(|y| {
+                    let start = y as usize * SCREEN_WIDTH as usize + rect.x as usize;
+                    framebuffer[start..start + rect.width as usize]
+                        .iter()
+                        .copied()
+                })
+                .collect()
+        });
+        Self {
+            width: rect.width,
+            height: rect.height,
+            pixels,
+        }
+    }
+
+    /// Returns the color of the pixel at (`x`, `y`), if it is inside the snapshot.
+    pub fn pixel(&self, x: u16, y: u16) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y as usize * self.width as usize + x as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Encodes the snapshot as a binary PPM (P6) image.
+    ///
+    /// RGB565 colors survive the round trip through [`Snapshot::from_ppm`].
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.extend(self.rgb_bytes());
+        data
+    }
+
+    /// Decodes a binary PPM (P6) image with a max value of 255.
+    pub fn from_ppm(data: &[u8]) -> Result<Self, String> {
+        // header : magic, width, height and max value, separated by whitespace
+        let mut fields = Vec::with_capacity(4);
+        let mut pos = 0;
+        while fields.len() < 4 {
+            while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+                if data[pos] == b'#' {
+                    while pos < data.len() && data[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err("Truncated PPM header".to_string());
+            }
+            fields.push(core::str::from_utf8(&data[start..pos]).unwrap_or(""));
+        }
+        // exactly one whitespace between the header and the pixels
+        pos += 1;
+
+        if fields[0] != "P6" {
+            return Err(format!(
+                "Unsupported PPM format {:?}, expected P6",
+                fields[0]
+            ));
+        }
+        let parse = |field: &str| field.parse::<u16>().map_err(|e| e.to_string());
+        let width = parse(fields[1])?;
+        let height = parse(fields[2])?;
+        if fields[3] != "255" {
+            return Err(format!(
+                "Unsupported PPM max value {}, expected 255",
+                fields[3]
+            ));
+        }
+
+        let expected = width as usize * height as usize * 3;
+        let body = data.get(pos..).unwrap_or(&[]);
+        if body.len() != expected {
+            return Err(format!(
+                "PPM body is {} bytes long, expected {expected}",
+                body.len()
+            ));
+        }
+        let pixels = body
+            .chunks_exact(3)
+            .map(|rgb| Color::from_rgb(rgb[0], rgb[1], rgb[2]))
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Encodes the snapshot as an (uncompressed) PNG image.
+    ///
+    /// Fails if the snapshot is empty, PNG images being at least 1×1 pixel large:
+    /// use [`Self::to_ppm`] instead.
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!(
+                "Can't encode an empty {}x{} snapshot as PNG",
+                self.width, self.height
+            ));
+        }
+        let mut data = Vec::from(*b"\x89PNG\r\n\x1a\n");
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend((self.width as u32).to_be_bytes());
+        ihdr.extend((self.height as u32).to_be_bytes());
+        // bit depth 8, truecolor, deflate, no filter, no interlace
+        ihdr.extend([8, 2, 0, 0, 0]);
+        push_png_chunk(&mut data, b"IHDR", &ihdr);
+
+        // each scanline starts with its filter type (0 : none)
+        let rgb = self.rgb_bytes().collect::<Vec<u8>>();
+        let mut raw = Vec::with_capacity(rgb.len() + self.height as usize);
+        let stride = self.width as usize * 3;
+        for y in 0..self.height as usize {
+            raw.push(0);
+            raw.extend_from_slice(&rgb[y * stride..(y + 1) * stride]);
+        }
+        push_png_chunk(&mut data, b"IDAT", &zlib_store(&raw));
+
+        push_png_chunk(&mut data, b"IEND", &[]);
+        Ok(data)
+    }
+
+    /// Writes the snapshot to `path` as a PPM image.
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        write_file(path.as_ref(), &self.to_ppm())
+    }
+
+    /// Writes the snapshot to `path` as a PNG image.
+    ///
+    /// Fails if the snapshot is empty, see [`Self::to_png`].
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        write_file(path.as_ref(), &self.to_png()?)
+    }
+
+    /// Reads a snapshot from a PPM image.
+    pub fn load_ppm(path: impl AsRef<Path>) -> Result<Self, String> {
+        let data = fs::read(path.as_ref()).map_err(|e| e.to_string())?;
+        Self::from_ppm(&data)
+    }
+
+    /// Compares `self` with a `reference` snapshot.
+    pub fn diff(&self, reference: &Snapshot) -> SnapshotDiff {
+        if (self.width, self.height) != (reference.width, reference.height) {
+            return SnapshotDiff::SizeMismatch {
+                actual: (self.width, self.height),
+                expected: (reference.width, reference.height),
+            };
+        }
+        let mut count = 0;
+        let mut max_delta = 0;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u16::MAX, u16::MAX, 0, 0);
+        let mut first = None;
+        for (i, (actual, expected)) in self.pixels.iter().zip(&reference.pixels).enumerate() {
+            if actual == expected {
+                continue;
+            }
+            let x = (i % self.width as usize) as u16;
+            let y = (i / self.width as usize) as u16;
+            count += 1;
+            first.get_or_insert((x, y, *actual, *expected));
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+            let (ar, ag, ab) = actual.separate_rgb();
+            let (er, eg, eb) = expected.separate_rgb();
+            max_delta = max_delta
+                .max(ar.abs_diff(er))
+                .max(ag.abs_diff(eg))
+                .max(ab.abs_diff(eb));
+        }
+        match first {
+            None => SnapshotDiff::Identical,
+            Some(first) => SnapshotDiff::Pixels {
+                count,
+                bounds: Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1),
+                max_channel_delta: max_delta,
+                first,
+            },
+        }
+    }
+
+    /// Builds an image highlighting the pixels that differ from `reference` in red,
+    /// on top of a darkened copy of `self`.
+    ///
+    /// Returns `None` if the sizes don't match.
+    pub fn diff_image(&self, reference: &Snapshot) -> Option<Snapshot> {
+        if (self.width, self.height) != (reference.width, reference.height) {
+            return None;
+        }
+        let pixels = self
+            .pixels
+            .iter()
+            .zip(&reference.pixels)
+            .map(|(actual, expected)| {
+                if actual == expected {
+                    let (r, g, b) = actual.separate_rgb();
+                    Color::from_rgb(r / 4, g / 4, b / 4)
+                } else {
+                    Color::RED
+                }
+            })
+            .collect();
+        Some(Snapshot {
+            width: self.width,
+            height: self.height,
+            pixels,
+        })
+    }
+
+    /// The pixels as a sequence of 8-bit red, green and blue channels.
+    fn rgb_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pixels.iter().flat_map(|color| {
+            let (r, g, b) = color.separate_rgb();
+            [r, g, b]
+        })
+    }
+}
+
+/// The result of [`Snapshot::diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotDiff {
+    /// Both snapshots are the same.
+    Identical,
+    /// The snapshots don't have the same size, so they were not compared further.
+    SizeMismatch {
+        actual: (u16, u16),
+        expected: (u16, u16),
+    },
+    /// Some pixels differ.
+    Pixels {
+        /// How many pixels differ.
+        count: usize,
+        /// The smallest rect containing every differing pixel.
+        bounds: Rect,
+        /// The largest difference on a single channel (0 to 255).
+        max_channel_delta: u8,
+        /// The first differing pixel: (x, y, actual, expected).
+        first: (u16, u16, Color, Color),
+    },
+}
+
+impl SnapshotDiff {
+    /// Checks if the snapshots were identical.
+    pub fn is_identical(&self) -> bool {
+        matches!(self, Self::Identical)
+    }
+}
+
+impl Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Identical => write!(f, "snapshots are identical"),
+            Self::SizeMismatch { actual, expected } => write!(
+                f,
+                "size mismatch: got {}×{}, expected {}×{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            Self::Pixels {
+                count,
+                bounds,
+                max_channel_delta,
+                first: (x, y, actual, expected),
+            } => write!(
+                f,
+                "{count} pixel(s) differ inside {}×{} at ({}, {}), max channel delta {max_channel_delta}; \
+                 first at ({x}, {y}): got {:?}, expected {:?}",
+                bounds.width,
+                bounds.height,
+                bounds.x,
+                bounds.y,
+                actual.separate_rgb(),
+                expected.separate_rgb(),
+            ),
+        }
+    }
+}
+
+/// Compares the whole screen against the reference PPM image at `path`.
+///
+/// If [`UPDATE_ENV_VAR`] is set, the reference is (over)written with the current screen
+/// instead.
+///
+/// # Panics
+/// Panics if the reference doesn't exist, or with a diff report if the screen doesn't
+/// match it. The actual screen (and a diff image, when the sizes match) is then written
+/// next to the reference, with the `.actual.ppm` and `.diff.ppm` extensions.
+pub fn assert_snapshot(path: impl AsRef<Path>) {
+    assert_snapshot_rect(path, Rect::SCREEN);
+}
+
+/// Same as [`assert_snapshot`], but only compares the pixels in `rect`.
+pub fn assert_snapshot_rect(path: impl AsRef<Path>, rect: Rect) {
+    check_snapshot(path.as_ref(), rect, env::var_os(UPDATE_ENV_VAR).is_some());
+}
+
+/// Compares the pixels in `rect` against the reference at `path`, or overwrites the
+/// reference if `update` is set.
+fn check_snapshot(path: &Path, rect: Rect, update: bool) {
+    let actual = Snapshot::capture_rect(rect);
+    if update {
+        if let Err(e) = actual.save_ppm(path) {
+            panic!("Failed to write snapshot {}: {e}", path.display());
+        }
+        return;
+    }
+    let actual_path = path.with_extension("actual.ppm");
+    if !path.exists() {
+        let _ = actual.save_ppm(&actual_path);
+        panic!(
+            "Missing snapshot {}\nactual: {}\n(set {UPDATE_ENV_VAR} to create it)",
+            path.display(),
+            actual_path.display(),
+        );
+    }
+    let reference = match Snapshot::load_ppm(path) {
+        Ok(reference) => reference,
+        Err(e) => panic!("Failed to read snapshot {}: {e}", path.display()),
+    };
+    let diff = actual.diff(&reference);
+    if diff.is_identical() {
+        return;
+    }
+    let _ = actual.save_ppm(&actual_path);
+    let diff_path = path.with_extension("diff.ppm");
+    if let Some(diff_image) = actual.diff_image(&reference) {
+        let _ = diff_image.save_ppm(&diff_path);
+    }
+    panic!(
+        "Screen doesn't match snapshot {}: {diff}\nactual: {}\ndiff: {}\n(set {UPDATE_ENV_VAR} to accept the new screen)",
+        path.display(),
+        actual_path.display(),
+        diff_path.display(),
+    );
+}
+
+/// Writes `data` to `path`, creating the parent directories if needed.
+fn write_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Appends a PNG chunk (length, type, data and CRC) to `png`.
+fn push_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    // deflate, 32K window, no dictionary, fastest
+    out.extend([0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        out.push(is_final as u8);
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// The CRC-32 used by PNG.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// The Adler-32 checksum used by zlib.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3×2 snapshot with distinct RGB565 colors.
+    fn sample() -> Snapshot {
+        Snapshot {
+            width: 3,
+            height: 2,
+            pixels: Vec::from([
+                Color::RED,
+                Color::GREEN,
+                Color::BLUE,
+                Color::WHITE,
+                Color::BLACK,
+                Color::from_rgb(0x18, 0x9C, 0xF8),
+            ]),
+        }
+    }
+
+    /// Reads the (length, type, data) chunks of a PNG image, checking their CRC.
+    fn png_chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let body = &rest[4..8 + len];
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc32(body), crc);
+            chunks.push((body[..4].try_into().unwrap(), &body[4..]));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    /// Unwraps a zlib stream made of uncompressed deflate blocks, checking its checksum.
+    fn zlib_unstore(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(&stream[..2], [0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut pos = 2;
+        loop {
+            let is_final = stream[pos] & 1 != 0;
+            let len = u16::from_le_bytes([stream[pos + 1], stream[pos + 2]]);
+            let nlen = u16::from_le_bytes([stream[pos + 3], stream[pos + 4]]);
+            assert_eq!(len, !nlen);
+            data.extend_from_slice(&stream[pos + 5..pos + 5 + len as usize]);
+            pos += 5 + len as usize;
+            if is_final {
+                break;
+            }
+        }
+        assert_eq!(&stream[pos..], adler32(&data).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn ppm_round_trips() {
+        let snapshot = sample();
+        let ppm = snapshot.to_ppm();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(Snapshot::from_ppm(&ppm), Ok(snapshot));
+    }
+
+    #[test]
+    fn ppm_headers_may_have_comments() {
+        let mut ppm = Vec::from(*b"P6 # a comment\n1\n1 255\n");
+        ppm.extend([0xFF, 0, 0]);
+        let snapshot = Snapshot::from_ppm(&ppm).unwrap();
+        assert_eq!(snapshot.pixels, [Color::RED]);
+    }
+
+    #[test]
+    fn bad_ppm_images_are_rejected() {
+        assert!(Snapshot::from_ppm(b"P3\n1 1\n255\n\0\0\0").is_err());
+        assert!(Snapshot::from_ppm(b"P6\n1 1\n65535\n\0\0\0").is_err());
+        assert!(Snapshot::from_ppm(b"P6\n1 1\n255\n\0\0").is_err());
+        assert!(Snapshot::from_ppm(b"P6\n1").is_err());
+    }
+
+    #[test]
+    fn png_holds_the_same_pixels() {
+        let snapshot = sample();
+        let png = snapshot.to_png().unwrap();
+        let chunks = png_chunks(&png);
+        let kinds = chunks.iter().map(|(kind, _)| kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+
+        let raw = zlib_unstore(chunks[1].1);
+        let mut rgb = Vec::new();
+        for line in raw.chunks(1 + 3 * 3) {
+            assert_eq!(line[0], 0);
+            rgb.extend_from_slice(&line[1..]);
+        }
+        assert_eq!(rgb, snapshot.rgb_bytes().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn large_png_data_is_split_in_blocks() {
+        let data = (0..70_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        assert_eq!(zlib_unstore(&zlib_store(&data)), data);
+        assert_eq!(zlib_unstore(&zlib_store(&[])), []);
+    }
+
+    #[test]
+    fn checksums_match_the_reference_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn diff_reports_the_count_and_bounds() {
+        let reference = sample();
+        assert_eq!(reference.diff(&reference), SnapshotDiff::Identical);
+
+        let mut actual = reference.clone();
+        actual.pixels[1] = Color::BLACK;
+        actual.pixels[5] = Color::BLACK;
+        assert_eq!(
+            actual.diff(&reference),
+            SnapshotDiff::Pixels {
+                count: 2,
+                bounds: Rect::new(1, 0, 2, 2),
+                max_channel_delta: 0xFF,
+                first: (1, 0, Color::BLACK, Color::GREEN),
+            }
+        );
+
+        let diff_image = actual.diff_image(&reference).unwrap();
+        assert_eq!(diff_image.pixel(1, 0), Some(Color::RED));
+        assert_eq!(
+            diff_image.pixel(0, 1),
+            Some(Color::from_rgb(0x3E, 0x3F, 0x3E))
+        );
+    }
+
+    #[test]
+    fn diff_reports_a_size_mismatch() {
+        let reference = sample();
+        let actual = Snapshot {
+            width: 2,
+            height: 3,
+            pixels: reference.pixels.clone(),
+        };
+        assert_eq!(
+            actual.diff(&reference),
+            SnapshotDiff::SizeMismatch {
+                actual: (2, 3),
+                expected: (3, 2),
+            }
+        );
+        assert_eq!(actual.diff_image(&reference), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing snapshot")]
+    fn a_missing_reference_fails() {
+        let path = env::temp_dir().join("libnw-missing-snapshot/none.ppm");
+        // whatever the value of `UPDATE_ENV_VAR` in the environment
+        check_snapshot(&path, Rect::SCREEN, false);
+    }
+
+    #[test]
+    fn empty_snapshots_are_only_encoded_as_ppm() {
+        crate::host::reset();
+        let offscreen = Snapshot::capture_rect(Rect::new(400, 10, 20, 20));
+        assert_eq!(offscreen.pixels.len(), 0);
+        for snapshot in [
+            offscreen,
+            Snapshot {
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+            },
+        ] {
+            assert!(snapshot.to_png().is_err());
+            let path = env::temp_dir().join("libnw-empty-snapshot.png");
+            assert!(snapshot.save_png(&path).is_err());
+            assert!(!path.exists());
+            assert_eq!(Snapshot::from_ppm(&snapshot.to_ppm()), Ok(snapshot));
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Action-based input mapping over the raw keys.
+//!
+//! Instead of checking `RawKey::Ok || RawKey::Exe` everywhere, a game declares its actions
+//! and binds them to keys (or combinations of keys) in an [`InputMap`],
+//! which can be remapped at runtime and saved.
+//!
+//! # Example :
+//! ```
+//! use libnw::{
+//!     input::{Action, InputMap},
+//!     keyboard::{KeyboardTimedState, RawKey},
+//! };
+//!
+//! #[derive(Clone, Copy, PartialEq, Eq)]
+//! enum Control {
+//!     Confirm,
+//!     Cancel,
+//! }
+//!
+//! impl Action for Control {
+//!     fn id(self) -> u8 {
+//!         self as u8
+//!     }
+//!     fn from_id(id: u8) -> Option<Self> {
+//!         [Control::Confirm, Control::Cancel].get(id as usize).copied()
+//!     }
+//! }
+//!
+//! let controls = InputMap::new()
+//!     .with(Control::Confirm, RawKey::Ok)
+//!     .with(Control::Confirm, RawKey::Exe)
+//!     .with(Control::Cancel, RawKey::Back);
+//! let mut keyboard_state = KeyboardTimedState::new();
+//! keyboard_state.fetch();
+//! if controls.is_just_pressed(&keyboard_state, Control::Confirm) {
+//!     // ...
+//! }
+//! ```
+
+extern crate alloc;
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::keyboard::{KeyboardState, KeyboardTimedState, RawKey};
+
+/// The first bytes of saved bindings, see [`InputMap::to_bytes`].
+pub const INPUT_MAP_MAGIC: &[u8; 4] = b"NWIM";
+
+/// Something the player can do, bound to keys by an [`InputMap`].
+pub trait Action: Copy + PartialEq {
+    /// A unique id, used to save the bindings.
+    fn id(self) -> u8;
+    /// The action with this id, if any.
+    fn from_id(id: u8) -> Option<Self>;
+}
+
+/// A combination of keys that must all be pressed together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord(u64);
+
+impl Chord {
+    /// A combination of all `keys`.
+    pub fn new(keys: &[RawKey]) -> Self {
+        Self(keys.iter().fold(0, |chord, &key| chord | (1 << key as u8)))
+    }
+
+    /// Checks if `key` is part of the combination.
+    pub fn contains(self, key: RawKey) -> bool {
+        (self.0 >> key as u8) & 1 != 0
+    }
+
+    /// Checks if every key of the combination is pressed.
+    pub fn is_pressed(self, keyboard_state: &KeyboardTimedState) -> bool {
+        self.is_in(keyboard_state.state())
+    }
+
+    /// Checks if every key of the combination is pressed, the last of them just now.
+    pub fn is_just_pressed(self, keyboard_state: &KeyboardTimedState) -> bool {
+        self.is_pressed(keyboard_state) && self.touches(keyboard_state.just_pressed_state())
+    }
+
+    /// The combination of the keys whose bit is set, if they all are keys.
+    fn from_bits(bits: u64) -> Option<Self> {
+        let all_keys = (0..64).all(|i| bits >> i & 1 == 0 || RawKey::try_from(i as u8).is_ok());
+        (bits != 0 && all_keys).then_some(Self(bits))
+    }
+
+    /// Checks if every key of the combination is pressed in `state`.
+    fn is_in(self, state: KeyboardState) -> bool {
+        self.0 != 0 && state.0 & self.0 == self.0
+    }
+
+    /// Checks if one of the keys of the combination is pressed in `state`.
+    fn touches(self, state: KeyboardState) -> bool {
+        state.0 & self.0 != 0
+    }
+}
+
+impl From<RawKey> for Chord {
+    fn from(key: RawKey) -> Self {
+        Self::new(&[key])
+    }
+}
+
+/// Binds actions to keys or combinations of keys.
+///
+/// An action may have several bindings, it is triggered by any of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputMap<A> {
+    bindings: Vec<(A, Chord)>,
+}
+
+impl<A: Action> Default for InputMap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Action> InputMap<A> {
+    /// Creates a map without any binding.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Adds a binding, to declare the map in one expression.
+    pub fn with(mut self, action: A, chord: impl Into<Chord>) -> Self {
+        self.bind(action, chord);
+        self
+    }
+
+    /// Adds a binding, keeping the other ones of `action`.
+    pub fn bind(&mut self, action: A, chord: impl Into<Chord>) {
+        let chord = chord.into();
+        if !self.bindings.contains(&(action, chord)) {
+            self.bindings.push((action, chord));
+        }
+    }
+
+    /// Removes every binding of `action`.
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.retain(|(bound, _)| *bound != action);
+    }
+
+    /// Replaces every binding of `action` by `chord`.
+    pub fn rebind(&mut self, action: A, chord: impl Into<Chord>) {
+        self.unbind(action);
+        self.bind(action, chord);
+    }
+
+    /// The combinations bound to `action`.
+    pub fn chords(&self, action: A) -> impl Iterator<Item = Chord> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(bound, _)| *bound == action)
+            .map(|(_, chord)| *chord)
+    }
+
+    /// Checks if `action` is currently triggered.
+    pub fn is_pressed(&self, keyboard_state: &KeyboardTimedState, action: A) -> bool {
+        self.chords(action)
+            .any(|chord| chord.is_pressed(keyboard_state))
+    }
+
+    /// Checks if `action` is just triggered: all the keys of a combination are pressed,
+    /// the last of them just now.
+    pub fn is_just_pressed(&self, keyboard_state: &KeyboardTimedState, action: A) -> bool {
+        self.chords(action)
+            .any(|chord| chord.is_just_pressed(keyboard_state))
+    }
+
+    /// Checks if `action` is just not triggered anymore.
+    pub fn is_just_released(&self, keyboard_state: &KeyboardTimedState, action: A) -> bool {
+        let now = keyboard_state.state();
+        let before = KeyboardState(
+            (now.0 & !keyboard_state.just_pressed_state().0)
+                | keyboard_state.just_released_state().0,
+        );
+        // not released while another binding still triggers it
+        self.chords(action).any(|chord| chord.is_in(before))
+            && !self.chords(action).any(|chord| chord.is_in(now))
+    }
+
+    /// Checks if `action` is just triggered, or held long enough to repeat.
+    ///
+    /// See [`KeyboardTimedState::is_key_repeated`].
+    pub fn is_repeated(&self, keyboard_state: &KeyboardTimedState, action: A) -> bool {
+        self.chords(action).any(|chord| {
+            chord.is_in(keyboard_state.state()) && chord.touches(keyboard_state.repeated_state())
+        })
+    }
+
+    /// -1, 0 or 1, depending on which of two opposite actions are triggered.
+    pub fn axis(&self, keyboard_state: &KeyboardTimedState, negative: A, positive: A) -> i8 {
+        self.is_pressed(keyboard_state, positive) as i8
+            - self.is_pressed(keyboard_state, negative) as i8
+    }
+
+    /// Saves the bindings.
+    ///
+    /// The format is the 4 bytes of [`INPUT_MAP_MAGIC`], then for each binding the
+    /// id of the action followed by the combination as a little endian u64.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = INPUT_MAP_MAGIC.to_vec();
+        for (action, chord) in &self.bindings {
+            bytes.push(action.id());
+            bytes.extend_from_slice(&chord.0.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Loads bindings saved with [`Self::to_bytes`].
+    ///
+    /// Unknown actions, and combinations that are empty or hold values that aren't keys,
+    /// are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let bindings = bytes
+            .strip_prefix(INPUT_MAP_MAGIC)
+            .ok_or("Not an input map (bad magic)")?;
+        if bindings.len() % 9 != 0 {
+            return Err("Truncated input map".to_string());
+        }
+        let mut map = Self::new();
+        for binding in bindings.chunks_exact(9) {
+            let action = A::from_id(binding[0]).ok_or("Unknown action in input map")?;
+            let mut chord = [0; 8];
+            chord.copy_from_slice(&binding[1..]);
+            let chord =
+                Chord::from_bits(u64::from_le_bytes(chord)).ok_or("Bad keys in input map")?;
+            map.bind(action, chord);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::ManualClock;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Control {
+        Confirm,
+        Cancel,
+        Menu,
+    }
+
+    impl Action for Control {
+        fn id(self) -> u8 {
+            self as u8
+        }
+        fn from_id(id: u8) -> Option<Self> {
+            [Control::Confirm, Control::Cancel, Control::Menu]
+                .get(id as usize)
+                .copied()
+        }
+    }
+
+    fn controls() -> InputMap<Control> {
+        InputMap::new()
+            .with(Control::Confirm, RawKey::Ok)
+            .with(Control::Confirm, RawKey::Exe)
+            .with(Control::Cancel, RawKey::Back)
+            .with(Control::Menu, Chord::new(&[RawKey::Shift, RawKey::Home]))
+    }
+
+    /// A keyboard that went from `before` to `now`.
+    fn keyboard(before: &[RawKey], now: &[RawKey]) -> KeyboardTimedState {
+        let mut keyboard =
+            KeyboardTimedState::from_source(&mut || KeyboardState(0), &ManualClock::new());
+        keyboard.update_with(KeyboardState(Chord::new(before).0), 10);
+        keyboard.update_with(KeyboardState(Chord::new(now).0), 20);
+        keyboard
+    }
+
+    #[test]
+    fn bindings_round_trip_through_bytes() {
+        let map = controls();
+        let bytes = map.to_bytes();
+        assert_eq!(bytes.len(), 4 + 4 * 9);
+        assert_eq!(InputMap::from_bytes(&bytes), Ok(map));
+        let empty = InputMap::<Control>::new();
+        assert_eq!(InputMap::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn bad_bytes_are_rejected() {
+        let bytes = controls().to_bytes();
+        for len in [0, 3, 5, bytes.len() - 1] {
+            assert!(
+                InputMap::<Control>::from_bytes(&bytes[..len]).is_err(),
+                "{len} bytes"
+            );
+        }
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(InputMap::<Control>::from_bytes(&bad_magic).is_err());
+        let mut bad_action = bytes.clone();
+        bad_action[4] = 3;
+        assert!(InputMap::<Control>::from_bytes(&bad_action).is_err());
+        // no key, or a value that isn't a key (7)
+        for chord in [0, 1 << 7, u64::MAX] {
+            let mut bad_chord = INPUT_MAP_MAGIC.to_vec();
+            bad_chord.push(0);
+            bad_chord.extend(chord.to_le_bytes());
+            assert!(
+                InputMap::<Control>::from_bytes(&bad_chord).is_err(),
+                "{chord:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn any_binding_triggers_the_action() {
+        let map = controls();
+        assert!(map.is_pressed(&keyboard(&[], &[RawKey::Exe]), Control::Confirm));
+        assert!(map.is_just_pressed(&keyboard(&[], &[RawKey::Ok]), Control::Confirm));
+        assert!(!map.is_just_pressed(&keyboard(&[RawKey::Ok], &[RawKey::Ok]), Control::Confirm));
+        assert!(!map.is_pressed(&keyboard(&[], &[RawKey::Ok]), Control::Cancel));
+    }
+
+    #[test]
+    fn chords_need_every_key() {
+        let map = controls();
+        assert!(!map.is_pressed(&keyboard(&[], &[RawKey::Shift]), Control::Menu));
+        let both = [RawKey::Shift, RawKey::Home];
+        // the last key of the chord makes it just pressed, whatever the order
+        assert!(map.is_just_pressed(&keyboard(&[RawKey::Shift], &both), Control::Menu));
+        assert!(map.is_just_pressed(&keyboard(&[RawKey::Home], &both), Control::Menu));
+        assert!(map.is_just_pressed(&keyboard(&[], &both), Control::Menu));
+        assert!(!map.is_just_pressed(&keyboard(&both, &both), Control::Menu));
+        // other keys may be pressed too
+        let more = [RawKey::Shift, RawKey::Home, RawKey::Ok];
+        assert!(map.is_pressed(&keyboard(&both, &more), Control::Menu));
+    }
+
+    #[test]
+    fn releasing_a_key_of_the_chord_releases_the_action() {
+        let map = controls();
+        let both = [RawKey::Shift, RawKey::Home];
+        assert!(map.is_just_released(&keyboard(&both, &[RawKey::Shift]), Control::Menu));
+        assert!(map.is_just_released(&keyboard(&both, &[]), Control::Menu));
+        assert!(!map.is_just_released(&keyboard(&both, &both), Control::Menu));
+        assert!(!map.is_just_released(&keyboard(&[RawKey::Shift], &[]), Control::Menu));
+        assert!(map.is_just_released(&keyboard(&[RawKey::Ok], &[]), Control::Confirm));
+        // still held through the other binding
+        let both_bindings = [RawKey::Ok, RawKey::Exe];
+        assert!(!map.is_just_released(&keyboard(&both_bindings, &[RawKey::Exe]), Control::Confirm));
+    }
+
+    #[test]
+    fn axis_cancels_out() {
+        let map = controls();
+        assert_eq!(
+            map.axis(
+                &keyboard(&[], &[RawKey::Ok]),
+                Control::Cancel,
+                Control::Confirm
+            ),
+            1
+        );
+        assert_eq!(
+            map.axis(
+                &keyboard(&[], &[RawKey::Back]),
+                Control::Cancel,
+                Control::Confirm
+            ),
+            -1
+        );
+        let both = [RawKey::Ok, RawKey::Back];
+        assert_eq!(
+            map.axis(&keyboard(&[], &both), Control::Cancel, Control::Confirm),
+            0
+        );
+    }
+
+    #[test]
+    fn rebinding_replaces_the_chords() {
+        let mut map = controls();
+        map.rebind(Control::Confirm, RawKey::Plus);
+        assert_eq!(
+            map.chords(Control::Confirm).collect::<Vec<_>>(),
+            [Chord::from(RawKey::Plus)]
+        );
+        map.bind(Control::Confirm, RawKey::Plus);
+        assert_eq!(map.chords(Control::Confirm).count(), 1);
+        map.unbind(Control::Confirm);
+        assert_eq!(map.chords(Control::Confirm).count(), 0);
+    }
+}
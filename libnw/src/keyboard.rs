@@ -6,8 +6,11 @@ use alloc::format;
 use core::fmt::Display;
 use core::mem;
 
+use crate::time::{Clock, EadkClock};
+
 /// A hardware key
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RawKey {
     Left = 0,
     Up = 1,
@@ -61,7 +64,7 @@ pub enum RawKey {
 ///
 /// The result of pressing a key with or without modifiers (shift and alpha).
 #[repr(u16)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Key {
     Left = 0,
     Up = 1,
@@ -203,6 +206,7 @@ impl Key {
                 | Self::Five
                 | Self::Six
                 | Self::Seven
+                | Self::Eight
                 | Self::Nine
                 | Self::Zero
         )
@@ -224,6 +228,117 @@ impl Key {
             _ => None,
         }
     }
+
+    /// The number added to a raw key when pressed with shift.
+    pub const SHIFT_OFFSET: u16 = 54;
+    /// The number added to a raw key when pressed with alpha.
+    pub const ALPHA_OFFSET: u16 = 108;
+
+    /// The key produced by pressing `raw` with or without the modifiers, if any.
+    ///
+    /// With both modifiers, letters are uppercase.
+    pub fn from_raw(raw: RawKey, shift: bool, alpha: bool) -> Option<Key> {
+        let offset = shift as u16 * Self::SHIFT_OFFSET + alpha as u16 * Self::ALPHA_OFFSET;
+        Key::try_from(raw as u16 + offset).ok()
+    }
+
+    /// Converts the key to the character it types, if it types a printable ASCII one.
+    pub fn to_char(&self) -> Option<char> {
+        let value = *self as u16;
+        // letters are sorted, but skip a value every 5 or 6 letters
+        let letter = |first: u16, base: u8| Some((base + (value - first) as u8) as char);
+        match value {
+            126..=142 => letter(126, b'a'),
+            144..=148 => letter(144, b'r'),
+            150..=153 => letter(150, b'w'),
+            180..=196 => letter(180, b'A'),
+            198..=202 => letter(198, b'R'),
+            204..=207 => letter(204, b'W'),
+            _ => match self {
+                Self::Comma => Some(','),
+                Self::Power => Some('^'),
+                Self::LeftParenthesis => Some('('),
+                Self::RightParenthesis => Some(')'),
+                Self::Multiplication => Some('*'),
+                Self::Division => Some('/'),
+                Self::Plus => Some('+'),
+                Self::Minus => Some('-'),
+                Self::Dot => Some('.'),
+                Self::LeftBracket => Some('['),
+                Self::RightBracket => Some(']'),
+                Self::LeftBrace => Some('{'),
+                Self::RightBrace => Some('}'),
+                Self::Underscore => Some('_'),
+                Self::Equal => Some('='),
+                Self::Lower => Some('<'),
+                Self::Greater => Some('>'),
+                Self::Colon => Some(':'),
+                Self::Semicolon => Some(';'),
+                Self::DoubleQuotes => Some('"'),
+                Self::Percent => Some('%'),
+                Self::Space => Some(' '),
+                Self::Question => Some('?'),
+                Self::Exclamation => Some('!'),
+                _ => self.to_digit().map(|digit| (b'0' + digit) as char),
+            },
+        }
+    }
+}
+
+impl TryFrom<u16> for Key {
+    /// The value that isn't a key.
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0..=5
+            | 12..=34
+            | 36..=40
+            | 42..=46
+            | 48..=52
+            | 54..=57
+            | 67..=83
+            | 122..=142
+            | 144..=148
+            | 150..=154
+            | 156..=157
+            | 180..=196
+            | 198..=202
+            | 204..=207
+            | 223 => Ok(unsafe {
+                // SAFETY: `Key` is `repr(u16)` and the value is one of its discriminants.
+                core::mem::transmute::<u16, Key>(value)
+            }),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<u8> for RawKey {
+    /// The value that isn't a key.
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0..=6 | 8 | 12..=34 | 36..=40 | 42..=46 | 48..=52 => Ok(unsafe {
+                // SAFETY: `RawKey` is `repr(u8)` and the value is one of its discriminants.
+                core::mem::transmute::<u8, RawKey>(value)
+            }),
+            _ => Err(value),
+        }
+    }
+}
+
+impl TryFrom<u16> for RawKey {
+    /// The value that isn't a key.
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        u8::try_from(value)
+            .ok()
+            .and_then(|value| RawKey::try_from(value).ok())
+            .ok_or(value)
+    }
 }
 
 impl Display for Key {
@@ -247,6 +362,31 @@ impl KeyboardState {
 
 pub use eadk::scan;
 
+/// Where the keyboard state comes from, to run the same code against the real keyboard,
+/// a recording or a script.
+///
+/// Any `FnMut() -> KeyboardState` is a source.
+pub trait InputSource {
+    /// The keys pressed now.
+    fn scan(&mut self) -> KeyboardState;
+}
+
+/// The keyboard of the calculator, read with [`scan`].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct EadkInput;
+
+impl InputSource for EadkInput {
+    fn scan(&mut self) -> KeyboardState {
+        scan()
+    }
+}
+
+impl<F: FnMut() -> KeyboardState> InputSource for F {
+    fn scan(&mut self) -> KeyboardState {
+        self()
+    }
+}
+
 /// Waits for `timeout` or until a key is pressed.
 ///
 /// If the timeout is reached, returns `None`.
@@ -269,36 +409,147 @@ pub fn currently_pressed() -> Key {
     eadk::event_get(1)
 }
 
+/// When held keys repeat, see [`KeyboardTimedState::is_key_repeated`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyRepeat {
+    /// The time a key must be held before it starts repeating, in milliseconds.
+    pub delay_ms: u64,
+    /// The time between two repeats, in milliseconds.
+    pub interval_ms: u64,
+}
+
+impl KeyRepeat {
+    /// The number of repeats after holding a key for `held_ms`.
+    fn count(&self, held_ms: u64) -> u64 {
+        if held_ms < self.delay_ms {
+            0
+        } else {
+            1 + (held_ms - self.delay_ms) / self.interval_ms.max(1)
+        }
+    }
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay_ms: 400,
+            interval_ms: 100,
+        }
+    }
+}
+
 /// Follow keyboard state through time.
 ///
 /// Warning : having more than one instance of this
 /// or calling any `keyboard::scan()`-like method
 /// can break it (miss some events).
+///
+/// Hold durations are measured with `time::monotonic` (or the clock given to
+/// `fetch_from`), between two fetches.
 pub struct KeyboardTimedState {
     current_state: KeyboardState,
     pressing_state: KeyboardState,
     releasing_state: KeyboardState,
+    /// When each key was last pressed (in ms), indexed by `RawKey as u8`.
+    pressed_at: [u64; 64],
+    /// The time of the last fetch (in ms).
+    now: u64,
+    /// The time of the fetch before the last one (in ms).
+    previous: u64,
+    repeat: Option<KeyRepeat>,
 }
 
 impl KeyboardTimedState {
     /// Creates new instance.
     pub fn new() -> Self {
+        Self::from_source(&mut EadkInput, &EadkClock)
+    }
+
+    /// Creates new instance, reading the keyboard from `input` at the time of `clock`.
+    pub fn from_source(input: &mut impl InputSource, clock: &impl Clock) -> Self {
         let mut kbts = Self {
             current_state: KeyboardState(0),
             pressing_state: KeyboardState(0),
             releasing_state: KeyboardState(0),
+            pressed_at: [0; 64],
+            now: 0,
+            previous: 0,
+            repeat: Some(KeyRepeat::default()),
         };
         // fetch 2 times to avoid unwanted keypress when launching app
-        kbts.fetch();
-        kbts.fetch();
+        kbts.fetch_from(input, clock);
+        kbts.fetch_from(input, clock);
         kbts
     }
 
     /// Update the state (fetch new state / events).
     pub fn fetch(&mut self) {
-        let previous_state = mem::replace(&mut self.current_state, scan());
+        self.fetch_from(&mut EadkInput, &EadkClock);
+    }
+
+    /// Update the state from `input`, at the time of `clock`.
+    pub fn fetch_from(&mut self, input: &mut impl InputSource, clock: &impl Clock) {
+        self.update_with(input.scan(), clock.now().as_millis());
+    }
+
+    /// Update the state with an already scanned keyboard `state`, seen at `now` (in ms).
+    ///
+    /// `now` may be earlier than the last update (e.g. a clock that was reset): the keys
+    /// pressed before are then seen as held for 0 ms.
+    pub fn update_with(&mut self, state: KeyboardState, now: u64) {
+        let previous_state = mem::replace(&mut self.current_state, state);
         self.pressing_state = KeyboardState((!previous_state.0) & self.current_state.0);
         self.releasing_state = KeyboardState(previous_state.0 & (!self.current_state.0));
+        self.previous = mem::replace(&mut self.now, now);
+        for (i, pressed_at) in self.pressed_at.iter_mut().enumerate() {
+            if (self.pressing_state.0 >> i) & 1 != 0 {
+                *pressed_at = now;
+            }
+        }
+    }
+
+    /// Sets when held keys repeat, `None` to disable the repeats.
+    pub fn set_repeat(&mut self, repeat: Option<KeyRepeat>) {
+        self.repeat = repeat;
+    }
+
+    /// When held keys repeat.
+    pub fn repeat(&self) -> Option<KeyRepeat> {
+        self.repeat
+    }
+
+    /// The keys currently pressed.
+    pub fn state(&self) -> KeyboardState {
+        self.current_state
+    }
+
+    /// The keys just pressed (pressed now but not before).
+    pub fn just_pressed_state(&self) -> KeyboardState {
+        self.pressing_state
+    }
+
+    /// The keys just released (not pressed now but were before).
+    pub fn just_released_state(&self) -> KeyboardState {
+        self.releasing_state
+    }
+
+    /// The keys just pressed, or held long enough to repeat since the last fetch.
+    ///
+    /// See [`Self::is_key_repeated`].
+    pub fn repeated_state(&self) -> KeyboardState {
+        let mut state = self.pressing_state.0;
+        if let Some(repeat) = self.repeat {
+            for (i, &pressed_at) in self.pressed_at.iter().enumerate() {
+                let held = (self.current_state.0 & !self.pressing_state.0) >> i & 1 != 0;
+                if held
+                    && repeat.count(self.now.saturating_sub(pressed_at))
+                        > repeat.count(self.previous.saturating_sub(pressed_at))
+                {
+                    state |= 1 << i;
+                }
+            }
+        }
+        KeyboardState(state)
     }
 
     /// Checks if `key` is currently pressed.
@@ -315,11 +566,55 @@ impl KeyboardTimedState {
     pub fn is_key_just_released(&self, key: RawKey) -> bool {
         eadk::keyboard_key_down(KeyboardState(self.releasing_state.0), key)
     }
+
+    /// Checks if `key` is just pressed, or held long enough to repeat since the last fetch.
+    ///
+    /// Handy to scroll through menus while a key is held.
+    pub fn is_key_repeated(&self, key: RawKey) -> bool {
+        self.repeated_state().is_pressed(key)
+    }
+
+    /// For how long `key` has been held, in milliseconds.
+    ///
+    /// For a key just released, this is for how long it was held.
+    /// Returns `None` if the key is neither pressed nor just released.
+    pub fn key_hold_duration(&self, key: RawKey) -> Option<u64> {
+        if self.is_key_pressed(key) || self.is_key_just_released(key) {
+            Some(self.held_for(key))
+        } else {
+            None
+        }
+    }
+
+    /// Checks if `key` is pressed and has been held for at least `ms` milliseconds.
+    pub fn is_key_long_pressed(&self, key: RawKey, ms: u64) -> bool {
+        self.is_key_pressed(key) && self.held_for(key) >= ms
+    }
+
+    /// Checks if `key` has just been held for `ms` milliseconds (only true for one fetch).
+    pub fn is_key_just_long_pressed(&self, key: RawKey, ms: u64) -> bool {
+        self.is_key_long_pressed(key, ms)
+            && (self.is_key_just_pressed(key)
+                || self.previous.saturating_sub(self.pressed_at[key as usize]) < ms)
+    }
+
+    /// Checks if `key` is just released after being held for less than `ms` milliseconds.
+    ///
+    /// Together with [`Self::is_key_just_long_pressed`], this tells a tap from a long press.
+    pub fn is_key_just_tapped(&self, key: RawKey, ms: u64) -> bool {
+        self.is_key_just_released(key) && self.held_for(key) < ms
+    }
+
+    /// The time since `key` was last pressed, in milliseconds.
+    fn held_for(&self, key: RawKey) -> u64 {
+        self.now.saturating_sub(self.pressed_at[key as usize])
+    }
 }
 
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     use super::{Key, KeyboardState, RawKey};
 
@@ -350,3 +645,317 @@ pub mod eadk {
         safe fn eadk_event_get(timeout: *mut i32) -> Key;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::keyboard as eadk;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::time::{Instant, ManualClock};
+
+    const OK: KeyboardState = KeyboardState(1 << RawKey::Ok as u64);
+    const NONE: KeyboardState = KeyboardState(0);
+
+    /// A keyboard fetched from scripted states on a virtual clock.
+    struct Driver {
+        keyboard: KeyboardTimedState,
+        clock: ManualClock,
+    }
+
+    impl Driver {
+        fn new() -> Self {
+            let clock = ManualClock::new();
+            let keyboard = KeyboardTimedState::from_source(&mut || NONE, &clock);
+            Self { keyboard, clock }
+        }
+
+        /// Fetches `state` at `ms` milliseconds.
+        fn fetch_at(&mut self, ms: u64, state: KeyboardState) -> &KeyboardTimedState {
+            self.clock = ManualClock::starting_at(Instant::from_millis(ms));
+            self.keyboard.fetch_from(&mut || state, &self.clock);
+            &self.keyboard
+        }
+
+        /// The times OK repeats at while held from 0 to `until`, fetched every `step` ms.
+        fn repeats(&mut self, until: u64, step: u64) -> Vec<u64> {
+            (0..=until)
+                .step_by(step as usize)
+                .filter(|&ms| self.fetch_at(ms, OK).is_key_repeated(RawKey::Ok))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn held_keys_repeat_after_a_delay() {
+        let mut driver = Driver::new();
+        assert_eq!(driver.repeats(700, 50), [0, 400, 500, 600, 700]);
+    }
+
+    #[test]
+    fn repeats_follow_the_settings() {
+        let mut driver = Driver::new();
+        driver.keyboard.set_repeat(Some(KeyRepeat {
+            delay_ms: 200,
+            interval_ms: 30,
+        }));
+        assert_eq!(driver.repeats(300, 10), [0, 200, 230, 260, 290]);
+
+        let mut driver = Driver::new();
+        driver.keyboard.set_repeat(None);
+        assert_eq!(driver.repeats(1000, 50), [0]);
+    }
+
+    #[test]
+    fn slow_fetches_repeat_once() {
+        let mut driver = Driver::new();
+        // the 400 and 500 ms repeats both happen between these fetches
+        assert_eq!(driver.repeats(1000, 550), [0, 550]);
+        assert!(!driver.fetch_at(560, OK).is_key_repeated(RawKey::Ok));
+    }
+
+    #[test]
+    fn long_presses_start_at_the_threshold() {
+        let mut driver = Driver::new();
+        driver.fetch_at(100, OK);
+        let keyboard = driver.fetch_at(590, OK);
+        assert!(!keyboard.is_key_long_pressed(RawKey::Ok, 500));
+        assert_eq!(keyboard.key_hold_duration(RawKey::Ok), Some(490));
+        let keyboard = driver.fetch_at(600, OK);
+        assert!(keyboard.is_key_long_pressed(RawKey::Ok, 500));
+        assert!(keyboard.is_key_just_long_pressed(RawKey::Ok, 500));
+        let keyboard = driver.fetch_at(610, OK);
+        assert!(keyboard.is_key_long_pressed(RawKey::Ok, 500));
+        assert!(!keyboard.is_key_just_long_pressed(RawKey::Ok, 500));
+        let keyboard = driver.fetch_at(620, NONE);
+        assert_eq!(keyboard.key_hold_duration(RawKey::Ok), Some(520));
+        assert!(!keyboard.is_key_just_tapped(RawKey::Ok, 500));
+        assert_eq!(
+            driver.fetch_at(630, NONE).key_hold_duration(RawKey::Ok),
+            None
+        );
+    }
+
+    #[test]
+    fn short_presses_are_taps() {
+        let mut driver = Driver::new();
+        driver.fetch_at(100, OK);
+        assert!(!driver.fetch_at(200, OK).is_key_just_tapped(RawKey::Ok, 500));
+        assert!(
+            driver
+                .fetch_at(300, NONE)
+                .is_key_just_tapped(RawKey::Ok, 500)
+        );
+        // a threshold of 0 always is a long press
+        driver.fetch_at(400, OK);
+        assert!(driver.keyboard.is_key_just_long_pressed(RawKey::Ok, 0));
+        assert!(!driver.fetch_at(400, NONE).is_key_just_tapped(RawKey::Ok, 0));
+    }
+
+    #[test]
+    fn the_keyboard_survives_a_clock_going_back() {
+        let mut driver = Driver::new();
+        driver.fetch_at(1000, OK);
+        let keyboard = driver.fetch_at(500, OK);
+        assert_eq!(keyboard.key_hold_duration(RawKey::Ok), Some(0));
+        assert!(!keyboard.is_key_long_pressed(RawKey::Ok, 100));
+        assert!(!keyboard.is_key_just_long_pressed(RawKey::Ok, 100));
+        assert!(!keyboard.is_key_repeated(RawKey::Ok));
+        assert!(
+            driver
+                .fetch_at(400, NONE)
+                .is_key_just_tapped(RawKey::Ok, 100)
+        );
+    }
+
+    /// The first and the last key of each range of values.
+    const KEY_BOUNDS: [(Key, u16); 29] = [
+        (Key::Left, 0),
+        (Key::Back, 5),
+        (Key::Shift, 12),
+        (Key::RightParenthesis, 34),
+        (Key::Four, 36),
+        (Key::Division, 40),
+        (Key::One, 42),
+        (Key::Minus, 46),
+        (Key::Zero, 48),
+        (Key::Exe, 52),
+        (Key::ShiftLeft, 54),
+        (Key::ShiftRight, 57),
+        (Key::AlphaLock, 67),
+        (Key::Greater, 83),
+        (Key::Colon, 122),
+        (Key::LowerQ, 142),
+        (Key::LowerR, 144),
+        (Key::LowerV, 148),
+        (Key::LowerW, 150),
+        (Key::Space, 154),
+        (Key::Question, 156),
+        (Key::Exclamation, 157),
+        (Key::UpperA, 180),
+        (Key::UpperQ, 196),
+        (Key::UpperR, 198),
+        (Key::UpperV, 202),
+        (Key::UpperW, 204),
+        (Key::UpperZ, 207),
+        (Key::None, 223),
+    ];
+
+    /// The values right outside of the ranges, which are not keys.
+    const KEY_GAPS: [u16; 25] = [
+        6, 11, 35, 41, 47, 53, 58, 66, 84, 121, 143, 149, 155, 158, 179, 197, 203, 208, 222, 224,
+        255, 256, 1000, 0x8000, 0xFFFF,
+    ];
+
+    /// The first and the last raw key of each range of values.
+    const RAW_KEY_BOUNDS: [(RawKey, u8); 11] = [
+        (RawKey::Left, 0),
+        (RawKey::Home, 6),
+        (RawKey::OnOff, 8),
+        (RawKey::Shift, 12),
+        (RawKey::RightParenthesis, 34),
+        (RawKey::Four, 36),
+        (RawKey::Division, 40),
+        (RawKey::One, 42),
+        (RawKey::Minus, 46),
+        (RawKey::Zero, 48),
+        (RawKey::Exe, 52),
+    ];
+
+    /// The values right outside of the ranges, which are not raw keys.
+    const RAW_KEY_GAPS: [u8; 10] = [7, 9, 11, 35, 41, 47, 53, 54, 200, 255];
+
+    #[test]
+    fn key_values_match_the_table() {
+        for (key, value) in KEY_BOUNDS {
+            assert_eq!(Key::try_from(value), Ok(key), "{value}");
+        }
+        for value in KEY_GAPS {
+            assert_eq!(Key::try_from(value), Err(value));
+        }
+        let keys = (0..=u16::MAX)
+            .filter(|&value| Key::try_from(value).is_ok())
+            .count();
+        assert_eq!(keys, 125);
+    }
+
+    #[test]
+    fn raw_key_values_match_the_table() {
+        for (raw, value) in RAW_KEY_BOUNDS {
+            assert_eq!(RawKey::try_from(value), Ok(raw), "{value}");
+            assert_eq!(RawKey::try_from(value as u16), Ok(raw), "{value}");
+        }
+        for value in RAW_KEY_GAPS {
+            assert_eq!(RawKey::try_from(value), Err(value));
+        }
+        assert_eq!(RawKey::try_from(256u16), Err(256));
+        let raw_keys = (0..=u8::MAX)
+            .filter(|&value| RawKey::try_from(value).is_ok())
+            .count();
+        assert_eq!(raw_keys, 46);
+    }
+
+    #[test]
+    fn raw_keys_type_the_key_of_the_same_value() {
+        let typed = [
+            (RawKey::Left, Some(Key::Left)),
+            (RawKey::Back, Some(Key::Back)),
+            (RawKey::Home, None),
+            (RawKey::OnOff, None),
+            (RawKey::Shift, Some(Key::Shift)),
+            (RawKey::RightParenthesis, Some(Key::RightParenthesis)),
+            (RawKey::Four, Some(Key::Four)),
+            (RawKey::Division, Some(Key::Division)),
+            (RawKey::One, Some(Key::One)),
+            (RawKey::Minus, Some(Key::Minus)),
+            (RawKey::Zero, Some(Key::Zero)),
+            (RawKey::Exe, Some(Key::Exe)),
+        ];
+        for (raw, key) in typed {
+            assert_eq!(Key::from_raw(raw, false, false), key, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn letters_skip_the_missing_values() {
+        let letters = [
+            RawKey::Exp,
+            RawKey::Ln,
+            RawKey::Log,
+            RawKey::Imaginary,
+            RawKey::Comma,
+            RawKey::Power,
+            RawKey::Sine,
+            RawKey::Cosine,
+            RawKey::Tangent,
+            RawKey::Pi,
+            RawKey::Sqrt,
+            RawKey::Square,
+            RawKey::Seven,
+            RawKey::Eight,
+            RawKey::Nine,
+            RawKey::LeftParenthesis,
+            RawKey::RightParenthesis,
+            RawKey::Four,
+            RawKey::Five,
+            RawKey::Six,
+            RawKey::Multiplication,
+            RawKey::Division,
+            RawKey::One,
+            RawKey::Two,
+            RawKey::Three,
+            RawKey::Plus,
+        ];
+        let typed = |shift: bool| -> alloc::string::String {
+            letters
+                .iter()
+                .map(|&raw| {
+                    Key::from_raw(raw, shift, true)
+                        .and_then(|key| key.to_char())
+                        .unwrap()
+                })
+                .collect()
+        };
+        assert_eq!(typed(false), "abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(typed(true), "ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+        assert_eq!(Key::from_raw(RawKey::Minus, false, true), Some(Key::Space));
+    }
+
+    #[test]
+    fn keys_type_their_characters() {
+        assert_eq!(Key::Zero.to_char(), Some('0'));
+        assert_eq!(Key::Nine.to_char(), Some('9'));
+        assert_eq!(Key::Percent.to_char(), Some('%'));
+        assert_eq!(Key::Exclamation.to_char(), Some('!'));
+        assert_eq!(
+            Key::from_raw(RawKey::Backspace, false, true),
+            Some(Key::Percent)
+        );
+        assert_eq!(
+            Key::from_raw(RawKey::Backspace, true, false),
+            Some(Key::Clear)
+        );
+        for key in [
+            Key::Ok,
+            Key::Backspace,
+            Key::Shift,
+            Key::Clear,
+            Key::Sine,
+            Key::None,
+        ] {
+            assert_eq!(key.to_char(), None, "{key:?}");
+        }
+        // every char is printable ASCII, and only typed by a single key
+        let mut typed = alloc::vec::Vec::new();
+        for key in (0..=u16::MAX).filter_map(|value| Key::try_from(value).ok()) {
+            if let Some(ch) = key.to_char() {
+                assert!(ch.is_ascii_graphic() || ch == ' ', "{key:?}");
+                assert!(!typed.contains(&ch), "{ch:?} typed twice");
+                typed.push(ch);
+            }
+        }
+        assert_eq!(typed.len(), 10 + 2 * 26 + 24);
+    }
+}
@@ -1,16 +1,20 @@
 #![no_std]
 
+#[cfg(not(feature = "host"))]
 #[used]
 #[unsafe(link_section = ".rodata.eadk_api_level")]
 static EADK_API_LEVEL: u32 = 0;
 
-mod heap;
-
 pub mod backlight;
 pub mod battery;
 pub mod display;
 pub mod external;
+pub mod heap;
+#[cfg(feature = "host")]
+pub mod host;
+pub mod input;
 pub mod keyboard;
 pub mod random;
+pub mod storage;
 pub mod time;
 pub mod usb;
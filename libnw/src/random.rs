@@ -1,11 +1,18 @@
 //! Provides utility functions for generating random values such as integers, colors, and rectangles.
+//!
+//! The functions use the hardware generator, use [`Rng`] to get reproducible sequences.
 
 use crate::display::{Color, Rect, SCREEN_HEIGHT, SCREEN_WIDTH};
 
 pub use eadk::random;
 
 /// Returns a random unsigned integer in [a, b[.
+///
+/// Returns `a` if the range is empty (`b <= a`).
 pub fn randuint(a: u32, b: u32) -> u32 {
+    if b <= a {
+        return a;
+    }
     let range = b - a;
     let random_number = eadk::random() % range;
     random_number + a
@@ -30,9 +37,178 @@ pub fn random_rect() -> Rect {
     Rect::new(x, y, width, height)
 }
 
+/// A seedable pseudo-random number generator (PCG32).
+///
+/// The same seed always gives the same sequence, on the device as on the host,
+/// so procedural content and replays can be reproduced.
+///
+/// # Example :
+/// ```
+/// use libnw::random::Rng;
+///
+/// let mut rng = Rng::new(42);
+/// let dice = rng.range_u32(1, 7);
+/// assert!((1..7).contains(&dice));
+/// let mut holes = [1, 2, 3, 4];
+/// rng.shuffle(&mut holes);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+    /// Always odd.
+    increment: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, Self::DEFAULT_STREAM)
+    }
+
+    /// Creates a generator from a seed and a stream.
+    ///
+    /// Different streams give different sequences for the same seed.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    /// Creates a generator seeded from the hardware generator.
+    pub fn from_hardware() -> Self {
+        Self::new(((eadk::random() as u64) << 32) | eadk::random() as u64)
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    /// Returns a random u32.
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Returns a random u64.
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Returns a random integer in [0, n[, without modulo bias.
+    ///
+    /// Returns 0 if `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        // Lemire's method: reject the few values that would favor some results
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let m = self.next_u32() as u64 * n as u64;
+            if m as u32 >= threshold {
+                return (m >> 32) as u32;
+            }
+        }
+    }
+
+    /// Returns a random unsigned integer in [a, b[, or `a` if the range is empty.
+    pub fn range_u32(&mut self, a: u32, b: u32) -> u32 {
+        if b <= a {
+            return a;
+        }
+        a + self.below(b - a)
+    }
+
+    /// Returns a random integer in [a, b[, or `a` if the range is empty.
+    pub fn range_i32(&mut self, a: i32, b: i32) -> i32 {
+        if b <= a {
+            return a;
+        }
+        a.wrapping_add(self.below(b.wrapping_sub(a) as u32) as i32)
+    }
+
+    /// Returns a random float in [0, 1[.
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits, the precision of a f32
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a random float in [a, b[.
+    pub fn range_f32(&mut self, a: f32, b: f32) -> f32 {
+        a + (b - a) * self.next_f32()
+    }
+
+    /// Returns `true` or `false` with the same probability.
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    /// Returns `true` with a probability of `p` (from 0 to 1).
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+
+    /// Returns a random element of `items`, `None` if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        items.get(self.below(items.len() as u32) as usize)
+    }
+
+    /// Returns a random index of `weights`, each index having a probability
+    /// proportional to its weight.
+    ///
+    /// Returns `None` if every weight is 0.
+    pub fn weighted_index(&mut self, weights: &[u32]) -> Option<usize> {
+        let total = weights.iter().map(|&w| w as u64).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = if total <= u32::MAX as u64 {
+            self.below(total as u32) as u64
+        } else {
+            self.next_u64() % total
+        };
+        for (i, &weight) in weights.iter().enumerate() {
+            if pick < weight as u64 {
+                return Some(i);
+            }
+            pick -= weight as u64;
+        }
+        None
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+impl Default for Rng {
+    /// Seeded from the hardware generator.
+    fn default() -> Self {
+        Self::from_hardware()
+    }
+}
+
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// Returns a random u32.
@@ -40,3 +216,104 @@ pub mod eadk {
         pub safe fn random() -> u32;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::random as eadk;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg32_gives_the_reference_sequence() {
+        // the first outputs of the reference `pcg32-demo`, seeded with 42 on stream 54
+        let mut rng = Rng::with_stream(42, 54);
+        let outputs = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        for expected in outputs {
+            assert_eq!(rng.next_u32(), expected);
+        }
+    }
+
+    #[test]
+    fn seeds_and_streams_give_other_sequences() {
+        let first = |mut rng: Rng| [rng.next_u32(), rng.next_u32()];
+        assert_eq!(first(Rng::new(7)), first(Rng::new(7)));
+        assert_ne!(first(Rng::new(7)), first(Rng::new(8)));
+        assert_ne!(first(Rng::with_stream(7, 1)), first(Rng::with_stream(7, 2)));
+    }
+
+    #[test]
+    fn ranges_stay_in_their_bounds() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            assert!((3..10).contains(&rng.range_u32(3, 10)));
+            assert!((-5..5).contains(&rng.range_i32(-5, 5)));
+            assert!((0.5..2.).contains(&rng.range_f32(0.5, 2.)));
+            assert!((0. ..1.).contains(&rng.next_f32()));
+            assert!(rng.below(1) == 0);
+        }
+        // the whole range is reached
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[rng.range_u32(3, 10) as usize - 3] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+        let full = rng.range_i32(i32::MIN, i32::MAX);
+        assert!(full < i32::MAX);
+    }
+
+    #[test]
+    fn empty_ranges_give_their_start() {
+        let mut rng = Rng::new(1);
+        assert_eq!(rng.below(0), 0);
+        assert_eq!(rng.range_u32(5, 5), 5);
+        assert_eq!(rng.range_u32(7, 3), 7);
+        assert_eq!(rng.range_i32(-2, -2), -2);
+        assert_eq!(rng.range_i32(4, -4), 4);
+        assert_eq!(rng.choose::<u8>(&[]), None);
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn randuint_handles_empty_ranges() {
+        crate::host::reset();
+        assert_eq!(randuint(5, 5), 5);
+        assert_eq!(randuint(7, 3), 7);
+        for _ in 0..100 {
+            assert!((2..4).contains(&randuint(2, 4)));
+        }
+    }
+
+    #[test]
+    fn zero_weights_are_never_picked() {
+        let mut rng = Rng::new(3);
+        assert_eq!(rng.weighted_index(&[]), None);
+        assert_eq!(rng.weighted_index(&[0, 0, 0]), None);
+        for _ in 0..1000 {
+            let index = rng.weighted_index(&[0, 3, 0, 1, 0]).unwrap();
+            assert!(index == 1 || index == 3);
+        }
+        assert_eq!(rng.weighted_index(&[0, 0, 5]), Some(2));
+        // totals above u32::MAX
+        let index = rng.weighted_index(&[u32::MAX, u32::MAX, 0]).unwrap();
+        assert!(index < 2);
+    }
+
+    #[test]
+    fn shuffle_keeps_every_element() {
+        let mut rng = Rng::new(4);
+        let mut items: [u32; 20] = core::array::from_fn(|i| i as u32);
+        rng.shuffle(&mut items);
+        assert_ne!(items, core::array::from_fn(|i| i as u32));
+        let mut sorted = items;
+        sorted.sort();
+        assert_eq!(sorted, core::array::from_fn(|i| i as u32));
+        // nothing to shuffle
+        rng.shuffle::<u8>(&mut []);
+        let mut one = [9];
+        rng.shuffle(&mut one);
+        assert_eq!(one, [9]);
+    }
+}
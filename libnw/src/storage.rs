@@ -0,0 +1,414 @@
+//! Interface with the file storage of the calculator, kept between launches.
+//!
+//! The storage holds named records, the ones of the other apps included (scripts,
+//! lists...). A name is made of a base name and an extension (`golf.sav`), use an
+//! extension of your own to find your records back with [`list`].
+//!
+//! The eadk doesn't give access to the storage: the functions of the `eadk` module
+//! come from the `storage.c` of the numworks-extapp-storage library, which must be
+//! compiled and linked with the app. They are only declared with the `extapp-storage`
+//! feature: without it, the calculator has no storage, [`write`] failing with an error
+//! telling so.
+//!
+//! # Example :
+//! ```
+//! use libnw::storage;
+//!
+//! let hits = 3;
+//! let best = storage::read("golf.sav").and_then(|data| data.first().copied());
+//! storage::write("golf.sav", &[best.map_or(hits, |best| best.min(hits))])?;
+//! # Ok::<(), String>(())
+//! ```
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+
+/// Checks that `name` can name a record: a base name and an extension,
+/// with neither `'\0'` nor `'/'`.
+pub fn check_name(name: &str) -> Result<(), String> {
+    let valid = match name.rsplit_once('.') {
+        Some((base, extension)) => !base.is_empty() && !extension.is_empty(),
+        None => false,
+    };
+    if !valid || name.contains(['\0', '/']) {
+        return Err(format!("invalid record name {name:?}"));
+    }
+    Ok(())
+}
+
+/// The number of bytes left to write records.
+pub fn free_space() -> usize {
+    size().saturating_sub(used())
+}
+
+#[cfg(all(not(feature = "host"), feature = "extapp-storage"))]
+pub use device::*;
+
+#[cfg(all(not(feature = "host"), not(feature = "extapp-storage")))]
+pub use unlinked::*;
+
+#[cfg(feature = "host")]
+pub use crate::host::storage::{delete, exists, list, read, size, used, write};
+
+/// The safe rust implementations, over the `eadk` functions.
+#[cfg(all(not(feature = "host"), feature = "extapp-storage"))]
+mod device {
+    extern crate alloc;
+
+    use alloc::{ffi::CString, format, string::String, vec::Vec};
+    use core::ffi::CStr;
+
+    use super::{check_name, eadk};
+
+    /// The most records `list` returns.
+    const MAX_RECORDS: usize = 64;
+
+    /// `name` as a C string, if it is valid.
+    fn c_name(name: &str) -> Result<CString, String> {
+        check_name(name)?;
+        CString::new(name).map_err(|e| format!("{e}"))
+    }
+
+    /// The names of the records with this extension (without the dot), sorted.
+    ///
+    /// At most 64 names are returned.
+    pub fn list(extension: &str) -> Vec<String> {
+        let Ok(extension) = CString::new(extension) else {
+            return Vec::new();
+        };
+        let mut names = [core::ptr::null(); MAX_RECORDS];
+        let count = unsafe {
+            // SAFETY: `names` holds `MAX_RECORDS` pointers, the names are copied
+            // before the storage changes.
+            eadk::file_list_with_extension(
+                names.as_mut_ptr(),
+                MAX_RECORDS as i32,
+                extension.as_ptr(),
+            )
+        };
+        let mut names: Vec<String> = names[..count.clamp(0, MAX_RECORDS as i32) as usize]
+            .iter()
+            .filter(|name| !name.is_null())
+            .map(|&name| unsafe { CStr::from_ptr(name) }.to_string_lossy().into())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Checks if a record exists.
+    pub fn exists(name: &str) -> bool {
+        c_name(name).is_ok_and(|name| unsafe { eadk::file_exists(name.as_ptr()) })
+    }
+
+    /// The content of a record, `None` if there is none with this name.
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        let name = c_name(name).ok()?;
+        let mut len = 0;
+        let data = unsafe { eadk::file_read(name.as_ptr(), &mut len) };
+        if data.is_null() {
+            return None;
+        }
+        // SAFETY: the record is `len` bytes long, and copied before the storage changes.
+        Some(unsafe { core::slice::from_raw_parts(data, len) }.to_vec())
+    }
+
+    /// Writes a record, replacing the one with the same name.
+    ///
+    /// Fails if the name is invalid (see [`check_name`]) or if there is not enough space.
+    pub fn write(name: &str, data: &[u8]) -> Result<(), String> {
+        let c_name = c_name(name)?;
+        match unsafe { eadk::file_write(c_name.as_ptr(), data.as_ptr(), data.len()) } {
+            true => Ok(()),
+            false => Err(format!("not enough space to write {name:?}")),
+        }
+    }
+
+    /// Deletes a record, returning `false` if there was none with this name.
+    pub fn delete(name: &str) -> bool {
+        c_name(name).is_ok_and(|name| unsafe { eadk::file_erase(name.as_ptr()) })
+    }
+
+    /// The size of the storage in bytes, the records of every app included.
+    pub fn size() -> usize {
+        eadk::size() as usize
+    }
+
+    /// The number of bytes used by the records, their names and headers included.
+    pub fn used() -> usize {
+        eadk::used() as usize
+    }
+}
+
+/// A storage without records nor space, when `storage.c` is not linked.
+#[cfg(all(not(feature = "host"), not(feature = "extapp-storage")))]
+mod unlinked {
+    extern crate alloc;
+
+    use alloc::{string::String, vec::Vec};
+
+    use super::check_name;
+
+    /// No names, there are no records.
+    pub fn list(_extension: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Always `false`, there are no records.
+    pub fn exists(_name: &str) -> bool {
+        false
+    }
+
+    /// Always `None`, there are no records.
+    pub fn read(_name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Always fails, telling how to get a storage.
+    pub fn write(name: &str, _data: &[u8]) -> Result<(), String> {
+        check_name(name)?;
+        Err(String::from(
+            "no storage: build libnw with the `extapp-storage` feature and link storage.c",
+        ))
+    }
+
+    /// Always `false`, there are no records.
+    pub fn delete(_name: &str) -> bool {
+        false
+    }
+
+    /// Always 0, there is no storage.
+    pub fn size() -> usize {
+        0
+    }
+
+    /// Always 0, there is no storage.
+    pub fn used() -> usize {
+        0
+    }
+}
+
+/// Interface with the raw C api of the storage.
+///
+/// If you don't know what you are doing, use the safe rust implementations.
+///
+/// # Link
+/// These functions are not part of the eadk, see the documentation of this module.
+#[cfg(all(not(feature = "host"), feature = "extapp-storage"))]
+pub mod eadk {
+    use core::ffi::c_char;
+
+    unsafe extern "C" {
+        /// Fills `filenames` with up to `max_records` pointers to the names of the records
+        /// ending with `.extension`, returning how many were found.
+        ///
+        /// # Safety
+        /// `filenames` must hold `max_records` pointers, `extension` must be null-terminated.
+        /// The names point into the storage, and are invalidated when it changes.
+        #[link_name = "extapp_fileListWithExtension"]
+        pub fn file_list_with_extension(
+            filenames: *mut *const c_char,
+            max_records: i32,
+            extension: *const c_char,
+        ) -> i32;
+
+        /// Checks if a record exists.
+        ///
+        /// # Safety
+        /// `filename` must be null-terminated.
+        #[link_name = "extapp_fileExists"]
+        pub fn file_exists(filename: *const c_char) -> bool;
+
+        /// Returns a pointer to the content of a record and writes its length to `len`,
+        /// or returns null if there is none.
+        ///
+        /// # Safety
+        /// `filename` must be null-terminated. The content points into the storage,
+        /// and is invalidated when it changes.
+        #[link_name = "extapp_fileRead"]
+        pub fn file_read(filename: *const c_char, len: *mut usize) -> *const u8;
+
+        /// Writes a record, returning `false` if there is not enough space.
+        ///
+        /// # Safety
+        /// `filename` must be null-terminated, `content` must be `len` bytes long.
+        #[link_name = "extapp_fileWrite"]
+        pub fn file_write(filename: *const c_char, content: *const u8, len: usize) -> bool;
+
+        /// Deletes a record, returning `false` if there is none.
+        ///
+        /// # Safety
+        /// `filename` must be null-terminated.
+        #[link_name = "extapp_fileErase"]
+        pub fn file_erase(filename: *const c_char) -> bool;
+
+        /// The size of the storage in bytes.
+        #[link_name = "extapp_size"]
+        pub safe fn size() -> u32;
+
+        /// The number of bytes used by the records.
+        #[link_name = "extapp_used"]
+        pub safe fn used() -> u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_need_a_base_and_an_extension() {
+        assert!(check_name("golf.sav").is_ok());
+        assert!(check_name("my.golf.sav").is_ok());
+        assert!(check_name("golf").is_err());
+        assert!(check_name(".sav").is_err());
+        assert!(check_name("golf.").is_err());
+        assert!(check_name("").is_err());
+        assert!(check_name("dir/golf.sav").is_err());
+        assert!(check_name("golf\0.sav").is_err());
+        assert_eq!(
+            check_name("golf"),
+            Err(String::from("invalid record name \"golf\""))
+        );
+    }
+
+    /// The storage emulated on the host, in memory or in a directory.
+    #[cfg(feature = "host")]
+    mod emulated {
+        extern crate std;
+
+        use alloc::vec;
+        use std::{env, fs, path::PathBuf, process};
+
+        use super::*;
+        use crate::host::{self, STORAGE_SIZE};
+
+        /// The bytes a record takes: its size, its name and a null byte, and its content.
+        fn record_size(name: &str, len: usize) -> usize {
+            2 + name.len() + 1 + len
+        }
+
+        /// An empty directory of its own for the test `name`.
+        fn temp_dir(name: &str) -> PathBuf {
+            let dir = env::temp_dir().join(format!("libnw-storage-{}-{name}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            dir
+        }
+
+        /// Runs `test` on an empty storage in memory, then on one in a directory.
+        fn on_both_backends(name: &str, test: impl Fn()) {
+            host::reset();
+            test();
+            host::reset();
+            let dir = temp_dir(name);
+            host::set_storage_dir(&dir).unwrap();
+            test();
+            host::reset();
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn records_are_written_read_and_deleted() {
+            on_both_backends("records", || {
+                assert!(!exists("golf.sav"));
+                assert_eq!(read("golf.sav"), None);
+                write("golf.sav", &[1, 2, 3]).unwrap();
+                assert!(exists("golf.sav"));
+                assert_eq!(read("golf.sav"), Some(vec![1, 2, 3]));
+                assert!(delete("golf.sav"));
+                assert!(!delete("golf.sav"));
+                assert!(!exists("golf.sav"));
+            });
+        }
+
+        #[test]
+        fn invalid_names_are_refused() {
+            on_both_backends("invalid", || {
+                assert!(write("golf", &[1]).is_err());
+                assert!(write("../golf.sav", &[1]).is_err());
+                assert_eq!(read("golf"), None);
+                assert!(!delete("golf"));
+                assert_eq!(used(), 0);
+            });
+        }
+
+        #[test]
+        fn free_space_counts_names_and_headers() {
+            on_both_backends("space", || {
+                assert_eq!(size(), STORAGE_SIZE);
+                assert_eq!(free_space(), STORAGE_SIZE);
+                write("golf.sav", &[0; 100]).unwrap();
+                write("a.py", b"print(1)").unwrap();
+                let expected = record_size("golf.sav", 100) + record_size("a.py", 8);
+                assert_eq!(used(), expected);
+                assert_eq!(free_space(), STORAGE_SIZE - expected);
+                delete("golf.sav");
+                assert_eq!(used(), record_size("a.py", 8));
+            });
+        }
+
+        #[test]
+        fn list_keeps_the_extension_asked() {
+            on_both_backends("list", || {
+                for name in ["b.sav", "a.sav", "c.py", "d.sav.bak", "sav.txt"] {
+                    write(name, &[]).unwrap();
+                }
+                assert_eq!(list("sav"), ["a.sav", "b.sav"]);
+                assert_eq!(list("bak"), ["d.sav.bak"]);
+                assert!(list("lua").is_empty());
+            });
+        }
+
+        #[test]
+        fn an_overwrite_can_shrink_a_record() {
+            on_both_backends("shrink", || {
+                write("golf.sav", &[7; 1000]).unwrap();
+                write("golf.sav", &[8; 10]).unwrap();
+                assert_eq!(read("golf.sav"), Some(vec![8; 10]));
+                assert_eq!(used(), record_size("golf.sav", 10));
+            });
+        }
+
+        #[test]
+        fn a_full_store_refuses_more() {
+            on_both_backends("full", || {
+                let fits = STORAGE_SIZE - record_size("big.sav", 0);
+                assert!(write("big.sav", &vec![0; fits + 1]).is_err());
+                write("big.sav", &vec![1; fits]).unwrap();
+                assert_eq!(free_space(), 0);
+                assert!(write("one.sav", &[]).is_err());
+                // replacing a record only needs the difference
+                assert!(write("big.sav", &vec![2; fits + 1]).is_err());
+                assert_eq!(read("big.sav").map(|data| data[0]), Some(1));
+                write("big.sav", &vec![3; fits - record_size("one.sav", 0)]).unwrap();
+                write("one.sav", &[]).unwrap();
+                assert_eq!(free_space(), 0);
+                assert!(exists("one.sav"));
+            });
+        }
+
+        #[test]
+        fn the_directory_keeps_one_file_per_record() {
+            host::reset();
+            let dir = temp_dir("files");
+            host::set_storage_dir(&dir).unwrap();
+            write("golf.sav", &[4, 2]).unwrap();
+            assert_eq!(fs::read(dir.join("golf.sav")).unwrap(), [4, 2]);
+            // files that can't be records are left alone
+            fs::write(dir.join("notes"), [0; 50]).unwrap();
+            fs::write(dir.join("moves.sav"), [9]).unwrap();
+            assert_eq!(list("sav"), ["golf.sav", "moves.sav"]);
+            assert_eq!(read("moves.sav"), Some(vec![9]));
+            assert_eq!(
+                used(),
+                record_size("golf.sav", 2) + record_size("moves.sav", 1)
+            );
+            // the records stay on the next launch
+            host::reset();
+            host::set_storage_dir(&dir).unwrap();
+            assert_eq!(read("golf.sav"), Some(vec![4, 2]));
+            host::reset();
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
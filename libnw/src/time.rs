@@ -1,10 +1,529 @@
 //! Day 13, you are now able to control the time.
 
+pub use core::time::Duration;
 pub use eadk::*;
 
+use core::ops::{Add, Sub};
+
+/// A point in time, with the millisecond precision of [`monotonic`].
+///
+/// Unlike `std::time::Instant`, the inner value can be read, to be saved for example.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// The current time.
+    pub fn now() -> Self {
+        Self(monotonic())
+    }
+
+    /// Creates an instant from a number of milliseconds, as returned by [`monotonic`].
+    pub fn from_millis(ms: u64) -> Self {
+        Self(ms)
+    }
+
+    /// The number of milliseconds since the start, as returned by [`monotonic`].
+    pub fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    /// The time elapsed since `earlier`, zero if `earlier` is later.
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+
+    /// The time elapsed since this instant.
+    pub fn elapsed(self) -> Duration {
+        Self::now().duration_since(self)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant(self.0 + rhs.as_millis() as u64)
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: Duration) -> Instant {
+        Instant(self.0.saturating_sub(rhs.as_millis() as u64))
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Measures time, and can be paused.
+///
+/// # Example :
+/// ```
+/// use libnw::time::{self, Stopwatch};
+///
+/// let mut chrono = Stopwatch::started();
+/// // ... the player finishes the hole
+/// time::msleep(20);
+/// chrono.stop();
+/// let time = chrono.elapsed();
+/// ```
+///
+/// It uses the clock of the calculator, unless given the time with the `_at` methods,
+/// such as [`Stopwatch::starting_at`] and [`Stopwatch::elapsed_at`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stopwatch {
+    /// The time measured before the last start.
+    accumulated: Duration,
+    /// When it was started, if running.
+    started_at: Option<Instant>,
+}
+
+impl Stopwatch {
+    /// Creates a stopped stopwatch, at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a running stopwatch.
+    pub fn started() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Creates a stopwatch running since `now`.
+    pub fn starting_at(now: Instant) -> Self {
+        let mut stopwatch = Self::new();
+        stopwatch.start_at(now);
+        stopwatch
+    }
+
+    /// Starts (or resumes) measuring time, does nothing if already running.
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    /// Starts (or resumes) measuring time from `now`, does nothing if already running.
+    pub fn start_at(&mut self, now: Instant) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    /// Pauses, keeping the time measured so far.
+    pub fn stop(&mut self) {
+        self.stop_at(Instant::now());
+    }
+
+    /// Pauses at `now`, keeping the time measured so far.
+    pub fn stop_at(&mut self, now: Instant) {
+        if let Some(started_at) = self.started_at.take() {
+            self.accumulated += now.duration_since(started_at);
+        }
+    }
+
+    /// Goes back to zero, keeping it running or not.
+    pub fn reset(&mut self) {
+        self.reset_at(Instant::now());
+    }
+
+    /// Goes back to zero at `now`, keeping it running or not.
+    pub fn reset_at(&mut self, now: Instant) {
+        self.accumulated = Duration::ZERO;
+        if self.started_at.is_some() {
+            self.started_at = Some(now);
+        }
+    }
+
+    /// Goes back to zero and starts running.
+    pub fn restart(&mut self) {
+        *self = Self::started();
+    }
+
+    /// Checks if it is measuring time.
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// The time measured.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_at(Instant::now())
+    }
+
+    /// The time measured, seen at `now`.
+    pub fn elapsed_at(&self, now: Instant) -> Duration {
+        self.accumulated
+            + self
+                .started_at
+                .map_or(Duration::ZERO, |started_at| now.duration_since(started_at))
+    }
+}
+
+/// Fires once or repeatedly after a duration, checked by polling.
+///
+/// # Example :
+/// ```
+/// use libnw::time::{Duration, Timer};
+///
+/// let mut blink = Timer::repeating(Duration::from_millis(500));
+/// let mut visible = true;
+/// // each frame
+/// if blink.fired() % 2 == 1 {
+///     visible = !visible;
+/// }
+/// ```
+///
+/// It uses the clock of the calculator, unless given the time with the `_at` methods,
+/// such as [`Timer::repeating_at`] and [`Timer::fired_at`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timer {
+    start: Instant,
+    period: Duration,
+    repeating: bool,
+    /// The number of times it fired and was reported by `fired`.
+    reported: u64,
+}
+
+impl Timer {
+    /// A timer firing once, after `duration`.
+    pub fn once(duration: Duration) -> Self {
+        Self::once_at(duration, Instant::now())
+    }
+
+    /// A timer firing once, `duration` after `now`.
+    pub fn once_at(duration: Duration, now: Instant) -> Self {
+        Self::new(duration, false, now)
+    }
+
+    /// A timer firing every `period`.
+    pub fn repeating(period: Duration) -> Self {
+        Self::repeating_at(period, Instant::now())
+    }
+
+    /// A timer firing every `period`, starting from `now`.
+    pub fn repeating_at(period: Duration, now: Instant) -> Self {
+        Self::new(period, true, now)
+    }
+
+    fn new(period: Duration, repeating: bool, start: Instant) -> Self {
+        Self {
+            start,
+            period,
+            repeating,
+            reported: 0,
+        }
+    }
+
+    /// The number of times it fired between the start and `now`.
+    fn fire_count(&self, now: Instant) -> u64 {
+        let elapsed = now.duration_since(self.start).as_millis() as u64;
+        let period = (self.period.as_millis() as u64).max(1);
+        if self.repeating {
+            elapsed / period
+        } else {
+            (elapsed >= period) as u64
+        }
+    }
+
+    /// The number of times it fired since the last call (0 or 1 for a one-shot timer).
+    ///
+    /// It may fire several times at once if the calls are too far apart.
+    pub fn fired(&mut self) -> u64 {
+        self.fired_at(Instant::now())
+    }
+
+    /// The number of times it fired between the last call and `now`.
+    ///
+    /// Nothing fires if `now` is before the last call.
+    pub fn fired_at(&mut self, now: Instant) -> u64 {
+        let count = self.fire_count(now);
+        let new = count.saturating_sub(self.reported);
+        self.reported = self.reported.max(count);
+        new
+    }
+
+    /// Checks if a one-shot timer fired (a repeating one never finishes).
+    pub fn is_finished(&self) -> bool {
+        self.is_finished_at(Instant::now())
+    }
+
+    /// Checks if a one-shot timer fired by `now`.
+    pub fn is_finished_at(&self, now: Instant) -> bool {
+        !self.repeating && self.fire_count(now) > 0
+    }
+
+    /// The time before it fires (again).
+    pub fn remaining(&self) -> Duration {
+        self.remaining_at(Instant::now())
+    }
+
+    /// The time from `now` before it fires (again).
+    pub fn remaining_at(&self, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.start);
+        if self.repeating {
+            let period = (self.period.as_millis() as u64).max(1);
+            Duration::from_millis(period - elapsed.as_millis() as u64 % period)
+        } else {
+            self.period.saturating_sub(elapsed)
+        }
+    }
+
+    /// How far it is in the current period, from 0 to 1, handy for animations.
+    pub fn progress(&self) -> f32 {
+        self.progress_at(Instant::now())
+    }
+
+    /// How far it is in the current period at `now`, from 0 to 1.
+    pub fn progress_at(&self, now: Instant) -> f32 {
+        let period = (self.period.as_millis() as u64).max(1);
+        let elapsed = now.duration_since(self.start).as_millis() as u64;
+        if self.repeating {
+            (elapsed % period) as f32 / period as f32
+        } else {
+            elapsed.min(period) as f32 / period as f32
+        }
+    }
+
+    /// Starts again from now.
+    pub fn reset(&mut self) {
+        self.reset_at(Instant::now());
+    }
+
+    /// Starts again from `now`.
+    pub fn reset_at(&mut self, now: Instant) {
+        self.start = now;
+        self.reported = 0;
+    }
+}
+
+/// Where the time comes from, to run the same code against the real clock or a virtual one.
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> Instant;
+    /// Waits for `duration`.
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// The clock of the calculator, with [`monotonic`] and [`msleep`].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct EadkClock;
+
+impl Clock for EadkClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        msleep(duration.as_millis() as u32);
+    }
+}
+
+/// A virtual clock, which only moves forward when asked to.
+///
+/// Sleeping returns at once and moves the clock forward, so that a replay or a test
+/// runs as fast as possible while seeing the time it would have taken.
+///
+/// # Example :
+/// ```
+/// use libnw::time::{Clock, Duration, ManualClock};
+///
+/// let mut clock = ManualClock::new();
+/// clock.sleep(Duration::from_millis(16));
+/// assert_eq!(clock.now().as_millis(), 16);
+/// ```
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ManualClock {
+    now: Instant,
+}
+
+impl ManualClock {
+    /// Creates a clock starting at 0.
+    pub fn new() -> Self {
+        Self::starting_at(Instant::from_millis(0))
+    }
+
+    /// Creates a clock starting at `now`.
+    pub fn starting_at(now: Instant) -> Self {
+        Self { now }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&mut self, duration: Duration) {
+        self.now = self.now + duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// Paces frames and measures their duration.
+///
+/// # Example :
+/// ```no_run
+/// use libnw::time::{Duration, FrameClock};
+///
+/// fn update(_dt: Duration) {}
+/// fn render() {}
+///
+/// let mut clock = FrameClock::new(60);
+/// loop {
+///     update(clock.delta());
+///     render();
+///     clock.wait_next_frame();
+/// }
+/// ```
+///
+/// It uses the clock of the calculator, unless told otherwise with
+/// [`FrameClock::starting_at`], [`FrameClock::tick_at`] and [`FrameClock::wait_next_frame_with`].
+#[derive(Clone, Debug)]
+pub struct FrameClock {
+    /// The wanted duration of a frame, `None` to run as fast as possible.
+    target: Option<Duration>,
+    last_tick: Instant,
+    delta: Duration,
+    frame: u64,
+    /// The duration of the last frames in ms, in a ring buffer.
+    history: [u32; Self::HISTORY],
+    spikes: u64,
+}
+
+impl FrameClock {
+    /// The number of frames the average is computed on.
+    pub const HISTORY: usize = 32;
+
+    /// Creates a clock aiming at `fps` frames per second, 0 not to limit the frame rate.
+    pub fn new(fps: u64) -> Self {
+        Self::starting_at(fps, Instant::now())
+    }
+
+    /// Creates a clock aiming at `fps` frames per second, its first frame starting at `now`.
+    pub fn starting_at(fps: u64, now: Instant) -> Self {
+        Self {
+            target: (fps != 0).then(|| Duration::from_millis(1000 / fps)),
+            last_tick: now,
+            delta: Duration::ZERO,
+            frame: 0,
+            history: [0; Self::HISTORY],
+            spikes: 0,
+        }
+    }
+
+    /// Ends the current frame now, without waiting.
+    ///
+    /// Returns the duration of the frame.
+    pub fn tick(&mut self) -> Duration {
+        self.tick_at(Instant::now())
+    }
+
+    /// Ends the current frame at `now`.
+    ///
+    /// Returns the duration of the frame.
+    pub fn tick_at(&mut self, now: Instant) -> Duration {
+        self.delta = now.duration_since(self.last_tick);
+        self.last_tick = now;
+        self.history[(self.frame % Self::HISTORY as u64) as usize] =
+            self.delta.as_millis().min(u32::MAX as u128) as u32;
+        self.frame += 1;
+        if self.is_spike() {
+            self.spikes += 1;
+        }
+        self.delta
+    }
+
+    /// Sleeps until the frame lasted the target duration, then ends it.
+    ///
+    /// Returns the duration of the frame.
+    pub fn wait_next_frame(&mut self) -> Duration {
+        self.wait_next_frame_with(&mut EadkClock)
+    }
+
+    /// Sleeps with `clock` until the frame lasted the target duration, then ends it.
+    ///
+    /// Returns the duration of the frame.
+    pub fn wait_next_frame_with(&mut self, clock: &mut impl Clock) -> Duration {
+        if let Some(target) = self.target {
+            let elapsed = clock.now().duration_since(self.last_tick);
+            if elapsed < target {
+                clock.sleep(target - elapsed);
+            }
+        }
+        self.tick_at(clock.now())
+    }
+
+    /// The duration of the last frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// The duration of the last frame in seconds.
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// The number of frames ended so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The wanted duration of a frame, if the frame rate is limited.
+    pub fn target(&self) -> Option<Duration> {
+        self.target
+    }
+
+    /// The average duration of the last frames.
+    pub fn average_frame_time(&self) -> Duration {
+        let count = self.frame.min(Self::HISTORY as u64);
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        let total: u64 = self.history[..count as usize]
+            .iter()
+            .map(|&ms| ms as u64)
+            .sum();
+        Duration::from_micros(total * 1000 / count)
+    }
+
+    /// The average number of frames per second, over the last frames.
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time().as_secs_f32();
+        if average == 0. { 0. } else { 1. / average }
+    }
+
+    /// The longest of the last frames.
+    pub fn max_frame_time(&self) -> Duration {
+        let count = self.frame.min(Self::HISTORY as u64) as usize;
+        Duration::from_millis(self.history[..count].iter().copied().max().unwrap_or(0) as u64)
+    }
+
+    /// Checks if the last frame took more than twice the target duration
+    /// (or twice the average if the frame rate is not limited).
+    pub fn is_spike(&self) -> bool {
+        let reference = self.target.unwrap_or_else(|| self.average_frame_time());
+        self.frame > 1 && !reference.is_zero() && self.delta > reference * 2
+    }
+
+    /// The number of spikes since the start.
+    pub fn spike_count(&self) -> u64 {
+        self.spikes
+    }
+}
+
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// Blocks the thread for a given amount of milliseconds.
@@ -35,3 +554,173 @@ pub mod eadk {
         pub safe fn monotonic() -> u64;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::time as eadk;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: u64) -> Instant {
+        Instant::from_millis(ms)
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn stopwatch_measures_while_running() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.elapsed_at(at(100)), Duration::ZERO);
+        stopwatch.start_at(at(100));
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_at(at(250)), ms(150));
+        // starting again keeps the first start
+        stopwatch.start_at(at(200));
+        stopwatch.stop_at(at(300));
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_at(at(1000)), ms(200));
+        // stopping again changes nothing
+        stopwatch.stop_at(at(400));
+        assert_eq!(stopwatch.elapsed_at(at(1000)), ms(200));
+        stopwatch.start_at(at(500));
+        assert_eq!(stopwatch.elapsed_at(at(550)), ms(250));
+    }
+
+    #[test]
+    fn stopwatch_resets_keeping_its_state() {
+        let mut stopwatch = Stopwatch::starting_at(at(0));
+        stopwatch.reset_at(at(100));
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_at(at(130)), ms(30));
+        stopwatch.stop_at(at(130));
+        stopwatch.reset_at(at(200));
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_at(at(300)), Duration::ZERO);
+    }
+
+    #[test]
+    fn stopwatch_ignores_a_clock_going_back() {
+        let mut stopwatch = Stopwatch::starting_at(at(100));
+        assert_eq!(stopwatch.elapsed_at(at(50)), Duration::ZERO);
+        stopwatch.stop_at(at(50));
+        assert_eq!(stopwatch.elapsed_at(at(200)), Duration::ZERO);
+    }
+
+    #[test]
+    fn one_shot_timer_fires_once() {
+        let mut timer = Timer::once_at(ms(100), at(1000));
+        assert_eq!(timer.fired_at(at(1099)), 0);
+        assert!(!timer.is_finished_at(at(1099)));
+        assert_eq!(timer.remaining_at(at(1040)), ms(60));
+        assert_eq!(timer.progress_at(at(1025)), 0.25);
+        assert_eq!(timer.fired_at(at(1100)), 1);
+        assert!(timer.is_finished_at(at(1100)));
+        assert_eq!(timer.fired_at(at(5000)), 0);
+        assert_eq!(timer.remaining_at(at(5000)), Duration::ZERO);
+        assert_eq!(timer.progress_at(at(5000)), 1.);
+    }
+
+    #[test]
+    fn repeating_timer_reports_every_period() {
+        let mut timer = Timer::repeating_at(ms(100), at(0));
+        assert_eq!(timer.fired_at(at(50)), 0);
+        assert_eq!(timer.fired_at(at(100)), 1);
+        assert_eq!(timer.fired_at(at(199)), 0);
+        // calls too far apart
+        assert_eq!(timer.fired_at(at(520)), 4);
+        assert!(!timer.is_finished_at(at(10_000)));
+        assert_eq!(timer.remaining_at(at(520)), ms(80));
+        assert_eq!(timer.progress_at(at(550)), 0.5);
+    }
+
+    #[test]
+    fn timer_ignores_a_clock_going_back() {
+        let mut timer = Timer::repeating_at(ms(100), at(1000));
+        assert_eq!(timer.fired_at(at(1300)), 3);
+        assert_eq!(timer.fired_at(at(1100)), 0);
+        assert_eq!(timer.fired_at(at(500)), 0);
+        // nothing is reported twice once the clock catches up
+        assert_eq!(timer.fired_at(at(1300)), 0);
+        assert_eq!(timer.fired_at(at(1400)), 1);
+    }
+
+    #[test]
+    fn timer_restarts_on_reset() {
+        let mut timer = Timer::once_at(ms(100), at(0));
+        assert_eq!(timer.fired_at(at(150)), 1);
+        timer.reset_at(at(200));
+        assert!(!timer.is_finished_at(at(250)));
+        assert_eq!(timer.fired_at(at(300)), 1);
+    }
+
+    #[test]
+    fn zero_period_timer_does_not_divide_by_zero() {
+        let mut timer = Timer::repeating_at(Duration::ZERO, at(0));
+        assert_eq!(timer.fired_at(at(3)), 3);
+        assert_eq!(timer.remaining_at(at(3)), ms(1));
+        assert!(Timer::once_at(Duration::ZERO, at(0)).is_finished_at(at(1)));
+    }
+
+    #[test]
+    fn frame_clock_waits_for_the_target() {
+        let mut clock = ManualClock::new();
+        let mut frames = FrameClock::starting_at(50, clock.now());
+        assert_eq!(frames.target(), Some(ms(20)));
+        clock.advance(ms(5));
+        assert_eq!(frames.wait_next_frame_with(&mut clock), ms(20));
+        assert_eq!(clock.now(), at(20));
+        // a slow frame is not waited for
+        clock.advance(ms(30));
+        assert_eq!(frames.wait_next_frame_with(&mut clock), ms(30));
+        assert_eq!(clock.now(), at(50));
+        assert_eq!(frames.frame(), 2);
+        assert_eq!(frames.delta(), ms(30));
+        assert_eq!(frames.average_frame_time(), ms(25));
+        assert_eq!(frames.max_frame_time(), ms(30));
+        assert_eq!(frames.fps(), 40.);
+    }
+
+    #[test]
+    fn unlimited_frame_clock_does_not_wait() {
+        let mut clock = ManualClock::new();
+        let mut frames = FrameClock::starting_at(0, clock.now());
+        assert_eq!(frames.target(), None);
+        assert_eq!(frames.wait_next_frame_with(&mut clock), Duration::ZERO);
+        assert_eq!(frames.fps(), 0.);
+        assert_eq!(frames.average_frame_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn frame_clock_counts_spikes() {
+        let mut frames = FrameClock::starting_at(100, at(0));
+        let mut now = 0;
+        for delta in [10, 10, 25, 10, 20, 21] {
+            now += delta;
+            frames.tick_at(at(now));
+        }
+        // more than twice the 10 ms target
+        assert_eq!(frames.spike_count(), 2);
+        assert!(frames.is_spike());
+    }
+
+    #[test]
+    fn frame_clock_averages_the_last_frames() {
+        let mut frames = FrameClock::starting_at(0, at(0));
+        let mut now = 0;
+        for _ in 0..FrameClock::HISTORY {
+            now += 100;
+            frames.tick_at(at(now));
+        }
+        for _ in 0..FrameClock::HISTORY {
+            now += 10;
+            frames.tick_at(at(now));
+        }
+        assert_eq!(frames.average_frame_time(), ms(10));
+        assert_eq!(frames.max_frame_time(), ms(10));
+        // the clock going back gives an empty frame
+        assert_eq!(frames.tick_at(at(0)), Duration::ZERO);
+    }
+}
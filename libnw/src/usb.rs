@@ -7,6 +7,7 @@ pub use eadk::is_plugged;
 /// Interface with the raw `eadk` C api.
 ///
 /// If you don't know what you are doing, use the safe rust implementations.
+#[cfg(not(feature = "host"))]
 pub mod eadk {
     unsafe extern "C" {
         /// Checks if the USB is plugged.
@@ -18,3 +19,6 @@ pub mod eadk {
         pub safe fn is_plugged() -> bool;
     }
 }
+
+#[cfg(feature = "host")]
+pub use crate::host::usb as eadk;
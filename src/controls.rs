@@ -0,0 +1,88 @@
+//! the actions of the game, and the keys they are bound to
+
+extern crate alloc;
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use graphmgr::ui::Event;
+use libnw::{
+    input::{Action, InputMap},
+    keyboard::{KeyboardTimedState, RawKey},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Confirm,
+    Cancel,
+    AimLeft,
+    AimRight,
+    AimUp,
+    AimDown,
+    ZoomIn,
+    ZoomOut,
+}
+
+/// every control, in the order of their ids
+const ALL: [Control; 8] = [
+    Control::Confirm,
+    Control::Cancel,
+    Control::AimLeft,
+    Control::AimRight,
+    Control::AimUp,
+    Control::AimDown,
+    Control::ZoomIn,
+    Control::ZoomOut,
+];
+
+impl Action for Control {
+    fn id(self) -> u8 {
+        self as u8
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        ALL.get(id as usize).copied()
+    }
+}
+
+/// the bindings, shared by every state so they can be remapped at runtime
+pub type Controls = Rc<RefCell<InputMap<Control>>>;
+
+/// the default bindings
+pub fn default_controls() -> Controls {
+    Rc::new(RefCell::new(
+        InputMap::new()
+            .with(Control::Confirm, RawKey::Ok)
+            .with(Control::Confirm, RawKey::Exe)
+            .with(Control::Cancel, RawKey::Back)
+            .with(Control::AimLeft, RawKey::Left)
+            .with(Control::AimRight, RawKey::Right)
+            .with(Control::AimUp, RawKey::Up)
+            .with(Control::AimDown, RawKey::Down)
+            .with(Control::ZoomIn, RawKey::Plus)
+            .with(Control::ZoomOut, RawKey::Minus),
+    ))
+}
+
+/// the menu event of the controls pressed, so the menus follow the bindings
+pub fn ui_event(controls: &Controls, keyboard_state: &KeyboardTimedState) -> Option<Event> {
+    let controls = controls.borrow();
+    let repeated = [
+        (Control::AimUp, Event::Up),
+        (Control::AimDown, Event::Down),
+        (Control::AimLeft, Event::Left),
+        (Control::AimRight, Event::Right),
+    ];
+    let just_pressed = [
+        (Control::Confirm, Event::Ok),
+        (Control::Cancel, Event::Back),
+    ];
+    repeated
+        .into_iter()
+        .find(|(control, _)| controls.is_repeated(keyboard_state, *control))
+        .or_else(|| {
+            just_pressed
+                .into_iter()
+                .find(|(control, _)| controls.is_just_pressed(keyboard_state, *control))
+        })
+        .map(|(_, event)| event)
+}
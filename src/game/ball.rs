@@ -1,8 +1,10 @@
 use crate::utils::vec::Vec2;
-use libnw::display::{self, Color, Rect, SCREEN_WIDTH};
+use libnw::display::{Color, Painter, Rect};
 
 const BALL_LAUNCH_SPEED: f32 = -1.2;
 const CLUB_DISTANCE: f32 = 60.;
+// opacity of the shadow of the ball
+const SHADOW_ALPHA: u8 = 96;
 
 pub struct Ball {
     pub pos: Vec2<f32>,
@@ -45,33 +47,79 @@ impl Ball {
         }
     }
 
-    pub fn render(&self, offset: &Vec2<f32>, unit_size: i32, render_launch: bool) {
-        // How to render a circle, 2 methods
-        // 1 - Pull the rect (AABB) where it will be drawn
-        // then re-color some part and draw it again
-        // 2 - draw pixel by pixel (can be optimized by
-        // drawing rectangles)
+    /// screen space position of the center of the ball
+    fn screen_pos(&self, offset: &Vec2<f32>, unit_size: i32) -> Vec2<i32> {
+        (&(&self.pos * unit_size as f32) - offset).to_int()
+    }
+
+    /// radius of the ball on screen, in pixels
+    fn screen_radius(unit_size: i32) -> u16 {
+        (unit_size / 2) as u16
+    }
 
-        // using method 2 bc easyer & faster to implement
-        // ball radius : 1 unit
-        let ball_pos = &(&(&self.pos * unit_size as f32) - offset).to_int()
-            - &Vec2 {
-                x: unit_size / 2,
-                y: unit_size / 2,
-            };
-        for x in (ball_pos.x).max(0)..(ball_pos.x + unit_size).min(SCREEN_WIDTH as i32) {
-            for y in (ball_pos.y).max(0)..(ball_pos.y + unit_size).min(SCREEN_WIDTH as i32) {
-                display::set_pixel(x as u16, y as u16, Color::WHITE);
-            }
+    /// offset of the shadow from the ball, down-right, in pixels
+    fn shadow_offset(unit_size: i32) -> i32 {
+        Self::screen_radius(unit_size) as i32 / 2
+    }
+
+    /// screen space position of the club, if there is one
+    fn club_pos(&self, offset: &Vec2<f32>, unit_size: i32) -> Option<Vec2<i32>> {
+        if self.launch_vec.norm_sqd() > 0.01 {
+            Some(
+                (&(&(&self.pos * unit_size as f32) - offset) + &(&self.launch_vec * CLUB_DISTANCE))
+                    .to_int(),
+            )
+        } else {
+            None
         }
-        if render_launch && self.launch_vec.norm_sqd() > 0.01 {
-            let club_pos = (&(&(&self.pos * unit_size as f32) - offset)
-                + &(&self.launch_vec * CLUB_DISTANCE))
-                .to_int();
-            display::eadk::push_rect_uniform(
-                Rect::screen_space_clipping(club_pos.x - 1, club_pos.y - 1, 3, 3),
-                Color::BLUE,
+    }
+
+    /// screen space bounds of the ball and its shadow (may be empty if off-screen)
+    pub fn bounds(&self, offset: &Vec2<f32>, unit_size: i32) -> Rect {
+        let center = self.screen_pos(offset, unit_size);
+        let radius = Self::screen_radius(unit_size);
+        let shadow = Self::shadow_offset(unit_size) as u16;
+        Rect::screen_space_clipping(
+            center.x - radius as i32,
+            center.y - radius as i32,
+            2 * radius + 1 + shadow,
+            2 * radius + 1 + shadow,
+        )
+    }
+
+    /// screen space bounds of the aim line and club indicator, if there is one
+    pub fn club_bounds(&self, offset: &Vec2<f32>, unit_size: i32) -> Option<Rect> {
+        let center = self.screen_pos(offset, unit_size);
+        let club = self.club_pos(offset, unit_size)?;
+        // the line goes from the center of the ball to the center of the club
+        let x = center.x.min(club.x - 1);
+        let y = center.y.min(club.y - 1);
+        Some(Rect::screen_space_clipping(
+            x,
+            y,
+            (center.x.max(club.x + 1) - x + 1) as u16,
+            (center.y.max(club.y + 1) - y + 1) as u16,
+        ))
+    }
+
+    /// draw the part of the ball (and aim) inside `clip`
+    pub fn render(&self, offset: &Vec2<f32>, unit_size: i32, render_launch: bool, clip: Rect) {
+        let painter = Painter::new(clip);
+        let center = self.screen_pos(offset, unit_size);
+        let radius = Self::screen_radius(unit_size);
+        let shadow = Self::shadow_offset(unit_size);
+        if shadow > 0 {
+            painter.with_alpha(SHADOW_ALPHA).fill_circle(
+                center.x + shadow,
+                center.y + shadow,
+                radius,
+                Color::BLACK,
             );
         }
+        if render_launch && let Some(club) = self.club_pos(offset, unit_size) {
+            painter.line(center.x, center.y, club.x, club.y, 1, Color::BLUE);
+            painter.fill_rect(club.x - 1, club.y - 1, 3, 3, Color::BLUE);
+        }
+        painter.fill_circle(center.x, center.y, radius, Color::WHITE);
     }
 }
@@ -3,24 +3,51 @@ extern crate alloc;
 use super::{
     ball::Ball,
     module::{BallInteraction, Module},
-    popup::PopupState,
 };
-use crate::{PopMessage, game::game_over::GameOverState, utils::vec::Vec2};
-use alloc::{boxed::Box, format, string::ToString, vec::Vec};
-use graphmgr::{StackAction, State};
-use libnw::{
-    display::{self, Color, SCREEN_HEIGHT, SCREEN_WIDTH},
-    keyboard::RawKey,
+use crate::{
+    PopMessage,
+    controls::{Control, Controls, ui_event},
+    game::game_over::GameOverState,
+    menu::THEME,
+    save::SavedRound,
+    utils::vec::Vec2,
 };
+use alloc::{boxed::Box, format, vec::Vec};
+use graphmgr::{
+    FrameContext, LifecycleEvent, StackAction, State,
+    dialog::{DialogChoice, DialogState},
+    transition::{Direction, Transition},
+};
+use libnw::display::{self, Color, DirtyRegion, Font, Rect, SCREEN_HEIGHT, SCREEN_WIDTH};
 
 pub struct GameState {
     is_moving: bool,
     modules: Vec<Box<dyn Module>>,
+    controls: Controls,
     cam_pos: Vec2<f32>,
+    /// where the ball and the camera were before the last update, to interpolate
+    prev_ball_pos: Vec2<f32>,
+    prev_cam_pos: Vec2<f32>,
     spawn_pos: Vec2<f32>,
     ball: Ball,
     attempts: u8,
     unit_size: u8,
+    /// the parts of the screen to repaint this frame
+    dirty: DirtyRegion,
+    /// what was on screen after the last render, `None` to repaint everything
+    last_frame: Option<FrameBounds>,
+    /// the answer of the dialog asking to leave the course, while it is open
+    leave: Option<DialogChoice<bool>>,
+}
+
+/// where things were drawn, to know what to repaint on the next frame
+struct FrameBounds {
+    cam_offset: (i32, i32),
+    unit_size: u8,
+    ball: Rect,
+    club: Option<Rect>,
+    attempts: u8,
+    score: Rect,
 }
 
 const CAM_OFFSET: Vec2<f32> = Vec2 {
@@ -30,35 +57,72 @@ const CAM_OFFSET: Vec2<f32> = Vec2 {
 const CAM_MOVE_SPEED: f32 = 0.04;
 
 impl GameState {
-    pub fn new(modules: Vec<Box<dyn Module>>, ball: Ball) -> Self {
+    pub fn new(controls: Controls, modules: Vec<Box<dyn Module>>, ball: Ball) -> Self {
+        let cam_pos = &ball.pos - &CAM_OFFSET;
         Self {
             is_moving: false,
             modules,
-            cam_pos: &ball.pos - &CAM_OFFSET,
+            controls,
+            cam_pos,
+            prev_ball_pos: ball.pos,
+            prev_cam_pos: cam_pos,
             spawn_pos: ball.pos.clone(),
             ball,
             attempts: 0,
             unit_size: 3,
+            dirty: DirtyRegion::new(),
+            last_frame: None,
+            leave: None,
         }
     }
+
+    /// goes on with a round where some shots were already played
+    pub fn with_attempts(mut self, attempts: u8) -> Self {
+        self.attempts = attempts;
+        self
+    }
 }
 
 impl State<PopMessage> for GameState {
-    fn resume(&mut self, pop_message: PopMessage) -> StackAction<PopMessage> {
-        if let PopMessage::OkBackPopupIsOk(true) = pop_message {
-            StackAction::Pop(PopMessage::None)
+    fn resume(&mut self, _pop_message: PopMessage) -> StackAction<PopMessage> {
+        // the state on top drew over everything
+        self.last_frame = None;
+        if self.leave.take().and_then(|leave| leave.get()) == Some(true) {
+            StackAction::Pop(PopMessage::None).with_transition(Transition::slide(
+                Direction::Right,
+                Color::GREEN,
+                20,
+            ))
         } else {
             StackAction::Nop
         }
     }
 
+    fn invalidate(&mut self) {
+        self.last_frame = None;
+    }
+
+    fn lifecycle(&mut self, _event: LifecycleEvent) {
+        // the round goes on from here on the next launch, a moving ball stops where it is
+        let round = SavedRound {
+            ball_pos: self.ball.pos,
+            attempts: self.attempts,
+        };
+        // nothing more can be done without space, the app is ending
+        let _ = round.save();
+    }
+
     fn update(
         &mut self,
         keyboard_state: &libnw::keyboard::KeyboardTimedState,
+        _ctx: &FrameContext,
     ) -> StackAction<PopMessage> {
-        // change zoom
-        let delta_zoom = (keyboard_state.is_key_just_pressed(RawKey::Plus) as i8)
-            - (keyboard_state.is_key_just_pressed(RawKey::Minus) as i8);
+        self.prev_ball_pos = self.ball.pos;
+        self.prev_cam_pos = self.cam_pos;
+        let controls = self.controls.borrow();
+        // change zoom, repeatedly while the key is held
+        let delta_zoom = (controls.is_repeated(keyboard_state, Control::ZoomIn) as i8)
+            - (controls.is_repeated(keyboard_state, Control::ZoomOut) as i8);
         self.unit_size = (self.unit_size as i8 + delta_zoom).clamp(1, 8) as u8;
 
         let mut is_outside = true;
@@ -66,7 +130,11 @@ impl State<PopMessage> for GameState {
         'modules_update: for m in self.modules.iter_mut() {
             match m.update(&mut self.ball) {
                 BallInteraction::Win => {
-                    return StackAction::Replace(Box::new(GameOverState(self.attempts)));
+                    return StackAction::Replace(Box::new(GameOverState::new(
+                        self.attempts,
+                        self.controls.clone(),
+                    )))
+                    .with_transition(Transition::iris(Color::BLACK, 30));
                 }
                 BallInteraction::Dead => {
                     // if ball is dead, act as if it was outside
@@ -83,6 +151,8 @@ impl State<PopMessage> for GameState {
         if is_outside {
             self.attempts += 1;
             self.ball.reset(self.spawn_pos);
+            // teleported, don't slide from where it died
+            self.prev_ball_pos = self.ball.pos;
             self.is_moving = false;
         } else {
             // apply mean drag
@@ -109,17 +179,12 @@ impl State<PopMessage> for GameState {
             }
             false => {
                 self.ball.move_launch(Vec2::<f32> {
-                    x: ((keyboard_state.is_key_pressed(RawKey::Right) as i8)
-                        - (keyboard_state.is_key_pressed(RawKey::Left) as i8))
-                        as f32
+                    x: controls.axis(keyboard_state, Control::AimLeft, Control::AimRight) as f32
                         * 0.04,
-                    y: ((keyboard_state.is_key_pressed(RawKey::Down) as i8)
-                        - (keyboard_state.is_key_pressed(RawKey::Up) as i8))
-                        as f32
+                    y: controls.axis(keyboard_state, Control::AimUp, Control::AimDown) as f32
                         * 0.04,
                 });
-                if (keyboard_state.is_key_just_pressed(RawKey::Ok)
-                    || keyboard_state.is_key_just_pressed(RawKey::Exe))
+                if controls.is_just_pressed(keyboard_state, Control::Confirm)
                     && self.ball.launch_vec.norm_sqd() > 0.01
                 {
                     self.attempts += 1;
@@ -129,31 +194,81 @@ impl State<PopMessage> for GameState {
             }
         }
 
-        if keyboard_state.is_key_just_pressed(RawKey::Back) {
-            StackAction::Push(Box::new(PopupState("Leave ???".to_string())))
+        if controls.is_just_pressed(keyboard_state, Control::Cancel) {
+            let controls = self.controls.clone();
+            let dialog = DialogState::new(
+                "Leave ?",
+                "This course will be lost.",
+                &[("Leave", true), ("Stay", false)],
+                PopMessage::None,
+            )
+            .with_theme(THEME)
+            .with_events(move |keyboard_state| ui_event(&controls, keyboard_state));
+            self.leave = Some(dialog.choice());
+            StackAction::Push(Box::new(dialog))
         } else {
             StackAction::Nop
         }
     }
 
-    fn render(&mut self) {
+    fn render(&mut self, alpha: f32) {
+        // draw between the last two updates, the ball position is restored at the end
+        let ball_pos = self.ball.pos;
+        self.ball.pos = &self.prev_ball_pos + &(&(&ball_pos - &self.prev_ball_pos) * alpha);
+        let cam_pos = &self.prev_cam_pos + &(&(&self.cam_pos - &self.prev_cam_pos) * alpha);
+
+        let unit_size = self.unit_size as i32;
+        let cam_offset = cam_pos.to_int();
+        let ball = self.ball.bounds(&cam_pos, unit_size);
+        let club = match self.is_moving {
+            true => None,
+            false => self.ball.club_bounds(&cam_pos, unit_size),
+        };
+        let score_msg = format!("score : {}", self.attempts);
+        let (score_width, score_height) = Font::Small.measure(&score_msg);
+        let score = Rect::new(0, 0, score_width, score_height);
+
+        // only repaint what changed, unless the whole course moved
+        match &self.last_frame {
+            Some(last)
+                if last.cam_offset == (cam_offset.x, cam_offset.y)
+                    && last.unit_size == self.unit_size =>
+            {
+                self.dirty.mark_moved(Some(last.ball), Some(ball));
+                self.dirty.mark_moved(last.club, club);
+                if last.attempts != self.attempts {
+                    self.dirty.mark(last.score);
+                    self.dirty.mark(score);
+                }
+            }
+            _ => self.dirty.mark_all(),
+        }
+
         display::eadk::wait_for_vblank();
-        display::clear_screen(Color::new(0x07E0));
-        // draw modules
-        for m in self.modules.iter() {
-            m.render(&self.cam_pos, self.unit_size as i32);
+        let mut score_dirty = false;
+        for clip in self.dirty.take() {
+            clip.fill(Color::new(0x07E0));
+            // draw modules
+            for m in self.modules.iter() {
+                m.render(&cam_pos, unit_size, clip);
+            }
+            // draw ball
+            self.ball.render(&cam_pos, unit_size, !self.is_moving, clip);
+            score_dirty |= clip.intersects(&score);
         }
-        // draw ball
-        self.ball
-            .render(&self.cam_pos, self.unit_size as i32, !self.is_moving);
-        // draw score
-        display::draw_string(
-            &format!("score : {}", self.attempts),
-            0,
-            0,
-            false,
-            Color::BLACK,
-            Color::GREEN,
-        );
+        // draw score, once and on top of everything, the text can't be clipped
+        if score_dirty {
+            Font::Small.draw(&score_msg, 0, 0, Color::BLACK, Some(Color::GREEN));
+        }
+
+        self.last_frame = Some(FrameBounds {
+            cam_offset: (cam_offset.x, cam_offset.y),
+            unit_size: self.unit_size,
+            ball,
+            club,
+            attempts: self.attempts,
+            score,
+        });
+        self.ball.pos = ball_pos;
     }
 }
@@ -1,37 +1,80 @@
-use alloc::format;
-use graphmgr::{StackAction, State};
+use alloc::{format, string::String};
+use graphmgr::{
+    FrameContext, StackAction, State,
+    transition::Transition,
+    ui::{Button, Label, List, Panel, Ui},
+};
 use libnw::{
-    display::{self, Color, LARGE_CHAR_HEIGHT, LARGE_CHAR_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH},
-    keyboard::{KeyboardTimedState, RawKey},
+    display::{Align, Color},
+    keyboard::KeyboardTimedState,
 };
 
 use crate::PopMessage;
+use crate::controls::{Controls, ui_event};
+use crate::menu::THEME;
+use crate::save;
+
+pub struct GameOverState {
+    pub attempts: u8,
+    /// the course was never done in so few hits
+    pub new_best: bool,
+    controls: Controls,
+    ui: Ui<()>,
+}
 
-pub struct GameOverState(pub u8);
+impl GameOverState {
+    pub fn new(attempts: u8, controls: Controls) -> Self {
+        let best = save::best_score();
+        let new_best = best.is_none_or(|best| attempts < best);
+        let best_msg = match best {
+            Some(best) if !new_best => format!("best : {best} hits"),
+            _ => String::from("new best !"),
+        };
+        let ui = Ui::centered(Panel::new(
+            "Game over",
+            List::new()
+                .with(Label::new(&format!("GG, done in {attempts} hits")).align(Align::Center))
+                .with(Label::new(&best_msg).align(Align::Center))
+                .with(Button::new("Back to menu", ())),
+        ))
+        .with_theme(THEME)
+        .clear_screen(Color::GREEN)
+        .on_back(());
+        GameOverState {
+            attempts,
+            new_best,
+            controls,
+            ui,
+        }
+    }
+}
 
 impl State<PopMessage> for GameOverState {
-    fn update(&mut self, keyboard_state: &KeyboardTimedState) -> StackAction<PopMessage> {
-        if keyboard_state.is_key_just_pressed(RawKey::Back)
-            || keyboard_state.is_key_just_pressed(RawKey::Ok)
-            || keyboard_state.is_key_just_pressed(RawKey::Exe)
-        {
-            StackAction::Pop(PopMessage::None)
-        } else {
-            StackAction::Nop
+    fn create(&mut self) -> StackAction<PopMessage> {
+        if self.new_best {
+            // the score is still shown without space to save it
+            let _ = save::save_best_score(self.attempts);
         }
+        StackAction::Nop
+    }
+
+    fn update(
+        &mut self,
+        keyboard_state: &KeyboardTimedState,
+        _ctx: &FrameContext,
+    ) -> StackAction<PopMessage> {
+        match ui_event(&self.controls, keyboard_state).and_then(|event| self.ui.send(event)) {
+            Some(()) => StackAction::Pop(PopMessage::None)
+                .with_transition(Transition::fade(Color::BLACK, 20)),
+            None => StackAction::Nop,
+        }
+    }
+
+    fn render(&mut self, _alpha: f32) {
+        self.ui.render();
     }
 
-    fn render(&mut self) {
-        display::eadk::wait_for_vblank();
-        display::clear_screen(Color::GREEN);
-        let msg = format!("GG, done in {} hits", self.0);
-        display::draw_string(
-            &msg,
-            (SCREEN_WIDTH - (msg.len() as u16 * LARGE_CHAR_WIDTH)) / 2,
-            (SCREEN_HEIGHT - LARGE_CHAR_HEIGHT) / 2,
-            true,
-            Color::BLACK,
-            Color::GREEN,
-        );
+    fn invalidate(&mut self) {
+        self.ui.invalidate();
     }
 }
@@ -3,4 +3,3 @@ pub mod ball;
 pub mod game;
 pub mod game_over;
 pub mod module;
-pub mod popup;
@@ -1,4 +1,7 @@
-use libnw::display::{self, Color, Rect};
+extern crate alloc;
+
+use alloc::vec::Vec;
+use libnw::display::{Color, Flip, Rect, Sprite, Transparency};
 
 use super::ball::Ball;
 use crate::utils::vec::Vec2;
@@ -16,7 +19,8 @@ pub enum BallInteraction {
 
 pub trait Module {
     fn update(&mut self, ball: &mut Ball) -> BallInteraction;
-    fn render(&self, cam_pos: &Vec2<f32>, unit_size: i32);
+    /// draw the part of the module inside `clip` (screen space)
+    fn render(&self, cam_pos: &Vec2<f32>, unit_size: i32, clip: Rect);
 }
 
 // the size of 1 module tile : the ball have a radius of 1 (diameter of 2)
@@ -52,18 +56,19 @@ impl Module for EmptyModule {
         BallInteraction::In(0.98)
     }
 
-    fn render(&self, offset: &Vec2<f32>, unit_size: i32) {
+    fn render(&self, offset: &Vec2<f32>, unit_size: i32, clip: Rect) {
         for x in 0..self.size.x {
             for y in 0..self.size.y {
-                display::eadk::push_rect_uniform(
-                    Rect::screen_space_clipping(
-                        x * unit_size * TILE_SIZE as i32 - offset.x as i32
-                            + self.pos.x as i32 * unit_size,
-                        y * unit_size * TILE_SIZE as i32 - offset.y as i32
-                            + self.pos.y as i32 * unit_size,
-                        unit_size as u16 * TILE_SIZE,
-                        unit_size as u16 * TILE_SIZE,
-                    ),
+                Rect::screen_space_clipping(
+                    x * unit_size * TILE_SIZE as i32 - offset.x as i32
+                        + self.pos.x as i32 * unit_size,
+                    y * unit_size * TILE_SIZE as i32 - offset.y as i32
+                        + self.pos.y as i32 * unit_size,
+                    unit_size as u16 * TILE_SIZE,
+                    unit_size as u16 * TILE_SIZE,
+                )
+                .fill_clipped(
+                    clip,
                     Color::new(if (x + y) % 2 == 0 { 0x0640 } else { 0x0580 }),
                 );
             }
@@ -71,14 +76,42 @@ impl Module for EmptyModule {
     }
 }
 
+/// the flag planted in the hole, the bottom of the pole being in the hole
+const FLAG: [&str; 14] = [
+    "|##     ", "|####   ", "|###### ", "|#######", "|###### ", "|####   ", "|##     ", "|       ",
+    "|       ", "|       ", "|       ", "|       ", "|       ", "|       ",
+];
+
+fn flag_sprite() -> Sprite<'static> {
+    let pixels: Vec<Color> = FLAG
+        .iter()
+        .flat_map(|row| row.bytes())
+        .map(|c| match c {
+            b'|' => Color::WHITE,
+            b'#' => Color::RED,
+            _ => Color::BLACK,
+        })
+        .collect();
+    Sprite::from_vec(
+        FLAG[0].len() as u16,
+        FLAG.len() as u16,
+        pixels,
+        Transparency::Key(Color::BLACK),
+    )
+}
+
 /// 4 × 4 Module (× TILE_SIZE (4))
 pub struct SquareEndModule {
     pos: Vec2<i32>,
+    flag: Sprite<'static>,
 }
 
 impl SquareEndModule {
     pub fn new_4x4(offset: Vec2<i32>) -> Self {
-        SquareEndModule { pos: offset }
+        SquareEndModule {
+            pos: offset,
+            flag: flag_sprite(),
+        }
     }
 }
 
@@ -103,32 +136,38 @@ impl Module for SquareEndModule {
         }
     }
 
-    fn render(&self, offset: &Vec2<f32>, unit_size: i32) {
+    fn render(&self, offset: &Vec2<f32>, unit_size: i32, clip: Rect) {
         for x in 0..4 {
             for y in 0..4 {
-                display::eadk::push_rect_uniform(
-                    Rect::screen_space_clipping(
-                        x * unit_size * TILE_SIZE as i32 - offset.x as i32
-                            + self.pos.x as i32 * unit_size,
-                        y * unit_size * TILE_SIZE as i32 - offset.y as i32
-                            + self.pos.y as i32 * unit_size,
-                        unit_size as u16 * TILE_SIZE,
-                        unit_size as u16 * TILE_SIZE,
-                    ),
+                Rect::screen_space_clipping(
+                    x * unit_size * TILE_SIZE as i32 - offset.x as i32
+                        + self.pos.x as i32 * unit_size,
+                    y * unit_size * TILE_SIZE as i32 - offset.y as i32
+                        + self.pos.y as i32 * unit_size,
+                    unit_size as u16 * TILE_SIZE,
+                    unit_size as u16 * TILE_SIZE,
+                )
+                .fill_clipped(
+                    clip,
                     Color::new(if (x + y) % 2 == 0 { 0x0640 } else { 0x0580 }),
                 );
             }
         }
-        display::eadk::push_rect_uniform(
-            Rect::screen_space_clipping(
-                2 * unit_size * TILE_SIZE as i32 - offset.x as i32 + self.pos.x as i32 * unit_size
-                    - unit_size,
-                2 * unit_size * TILE_SIZE as i32 - offset.y as i32 + self.pos.y as i32 * unit_size
-                    - unit_size,
-                unit_size as u16 * 2,
-                unit_size as u16 * 2,
-            ),
-            Color::BLACK,
+        // center of the hole
+        let hole_x = 2 * unit_size * TILE_SIZE as i32 - offset.x as i32 + self.pos.x * unit_size;
+        let hole_y = 2 * unit_size * TILE_SIZE as i32 - offset.y as i32 + self.pos.y * unit_size;
+        Rect::screen_space_clipping(
+            hole_x - unit_size,
+            hole_y - unit_size,
+            unit_size as u16 * 2,
+            unit_size as u16 * 2,
+        )
+        .fill_clipped(clip, Color::BLACK);
+        self.flag.draw(
+            hole_x,
+            hole_y - self.flag.height() as i32 + 1,
+            Flip::NONE,
+            clip,
         );
     }
 }
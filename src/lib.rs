@@ -2,8 +2,10 @@
 
 extern crate alloc;
 
+pub mod controls;
 pub mod game;
-mod menu;
+pub mod menu;
+pub mod save;
 pub mod utils;
 
 use alloc::boxed::Box;
@@ -18,13 +20,15 @@ pub enum ExitBehaviour {
 }
 
 pub enum PopMessage {
-    OkBackPopupIsOk(bool),
     None,
 }
 
 /// The core of the application logic
 pub fn main() -> ExitBehaviour {
     let mut state_mgr = StateManager::<PopMessage>::new();
-    state_mgr.run(Box::new(MainMenuState), 60);
+    // quit from the menu, home or on/off all end the run, once every state was told:
+    // back to the apps of the calculator. the eadk can't turn the calculator off,
+    // the system does it once the app is left
+    state_mgr.run(Box::new(MainMenuState::new(save::load_controls())), 60);
     ExitBehaviour::Exit
 }
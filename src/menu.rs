@@ -4,48 +4,121 @@ use crate::game::module::{EmptyModule, SquareEndModule};
 use crate::game::{ball::Ball, game::GameState};
 
 use crate::PopMessage;
+use crate::controls::{Controls, ui_event};
+use crate::save::{self, SavedRound};
 use crate::utils::vec::Vec2;
 use alloc::boxed::Box;
 use alloc::vec;
-use graphmgr::*;
+use graphmgr::{
+    transition::Transition,
+    ui::{Button, List, Panel, Theme, Ui},
+    *,
+};
 use libnw::{
-    display::{self, Color, LARGE_CHAR_HEIGHT, LARGE_CHAR_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH},
-    keyboard::{KeyboardTimedState, RawKey},
+    display::{Color, Font},
+    keyboard::KeyboardTimedState,
 };
 
-pub struct MainMenuState;
+/// the look of the menus
+pub const THEME: Theme = Theme {
+    font: Font::Large,
+    background: Color::GREEN,
+    foreground: Color::BLACK,
+    focus_background: Color::BLACK,
+    focus_foreground: Color::GREEN,
+    accent: Color::BLACK,
+};
 
-impl State<PopMessage> for MainMenuState {
-    fn update(&mut self, keyboard_state: &KeyboardTimedState) -> StackAction<PopMessage> {
-        if keyboard_state.is_key_just_pressed(RawKey::Ok)
-            || keyboard_state.is_key_just_pressed(RawKey::Exe)
-        {
-            StackAction::Push(Box::new(GameState::new(
+#[derive(Clone, Copy)]
+enum MenuAction {
+    Continue,
+    Play,
+    Quit,
+}
+
+pub struct MainMenuState {
+    controls: Controls,
+    ui: Ui<MenuAction>,
+    /// the round left with home or on/off at the last launch, if any
+    saved_round: Option<SavedRound>,
+}
+
+impl MainMenuState {
+    pub fn new(controls: Controls) -> Self {
+        let saved_round = SavedRound::load();
+        MainMenuState {
+            controls,
+            ui: Self::build_ui(saved_round.is_some()),
+            saved_round,
+        }
+    }
+
+    fn build_ui(can_continue: bool) -> Ui<MenuAction> {
+        let mut list = List::new();
+        if can_continue {
+            list.push(Button::new("Continue", MenuAction::Continue));
+        }
+        Ui::centered(Panel::new(
+            "Golf",
+            list.with(Button::new("Play", MenuAction::Play))
+                .with(Button::new("Quit", MenuAction::Quit)),
+        ))
+        .with_theme(THEME)
+        .clear_screen(Color::GREEN)
+        .on_back(MenuAction::Quit)
+    }
+
+    /// starts a round on the course, the ball at `ball_pos`
+    fn start_round(&mut self, ball_pos: Vec2<f32>, attempts: u8) -> StackAction<PopMessage> {
+        // the saved round is played or replaced by a new one
+        if self.saved_round.take().is_some() {
+            SavedRound::delete();
+            self.ui = Self::build_ui(false);
+        }
+        StackAction::Push(Box::new(
+            GameState::new(
+                self.controls.clone(),
                 vec![
                     Box::new(EmptyModule::new_rect(Vec2 { x: -8, y: -8 }, (8, 4))),
                     Box::new(EmptyModule::new_rect(Vec2 { x: 24, y: -8 }, (2, 7))),
                     Box::new(SquareEndModule::new_4x4(Vec2 { x: 20, y: 20 })),
                 ],
-                Ball::new(Vec2 { x: 0., y: 0. }),
-            )))
-        } else if keyboard_state.is_key_just_pressed(RawKey::Back) {
-            StackAction::Pop(PopMessage::None)
-        } else {
-            StackAction::Nop
+                Ball::new(ball_pos),
+            )
+            .with_attempts(attempts),
+        ))
+        .with_transition(Transition::fade(Color::BLACK, 20))
+    }
+}
+
+impl State<PopMessage> for MainMenuState {
+    fn update(
+        &mut self,
+        keyboard_state: &KeyboardTimedState,
+        _ctx: &FrameContext,
+    ) -> StackAction<PopMessage> {
+        let action = ui_event(&self.controls, keyboard_state).and_then(|event| self.ui.send(event));
+        match action {
+            Some(MenuAction::Continue) => match self.saved_round {
+                Some(round) => self.start_round(round.ball_pos, round.attempts),
+                None => StackAction::Nop,
+            },
+            Some(MenuAction::Play) => self.start_round(Vec2 { x: 0., y: 0. }, 0),
+            Some(MenuAction::Quit) => StackAction::Pop(PopMessage::None),
+            None => StackAction::Nop,
         }
     }
 
-    fn render(&mut self) {
-        const MSG: &str = "Press OK to play !";
-        display::eadk::wait_for_vblank();
-        display::clear_screen(Color::GREEN);
-        display::draw_string(
-            MSG,
-            (SCREEN_WIDTH - (MSG.len() as u16 * LARGE_CHAR_WIDTH)) / 2,
-            (SCREEN_HEIGHT - LARGE_CHAR_HEIGHT) / 2,
-            true,
-            Color::BLACK,
-            Color::GREEN,
-        );
+    fn render(&mut self, _alpha: f32) {
+        self.ui.render();
+    }
+
+    fn invalidate(&mut self) {
+        self.ui.invalidate();
+    }
+
+    fn lifecycle(&mut self, _event: LifecycleEvent) {
+        // the bindings may have been remapped, nothing more can be done without space
+        let _ = save::save_controls(&self.controls);
     }
 }
@@ -0,0 +1,76 @@
+//! what is kept between launches, in the storage of the calculator
+
+extern crate alloc;
+
+use alloc::{rc::Rc, string::String};
+use core::cell::RefCell;
+use libnw::{input::InputMap, storage};
+
+use crate::{
+    controls::{Controls, default_controls},
+    utils::vec::Vec2,
+};
+
+/// the bindings of the controls
+const CONTROLS_RECORD: &str = "golf.ctl";
+/// the round left with home or on/off
+const ROUND_RECORD: &str = "golf.run";
+/// the fewest hits the course was done in
+const BEST_RECORD: &str = "golf.best";
+
+/// the saved bindings, or the default ones
+pub fn load_controls() -> Controls {
+    match storage::read(CONTROLS_RECORD).and_then(|bytes| InputMap::from_bytes(&bytes).ok()) {
+        Some(bindings) => Rc::new(RefCell::new(bindings)),
+        None => default_controls(),
+    }
+}
+
+pub fn save_controls(controls: &Controls) -> Result<(), String> {
+    storage::write(CONTROLS_RECORD, &controls.borrow().to_bytes())
+}
+
+/// the fewest hits the course was done in, `None` if it was never done
+pub fn best_score() -> Option<u8> {
+    storage::read(BEST_RECORD)?.first().copied()
+}
+
+pub fn save_best_score(attempts: u8) -> Result<(), String> {
+    storage::write(BEST_RECORD, &[attempts])
+}
+
+/// a round left unfinished, to go on with on the next launch
+#[derive(Clone, Copy)]
+pub struct SavedRound {
+    pub ball_pos: Vec2<f32>,
+    pub attempts: u8,
+}
+
+impl SavedRound {
+    /// the round saved, if any
+    pub fn load() -> Option<Self> {
+        let bytes = storage::read(ROUND_RECORD)?;
+        // the ball position as 2 little endian f32, then the attempts
+        let [x0, x1, x2, x3, y0, y1, y2, y3, attempts] = <[u8; 9]>::try_from(bytes).ok()?;
+        Some(SavedRound {
+            ball_pos: Vec2 {
+                x: f32::from_le_bytes([x0, x1, x2, x3]),
+                y: f32::from_le_bytes([y0, y1, y2, y3]),
+            },
+            attempts,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut bytes = [0; 9];
+        bytes[..4].copy_from_slice(&self.ball_pos.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.ball_pos.y.to_le_bytes());
+        bytes[8] = self.attempts;
+        storage::write(ROUND_RECORD, &bytes)
+    }
+
+    /// forgets the round saved, if any
+    pub fn delete() {
+        storage::delete(ROUND_RECORD);
+    }
+}